@@ -0,0 +1,270 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use ethers::types::H160;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tracing::info;
+
+use crate::health::Heartbeat;
+use crate::hyperliquid::subscriptions::{
+    SubscriptionManager, SubscriptionState, SubscriptionStatus,
+};
+use crate::mutes::Mutes;
+use crate::notify::alerts::Alerter;
+use crate::notify::relay;
+use crate::routes::Routes;
+use crate::stats::{self, Stats};
+use crate::store::FillStore;
+use crate::watchlist::Reloader;
+use crate::{commands, context, metrics, now_ms, permalink};
+
+#[derive(Clone)]
+pub struct ApiState {
+    pub mutes: Arc<Mutex<Mutes>>,
+    pub routes: Arc<Mutex<Routes>>,
+    pub subscriptions: Arc<Mutex<SubscriptionManager>>,
+    pub fill_store: Arc<Mutex<FillStore>>,
+    pub vault_name: String,
+    pub alerter: Alerter,
+    /// Shared secret for `/relay`; relaying is disabled without one.
+    pub relay_token: Option<String>,
+    pub reloader: Reloader,
+    pub heartbeat: Heartbeat,
+    /// How long `/healthz` tolerates no WebSocket messages.
+    pub max_silence: Duration,
+    /// The watched network's explorer, for links to transactions.
+    pub explorer_url: &'static str,
+    /// Serves only the read-only routes, without operational data and with
+    /// addresses shortened.
+    pub public: bool,
+    /// Bearer token every route but `/healthz` and `/relay` requires, when set.
+    pub api_token: Option<String>,
+}
+
+async fn run_command(State(state): State<ApiState>, body: String) -> (StatusCode, String) {
+    match commands::execute(&body, &state).await {
+        Ok(reply) => (StatusCode::OK, reply),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()),
+    }
+}
+
+async fn list_routes(State(state): State<ApiState>) -> Json<BTreeMap<String, String>> {
+    Json(state.routes.lock().await.all().clone())
+}
+
+async fn list_subscriptions(
+    State(state): State<ApiState>,
+) -> Json<BTreeMap<H160, SubscriptionStatus>> {
+    Json(state.subscriptions.lock().await.statuses().clone())
+}
+
+#[derive(Serialize)]
+struct Status {
+    vault: String,
+    addresses: usize,
+    /// Left out of the public API.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    active: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retrying: Option<usize>,
+}
+
+async fn status(State(state): State<ApiState>) -> Json<Status> {
+    let subscriptions = state.subscriptions.lock().await;
+    let statuses = subscriptions.statuses();
+    let active = statuses
+        .values()
+        .filter(|status| status.state == SubscriptionState::Active)
+        .count();
+    let public = state.public;
+    Json(Status {
+        vault: state.vault_name.clone(),
+        addresses: statuses.len(),
+        active: (!public).then_some(active),
+        retrying: (!public).then_some(statuses.len() - active),
+    })
+}
+
+/// `GET /healthz`: 200 while WebSocket messages keep arriving, 503 once none
+/// has for `max_silence`.
+async fn healthz(State(state): State<ApiState>) -> (StatusCode, String) {
+    match state.heartbeat.age() {
+        Some(age) if age <= state.max_silence => (StatusCode::OK, "ok".to_string()),
+        Some(age) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("no WebSocket message for {}s", age.as_secs()),
+        ),
+        None => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "no WebSocket message yet".to_string(),
+        ),
+    }
+}
+
+#[derive(Deserialize)]
+struct StatsQuery {
+    window: Option<String>,
+    address: Option<H160>,
+}
+
+/// `GET /stats?window=7d&address=0x...`; both parameters are optional and the
+/// window defaults to a day.
+async fn get_stats(
+    State(state): State<ApiState>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<Stats>, (StatusCode, String)> {
+    let window = commands::parse_duration(query.window.as_deref().unwrap_or("1d"))
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+    let since = now_ms().saturating_sub(window.as_millis() as u64);
+    let fill_store = state.fill_store.lock().await;
+    Ok(Json(stats::compute(
+        fill_store.fills(),
+        since,
+        query.address,
+    )))
+}
+
+/// Prometheus text exposition of `crate::metrics`.
+async fn get_metrics() -> Result<String, (StatusCode, String)> {
+    metrics::render().map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+}
+
+/// `GET /fill/{id}`, the page notifications link each fill to.
+async fn get_fill(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> Result<Html<String>, (StatusCode, String)> {
+    let fill_store = state.fill_store.lock().await;
+    let fill = fill_store
+        .find(&id)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "unknown fill".to_string()))?;
+    Ok(Html(permalink::render(
+        fill,
+        fill_store.fills(),
+        state.explorer_url,
+        state.public,
+    )))
+}
+
+/// `POST /fills/{id}/note` with the note as the body; an empty body clears it.
+async fn note_fill(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+    body: String,
+) -> (StatusCode, String) {
+    let note = body.trim();
+    let note = (!note.is_empty()).then(|| note.to_string());
+    match state.fill_store.lock().await.annotate(&id, note) {
+        Ok(true) => (StatusCode::OK, "noted".to_string()),
+        Ok(false) => (StatusCode::NOT_FOUND, "unknown fill".to_string()),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+/// Compares `token` with `expected` in constant time, hashing both first so
+/// not even their lengths leak through the timing.
+fn token_matches(token: Option<&str>, expected: &str) -> bool {
+    let Some(token) = token else {
+        return false;
+    };
+    let token = ethers::utils::keccak256(token);
+    let expected = ethers::utils::keccak256(expected);
+    token
+        .iter()
+        .zip(expected.iter())
+        .fold(0, |difference, (a, b)| difference | (a ^ b))
+        == 0
+}
+
+async fn require_token(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    let Some(api_token) = &state.api_token else {
+        return next.run(request).await;
+    };
+    let bearer = request
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if !token_matches(bearer, api_token) {
+        return (StatusCode::UNAUTHORIZED, "invalid token").into_response();
+    }
+    next.run(request).await
+}
+
+/// Accepts the token as a bearer header or, for tools like TradingView that
+/// cannot set headers, as a `token` query parameter.
+async fn relay_alert(
+    State(state): State<ApiState>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    body: String,
+) -> (StatusCode, String) {
+    let Some(relay_token) = &state.relay_token else {
+        return (StatusCode::NOT_FOUND, "relay is disabled".to_string());
+    };
+    let bearer = headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let token = bearer.or(query.get("token").map(String::as_str));
+    if !token_matches(token, relay_token) {
+        return (StatusCode::UNAUTHORIZED, "invalid token".to_string());
+    }
+
+    match relay::relay(&state, &body).await {
+        Ok(true) => (StatusCode::OK, "relayed".to_string()),
+        Ok(false) => (StatusCode::OK, "muted".to_string()),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()),
+    }
+}
+
+pub async fn serve(addr: String, state: ApiState) -> anyhow::Result<()> {
+    let mut app = Router::new()
+        .route("/status", get(status))
+        .route("/stats", get(get_stats))
+        .route("/fill/:id", get(get_fill));
+    if !state.public {
+        app = app
+            .route("/commands", post(run_command))
+            .route("/routes", get(list_routes))
+            .route("/subscriptions", get(list_subscriptions))
+            .route("/metrics", get(get_metrics))
+            .route("/fills/:id/note", post(note_fill));
+    }
+    // Routes added after the layer skip the token: liveness probes carry none
+    // and `/relay` checks its own.
+    app = app
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .route("/healthz", get(healthz));
+    if !state.public {
+        app = app.route("/relay", post(relay_alert));
+    }
+    // Requests are served on tasks of their own; they still belong to the
+    // watcher serving them.
+    let context = context::current();
+    let app =
+        app.with_state(state)
+            .layer(middleware::from_fn(move |request: Request, next: Next| {
+                let context = context.clone();
+                async move {
+                    match context {
+                        Some(context) => context::scope(context, next.run(request)).await,
+                        None => next.run(request).await,
+                    }
+                }
+            }));
+
+    let listener = TcpListener::bind(&addr).await?;
+    info!("Listening on {addr}");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
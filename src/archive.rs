@@ -0,0 +1,114 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use ethers::types::H160;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::hyperliquid::fills::Fill;
+use crate::store::{FillStore, StoredFill};
+
+const BATCH_SIZE: usize = 10_000;
+
+/// One row of a user-provided CSV export.
+#[derive(Deserialize)]
+struct CsvFill {
+    time: u64,
+    user: String,
+    coin: String,
+    side: String,
+    px: f64,
+    sz: f64,
+    #[serde(default)]
+    dir: String,
+    #[serde(default)]
+    closed_pnl: f64,
+    #[serde(default)]
+    fee: f64,
+    #[serde(default)]
+    oid: u64,
+    #[serde(default)]
+    hash: String,
+    #[serde(default)]
+    tid: u64,
+}
+
+fn normalize_side(side: &str) -> anyhow::Result<String> {
+    match side.to_lowercase().as_str() {
+        "b" | "buy" | "bid" => Ok("B".to_string()),
+        "a" | "sell" | "ask" => Ok("A".to_string()),
+        _ => Err(anyhow!("unknown side {side:?}")),
+    }
+}
+
+fn parse_csv(path: &Path) -> anyhow::Result<Vec<StoredFill>> {
+    let mut fills = Vec::new();
+    for row in csv::Reader::from_path(path)?.deserialize() {
+        let row: CsvFill = row?;
+        fills.push(StoredFill {
+            user: H160::from_str(&row.user)?,
+            coin: row.coin,
+            side: normalize_side(&row.side)?,
+            px: row.px,
+            sz: row.sz,
+            time: row.time,
+            dir: row.dir,
+            closed_pnl: row.closed_pnl,
+            fee: row.fee,
+            oid: row.oid,
+            hash: row.hash,
+            tid: row.tid,
+            note: None,
+        });
+    }
+    Ok(fills)
+}
+
+/// Reads an `[user, fill]` pair as found in the node fill archives.
+fn parse_event(event: &Value) -> anyhow::Result<StoredFill> {
+    let (user, fill) = serde_json::from_value::<(H160, Fill)>(event.clone())?;
+    Ok(StoredFill::new(user, &fill))
+}
+
+/// Parses one line of a Hyperliquid fill archive. `node_fills` lines are a
+/// single `[user, fill]` pair; `node_fills_by_block` lines wrap a block's
+/// pairs in `events`.
+fn parse_archive_line(line: &str) -> anyhow::Result<Vec<StoredFill>> {
+    let value: Value = serde_json::from_str(line)?;
+    match value.get("events").and_then(Value::as_array) {
+        Some(events) => events.iter().map(parse_event).collect(),
+        None => Ok(vec![parse_event(&value)?]),
+    }
+}
+
+/// Imports a fill dataset into the store and returns how many fills were
+/// new. Files ending in `.csv` are read as CSV with a header row of `time`,
+/// `user`, `coin`, `side`, `px`, `sz` and optionally `dir`, `closed_pnl`,
+/// `fee`, `oid`, `hash` and `tid`; anything else is read as a decompressed
+/// Hyperliquid fill archive.
+pub fn import(store: &mut FillStore, path: &Path) -> anyhow::Result<usize> {
+    if path.extension().is_some_and(|extension| extension == "csv") {
+        return store.append(&parse_csv(path)?);
+    }
+
+    let mut added = 0;
+    let mut batch = Vec::new();
+    let reader = BufReader::new(File::open(path)?);
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        batch.extend(parse_archive_line(&line).with_context(|| format!("line {}", number + 1))?);
+        if batch.len() >= BATCH_SIZE {
+            added += store.append(&std::mem::take(&mut batch))?;
+            info!("Imported {added} fills so far");
+        }
+    }
+    added += store.append(&batch)?;
+    Ok(added)
+}
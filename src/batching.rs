@@ -0,0 +1,222 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::config::Destination;
+use crate::hyperliquid::events::FillEvent;
+use crate::{format_usd, tenants};
+
+/// How long fills are collected before they are posted as one batch, unless
+/// `BATCH_INTERVAL_MS` says otherwise.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+pub fn interval_from_env() -> anyhow::Result<Duration> {
+    match tenants::var("BATCH_INTERVAL_MS") {
+        Ok(value) => Ok(Duration::from_millis(value.parse()?)),
+        Err(_) => Ok(DEFAULT_INTERVAL),
+    }
+}
+
+/// How a batch lists its fills.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Aggregation {
+    /// One line per fill.
+    #[default]
+    Fill,
+    /// One line per address, coin and side, as `MergedFill`.
+    Merge,
+}
+
+impl Aggregation {
+    /// From `BATCH_AGGREGATION`, `fill` or `merge` (default `fill`).
+    pub fn from_env() -> anyhow::Result<Self> {
+        match tenants::var("BATCH_AGGREGATION") {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(Aggregation::Fill),
+        }
+    }
+}
+
+impl FromStr for Aggregation {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "fill" => Ok(Aggregation::Fill),
+            "merge" => Ok(Aggregation::Merge),
+            _ => Err(anyhow::anyhow!(
+                "unknown BATCH_AGGREGATION {value:?}, expected fill or merge"
+            )),
+        }
+    }
+}
+
+/// The fill rate is measured over this long.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Flushes batches less often and merges their fills while fills arrive
+/// faster than `max_fills_per_minute`, so the channel stays readable through
+/// volatility. Per-fill lines return once the rate falls under half of it,
+/// which keeps a rate around the threshold from flapping between the two.
+pub struct AdaptiveBatching {
+    max_fills_per_minute: usize,
+    busy_interval: Duration,
+    recent: VecDeque<(Instant, usize)>,
+    busy: bool,
+}
+
+impl AdaptiveBatching {
+    /// From `ADAPTIVE_BATCH_FILLS_PER_MINUTE`, off without it, and
+    /// `ADAPTIVE_BATCH_INTERVAL_SECS` (default 30).
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let Ok(max_fills_per_minute) = tenants::var("ADAPTIVE_BATCH_FILLS_PER_MINUTE") else {
+            return Ok(None);
+        };
+        let busy_interval = match tenants::var("ADAPTIVE_BATCH_INTERVAL_SECS") {
+            Ok(value) => Duration::from_secs(value.parse()?),
+            Err(_) => Duration::from_secs(30),
+        };
+        Ok(Some(AdaptiveBatching {
+            max_fills_per_minute: max_fills_per_minute.parse()?,
+            busy_interval,
+            recent: VecDeque::new(),
+            busy: false,
+        }))
+    }
+
+    /// Records a flushed batch of `fills` and returns whether that switched
+    /// between busy and calm.
+    pub fn record(&mut self, fills: usize) -> bool {
+        let now = Instant::now();
+        self.recent.push_back((now, fills));
+        while self
+            .recent
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > RATE_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+        let rate = self.fills_per_minute();
+        let busy = if self.busy {
+            rate * 2 > self.max_fills_per_minute
+        } else {
+            rate > self.max_fills_per_minute
+        };
+        let switched = busy != self.busy;
+        self.busy = busy;
+        switched
+    }
+
+    pub fn fills_per_minute(&self) -> usize {
+        self.recent.iter().map(|(_, fills)| fills).sum()
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.busy
+    }
+
+    /// How long the next batch collects fills, given the usual interval.
+    pub fn interval(&self, calm: Duration) -> Duration {
+        if self.busy {
+            self.busy_interval
+        } else {
+            calm
+        }
+    }
+
+    pub fn busy_interval(&self) -> Duration {
+        self.busy_interval
+    }
+}
+
+/// Fills of one address, coin and side within a batch, with their total size
+/// and average price.
+pub struct MergedFill {
+    /// The first fill with the size, price, PnL and fees of them all.
+    pub event: FillEvent,
+    pub fills: usize,
+    pub label: Option<String>,
+    /// Signed position after the last of them.
+    pub position_after: f64,
+}
+
+impl MergedFill {
+    fn add(&mut self, event: FillEvent, position_after: f64) {
+        let notional = self.event.px * self.event.sz + event.px * event.sz;
+        self.event.sz += event.sz;
+        if self.event.sz > 0.0 {
+            self.event.px = notional / self.event.sz;
+        }
+        self.event.time = self.event.time.max(event.time);
+        self.event.closed_pnl += event.closed_pnl;
+        self.event.fee += event.fee;
+        // No single fill's page stands for all of them.
+        self.event.permalink = None;
+        self.fills += 1;
+        self.position_after = position_after;
+    }
+
+    pub fn render(&self) -> String {
+        let side = self.event.side_label();
+        // Sums of decimal sizes pick up float noise.
+        let sz = (self.event.sz * 1e8).round() / 1e8;
+        let mut line = format!(
+            "{side} {} {sz} @ {:.4} avg ({} fills, {})",
+            self.event.coin,
+            self.event.px,
+            self.fills,
+            format_usd(self.event.px * self.event.sz)
+        );
+        if let Some(label) = &self.label {
+            line = format!("{label}: {line}");
+        }
+        if self.event.fee != 0.0 {
+            line.push_str(&format!(", fee {}", format_usd(self.event.fee)));
+        }
+        if self.event.closed_pnl != 0.0 {
+            line.push_str(&format!(", PnL {}", format_usd(self.event.closed_pnl)));
+        }
+        line.push_str(&format!(", position {}", self.position_after));
+        line
+    }
+}
+
+/// Merges a batch's fills per route, address, coin and side, in the order
+/// each group first appeared.
+#[derive(Default)]
+pub struct FillMerger {
+    groups: Vec<(Destination, MergedFill)>,
+}
+
+impl FillMerger {
+    pub fn add(
+        &mut self,
+        route: Destination,
+        event: FillEvent,
+        label: Option<&str>,
+        position_after: f64,
+    ) {
+        let group = self.groups.iter_mut().find(|(group_route, merged)| {
+            *group_route == route
+                && merged.event.user == event.user
+                && merged.event.coin == event.coin
+                && merged.event.side == event.side
+        });
+        match group {
+            Some((_, merged)) => merged.add(event, position_after),
+            None => self.groups.push((
+                route,
+                MergedFill {
+                    event,
+                    fills: 1,
+                    label: label.map(str::to_string),
+                    position_after,
+                },
+            )),
+        }
+    }
+
+    pub fn into_groups(self) -> Vec<(Destination, MergedFill)> {
+        self.groups
+    }
+}
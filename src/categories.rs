@@ -0,0 +1,48 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// How routes, coin lists and alerts refer to a category, as in
+/// `category:memes`.
+pub const PREFIX: &str = "category:";
+
+/// Named groups of coins from the config file, such as majors, memes or new
+/// listings:
+///
+/// ```toml
+/// [categories]
+/// majors = ["BTC", "ETH"]
+/// new_listings = ["HYPE"]
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Categories(BTreeMap<String, Vec<String>>);
+
+impl Categories {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The first category, by name, listing `coin`.
+    pub fn of(&self, coin: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(_, coins)| coins.iter().any(|listed| listed.eq_ignore_ascii_case(coin)))
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn coins(&self, name: &str) -> Option<&[String]> {
+        self.0
+            .iter()
+            .find(|(category, _)| category.eq_ignore_ascii_case(name))
+            .map(|(_, coins)| coins.as_slice())
+    }
+}
+
+/// The category name of an entry like `category:memes`.
+pub fn reference(entry: &str) -> Option<&str> {
+    entry
+        .get(..PREFIX.len())
+        .filter(|prefix| prefix.eq_ignore_ascii_case(PREFIX))
+        .map(|_| &entry[PREFIX.len()..])
+}
@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+use ethers::types::H160;
+use serde::{Deserialize, Serialize};
+
+/// Named groups of addresses known to belong together, such as one trader's
+/// wallets, which digests count as one entity. Members are watched whether or
+/// not they are vault children:
+///
+/// ```toml
+/// [clusters]
+/// whale = ["0x...", "0x..."]
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Clusters(BTreeMap<String, Vec<H160>>);
+
+impl Clusters {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The first cluster, by name, listing `user`.
+    pub fn of(&self, user: H160) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(_, members)| members.contains(&user))
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[H160])> {
+        self.0
+            .iter()
+            .map(|(name, members)| (name.as_str(), members.as_slice()))
+    }
+
+    pub fn members(&self) -> impl Iterator<Item = H160> + '_ {
+        self.0.values().flatten().copied()
+    }
+}
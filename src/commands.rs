@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use chrono::{TimeZone, Utc};
+
+use crate::api::ApiState;
+
+/// Parses durations such as `45s`, `30m`, `4h` or `2d`.
+pub fn parse_duration(value: &str) -> anyhow::Result<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| anyhow!("missing unit in duration {value:?}"))?;
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse()?;
+    let unit_seconds: u64 = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(anyhow!("unknown unit in duration {value:?}")),
+    };
+    // Callers work in milliseconds, so those have to fit as well.
+    let seconds = amount
+        .checked_mul(unit_seconds)
+        .filter(|seconds| seconds.checked_mul(1000).is_some())
+        .ok_or_else(|| anyhow!("duration {value:?} is too long"))?;
+    Ok(Duration::from_secs(seconds))
+}
+
+fn format_until(until: u64) -> String {
+    match Utc.timestamp_millis_opt(until as i64).single() {
+        Some(datetime) => datetime.format("%Y-%m-%d %H:%M UTC").to_string(),
+        None => until.to_string(),
+    }
+}
+
+/// Runs a slash-style command and returns the reply text.
+pub async fn execute(text: &str, state: &ApiState) -> anyhow::Result<String> {
+    let mutes = &state.mutes;
+    let args: Vec<&str> = text.split_whitespace().collect();
+    match args.as_slice() {
+        ["/mute", coin, duration] => {
+            let until = mutes.lock().await.mute(coin, parse_duration(duration)?)?;
+            Ok(format!(
+                "Muted {} until {}",
+                coin.to_uppercase(),
+                format_until(until)
+            ))
+        }
+        ["/unmute", coin] => {
+            if mutes.lock().await.unmute(coin)? {
+                Ok(format!("Unmuted {}", coin.to_uppercase()))
+            } else {
+                Ok(format!("{} was not muted", coin.to_uppercase()))
+            }
+        }
+        ["/mutes"] => {
+            let active = mutes.lock().await.active();
+            if active.is_empty() {
+                return Ok("No active mutes".to_string());
+            }
+            Ok(active
+                .iter()
+                .map(|(coin, until)| format!("{coin} until {}", format_until(*until)))
+                .collect::<Vec<String>>()
+                .join("\n"))
+        }
+        ["/route", coin, webhook_url] => {
+            state.routes.lock().await.set(coin, webhook_url)?;
+            Ok(format!("Routing {} to {webhook_url}", coin.to_uppercase()))
+        }
+        ["/unroute", coin] => {
+            if state.routes.lock().await.remove(coin)? {
+                Ok(format!(
+                    "{} uses the default webhook again",
+                    coin.to_uppercase()
+                ))
+            } else {
+                Ok(format!("{} had no route", coin.to_uppercase()))
+            }
+        }
+        ["/routes"] => {
+            let routes = state.routes.lock().await;
+            if routes.all().is_empty() {
+                return Ok("No routes".to_string());
+            }
+            Ok(routes
+                .all()
+                .iter()
+                .map(|(coin, webhook_url)| format!("{coin} -> {webhook_url}"))
+                .collect::<Vec<String>>()
+                .join("\n"))
+        }
+        ["/note", id, note @ ..] => {
+            let note = note.join(" ");
+            let cleared = note.is_empty();
+            let annotated = state
+                .fill_store
+                .lock()
+                .await
+                .annotate(id, (!cleared).then_some(note))?;
+            match (annotated, cleared) {
+                (false, _) => Err(anyhow!("unknown fill {id}")),
+                (true, false) => Ok(format!("Noted fill {id}")),
+                (true, true) => Ok(format!("Cleared the note of fill {id}")),
+            }
+        }
+        ["/reload"] => Ok(state.reloader.reload_and_report().await),
+        _ => Err(anyhow!("unknown command {text:?}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_unit() {
+        assert_eq!(parse_duration("45s").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(30 * 60));
+        assert_eq!(
+            parse_duration("4h").unwrap(),
+            Duration::from_secs(4 * 60 * 60)
+        );
+        assert_eq!(
+            parse_duration(" 2d ").unwrap(),
+            Duration::from_secs(2 * 24 * 60 * 60)
+        );
+    }
+
+    #[test]
+    fn rejects_missing_and_unknown_units() {
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("10w").is_err());
+        assert!(parse_duration("h").is_err());
+    }
+
+    #[test]
+    fn rejects_durations_beyond_milliseconds() {
+        assert!(parse_duration(&format!("{}s", u64::MAX / 1000 + 1)).is_err());
+        assert!(parse_duration(&format!("{}d", u64::MAX)).is_err());
+    }
+}
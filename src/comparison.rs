@@ -0,0 +1,160 @@
+use ethers::types::H160;
+use hyperliquid_rust_sdk::InfoClient;
+use tracing::warn;
+
+use crate::hyperliquid::vault::VaultDetails;
+use crate::now_ms;
+
+/// `userFills` only returns the most recent fills, so counts at this size are
+/// lower bounds.
+const FILLS_LIMIT: usize = 2000;
+const WEEK_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+pub struct VaultStats {
+    pub name: String,
+    pub return_pct: Option<f64>,
+    pub volume: f64,
+    pub max_drawdown_pct: Option<f64>,
+    pub fills: usize,
+    pub fills_capped: bool,
+    /// Sum of the week's `closedPnl`.
+    pub realized_pnl: f64,
+    /// The week's PnL less what was realized.
+    pub unrealized_change: Option<f64>,
+}
+
+/// Counts the past week's fills of the vault and, for parent vaults, of the
+/// child addresses that trade on its behalf, and sums their realized PnL.
+async fn count_fills(
+    info_client: &InfoClient,
+    vault: H160,
+    children: &[H160],
+) -> (usize, bool, f64) {
+    let since = now_ms().saturating_sub(WEEK_MS);
+    let mut count = 0;
+    let mut capped = false;
+    let mut realized_pnl = 0.0;
+    for user in std::iter::once(&vault).chain(children) {
+        match info_client.user_fills(*user).await {
+            Ok(fills) => {
+                capped |= fills.len() >= FILLS_LIMIT;
+                for fill in fills.iter().filter(|fill| fill.time >= since) {
+                    count += 1;
+                    realized_pnl += fill.closed_pnl.parse::<f64>().unwrap_or_default();
+                }
+            }
+            Err(err) => warn!("failed to fetch fills of {user:?}: {err:?}"),
+        }
+    }
+    (count, capped, realized_pnl)
+}
+
+pub async fn stats(info_client: &InfoClient, vault: H160, details: &VaultDetails) -> VaultStats {
+    let week = details.portfolio("week");
+    let (fills, fills_capped, realized_pnl) =
+        count_fills(info_client, vault, &details.child_addresses).await;
+    let week_pnl = week.and_then(|week| week.pnl());
+    VaultStats {
+        name: details.name.clone(),
+        return_pct: week.and_then(|week| week.return_pct()),
+        volume: week.map(|week| week.volume()).unwrap_or_default(),
+        max_drawdown_pct: week.and_then(|week| week.max_drawdown_pct()),
+        fills,
+        fills_capped,
+        realized_pnl,
+        unrealized_change: week_pnl.map(|pnl| pnl - realized_pnl),
+    }
+}
+
+/// Buy-and-hold return of a coin over the past week.
+pub struct Benchmark {
+    pub coin: String,
+    pub return_pct: f64,
+}
+
+/// From hourly candles: the first open of the week against the last close.
+pub async fn benchmark(info_client: &InfoClient, coin: &str) -> anyhow::Result<Benchmark> {
+    let now = now_ms();
+    let candles = info_client
+        .candles_snapshot(
+            coin.to_string(),
+            "1h".to_string(),
+            now.saturating_sub(WEEK_MS),
+            now,
+        )
+        .await?;
+    let (Some(first), Some(last)) = (candles.first(), candles.last()) else {
+        return Err(anyhow::anyhow!("no {coin} candles for the past week"));
+    };
+    let open: f64 = first.open.parse()?;
+    let close: f64 = last.close.parse()?;
+    Ok(Benchmark {
+        coin: coin.to_string(),
+        return_pct: (close - open) / open * 100.0,
+    })
+}
+
+fn percent(value: Option<f64>) -> String {
+    value.map_or_else(|| "n/a".to_string(), |value| format!("{value:.2}%"))
+}
+
+/// Renders the vaults as one table, best weekly return first, followed by
+/// each vault's return relative to the benchmark.
+pub fn render(mut stats: Vec<VaultStats>, benchmark: Option<&Benchmark>) -> String {
+    stats.sort_by(|a, b| {
+        b.return_pct
+            .unwrap_or(f64::MIN)
+            .total_cmp(&a.return_pct.unwrap_or(f64::MIN))
+    });
+    let name_width = stats
+        .iter()
+        .map(|stats| stats.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+
+    let mut lines = vec![
+        "Weekly vault comparison".to_string(),
+        "```".to_string(),
+        format!(
+            "{:<name_width$} {:>9} {:>16} {:>9} {:>7} {:>14} {:>14}",
+            "Vault", "Return", "Volume", "Max DD", "Fills", "Realized", "Unrealized Δ"
+        ),
+    ];
+    for stats in &stats {
+        let fills = if stats.fills_capped {
+            format!("{}+", stats.fills)
+        } else {
+            stats.fills.to_string()
+        };
+        lines.push(format!(
+            "{:<name_width$} {:>9} {:>16} {:>9} {:>7} {:>14} {:>14}",
+            stats.name,
+            percent(stats.return_pct),
+            format!("${:.0}", stats.volume),
+            percent(stats.max_drawdown_pct),
+            fills,
+            format!("${:.0}", stats.realized_pnl),
+            stats
+                .unrealized_change
+                .map_or_else(|| "n/a".to_string(), |change| format!("${change:.0}"))
+        ));
+    }
+    lines.push("```".to_string());
+    if let Some(benchmark) = benchmark {
+        let relative: Vec<String> = stats
+            .iter()
+            .filter_map(|stats| {
+                let excess = stats.return_pct? - benchmark.return_pct;
+                Some(format!("{} {excess:+.2} pts", stats.name))
+            })
+            .collect();
+        lines.push(format!(
+            "vs {} buy-and-hold ({:+.2}%): {}",
+            benchmark.coin,
+            benchmark.return_pct,
+            relative.join(", ")
+        ));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,796 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use ethers::types::H160;
+use hyperliquid_rust_sdk::BaseUrl;
+use serde::{Deserialize, Serialize};
+
+use crate::categories::Categories;
+use crate::clusters::Clusters;
+use crate::hyperliquid::orders;
+use crate::notify::alerts::{self, Priority};
+use crate::notify::{apprise, home_assistant};
+use crate::{batching, costs, filters, parse_addresses, permalink, schedule, slo, tenants};
+
+/// HLP, watched when no config file exists.
+const DEFAULT_VAULT: &str = "0xdfc24b077bc1425ad1dea75bcb6f8158e10df303";
+
+/// A vault whose child addresses are watched.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WatchedVault {
+    pub address: H160,
+    /// Shown in place of the vault's own name.
+    pub label: Option<String>,
+    /// Where the child addresses' fills go.
+    #[serde(flatten)]
+    pub destination: Destination,
+}
+
+/// A single address watched on its own.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WatchedAddress {
+    pub address: H160,
+    pub label: Option<String>,
+    #[serde(flatten)]
+    pub destination: Destination,
+}
+
+/// Where fills go in place of the default Discord webhook and Telegram chat.
+/// Left out when serialized unless set, so existing config hashes hold.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Destination {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discord_webhook_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub telegram_chat_id: Option<String>,
+}
+
+impl Destination {
+    /// Falls back to `webhook_url`, such as a coin's route, for Discord.
+    pub fn or_webhook(mut self, webhook_url: Option<&str>) -> Self {
+        if self.discord_webhook_url.is_none() {
+            self.discord_webhook_url = webhook_url.map(str::to_string);
+        }
+        self
+    }
+}
+
+/// The Hyperliquid deployment to watch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    pub fn base_url(self) -> BaseUrl {
+        match self {
+            Network::Mainnet => BaseUrl::Mainnet,
+            Network::Testnet => BaseUrl::Testnet,
+        }
+    }
+
+    pub fn api_url(self) -> &'static str {
+        match self {
+            Network::Mainnet => "https://api.hyperliquid.xyz",
+            Network::Testnet => "https://api.hyperliquid-testnet.xyz",
+        }
+    }
+
+    pub fn ws_url(self) -> &'static str {
+        match self {
+            Network::Mainnet => "wss://api.hyperliquid.xyz/ws",
+            Network::Testnet => "wss://api.hyperliquid-testnet.xyz/ws",
+        }
+    }
+
+    /// Where transactions are looked up in the browser.
+    pub fn explorer_url(self) -> &'static str {
+        match self {
+            Network::Mainnet => "https://app.hyperliquid.xyz/explorer",
+            Network::Testnet => "https://app.hyperliquid-testnet.xyz/explorer",
+        }
+    }
+
+    fn is_mainnet(&self) -> bool {
+        *self == Network::Mainnet
+    }
+}
+
+impl FromStr for Network {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "mainnet" => Ok(Network::Mainnet),
+            "testnet" => Ok(Network::Testnet),
+            _ => Err(anyhow::anyhow!(
+                "unknown network {value:?}, expected mainnet or testnet"
+            )),
+        }
+    }
+}
+
+/// What to watch, read from a TOML file:
+///
+/// ```toml
+/// # Optional: mainnet (default) or testnet
+/// network = "testnet"
+///
+/// [[vaults]]
+/// address = "0xdfc24b077bc1425ad1dea75bcb6f8158e10df303"
+/// label = "HLP"
+///
+/// [[addresses]]
+/// address = "0x..."
+/// label = "whale"
+/// # Optional: where this address's fills go; on a vault, its children's
+/// discord_webhook_url = "https://discord.com/api/webhooks/..."
+/// telegram_chat_id = "-100..."
+///
+/// [categories]
+/// memes = ["DOGE", "kPEPE"]
+///
+/// [clusters]
+/// whale = ["0x...", "0x..."]
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    /// Left out when serialized on mainnet so existing config hashes hold.
+    #[serde(default, skip_serializing_if = "Network::is_mainnet")]
+    pub network: Network,
+    #[serde(default)]
+    pub vaults: Vec<WatchedVault>,
+    #[serde(default)]
+    pub addresses: Vec<WatchedAddress>,
+    #[serde(default, skip_serializing_if = "Categories::is_empty")]
+    pub categories: Categories,
+    #[serde(default, skip_serializing_if = "Clusters::is_empty")]
+    pub clusters: Clusters,
+}
+
+impl Config {
+    /// Loads the config file, or watches HLP alone when there is none.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let config: Config = match fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Config {
+                network: Network::Mainnet,
+                vaults: vec![WatchedVault {
+                    address: DEFAULT_VAULT.parse()?,
+                    label: None,
+                    destination: Destination::default(),
+                }],
+                addresses: Vec::new(),
+                categories: Categories::default(),
+                clusters: Clusters::default(),
+            },
+            Err(err) => return Err(err.into()),
+        };
+        if config.vaults.is_empty() && config.addresses.is_empty() && config.clusters.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{} lists no vaults or addresses to watch",
+                path.display()
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Short fingerprint of what is watched, to tell config changes apart
+    /// across restarts.
+    pub fn hash(&self) -> anyhow::Result<String> {
+        let digest = ethers::utils::keccak256(serde_json::to_vec(self)?);
+        Ok(digest[..4]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect())
+    }
+
+    /// Describes what `new` watches differently, one change per line.
+    pub fn diff(&self, new: &Config) -> Vec<String> {
+        let vaults = |config: &Config| -> HashMap<H160, Watched> {
+            config
+                .vaults
+                .iter()
+                .map(|watched| {
+                    (
+                        watched.address,
+                        (watched.label.clone(), watched.destination.clone()),
+                    )
+                })
+                .collect()
+        };
+        let addresses = |config: &Config| -> HashMap<H160, Watched> {
+            config
+                .addresses
+                .iter()
+                .map(|watched| {
+                    (
+                        watched.address,
+                        (watched.label.clone(), watched.destination.clone()),
+                    )
+                })
+                .collect()
+        };
+        let mut changes = diff_watched("vault", &vaults(self), &vaults(new));
+        changes.extend(diff_watched("address", &addresses(self), &addresses(new)));
+        changes.extend(diff_clusters(&self.clusters, &new.clusters));
+        // Read once at startup, unlike what is watched.
+        if self.categories != new.categories {
+            changes.push("~ categories (take effect after a restart)".to_string());
+        }
+        if self.network != new.network {
+            changes.push(format!(
+                "~ network {:?} -> {:?} (takes effect after a restart)",
+                self.network, new.network
+            ));
+        }
+        changes
+    }
+}
+
+/// The watcher's settings from the environment, as described in the README.
+///
+/// Everything here is parsed up front, so a bad value fails the start before
+/// anything is connected or posted.
+pub struct WatcherConfig {
+    pub crash_loop_restarts: usize,
+    pub endpoint_max_latency: Duration,
+    pub metrics_prefix: String,
+    /// Fills go round-robin across these.
+    pub discord_webhook_urls: Vec<String>,
+    /// Where fills that did not parse are posted, as received.
+    pub debug_webhook_url: Option<String>,
+    pub ops_webhook_url: Option<String>,
+    pub sink_pacing: Duration,
+    pub sink_jitter: Duration,
+    pub discord_max_attempts: u32,
+    /// Alerts from this priority up skip the backpressure queue; `None` when
+    /// turned off.
+    pub queue_jump_priority: Option<Priority>,
+    pub desktop_priority: Option<Priority>,
+    pub tts_priority: Option<Priority>,
+    /// The command run on alerts from the priority up.
+    pub trigger: Option<(Priority, String)>,
+    /// Server URL and app token.
+    pub gotify: Option<(String, String)>,
+    pub sms: Option<alerts::SmsConfig>,
+    /// Applies to Twilio destinations in `alert_destinations` as well.
+    pub sms_daily_cap: u32,
+    /// From `NOTIFY_URLS`.
+    pub alert_destinations: Vec<apprise::Destination>,
+    pub mid_cache_ttl: Duration,
+    pub position_poll_interval: Duration,
+    pub ledger_alert_threshold_usd: f64,
+    pub vault_withdrawal_alert_usd: f64,
+    pub correlation_window: Duration,
+    pub correlation_min_addresses: usize,
+    pub velocity_spike_per_minute: usize,
+    pub velocity_silence: Duration,
+    /// Idle time that ends a session, when sessions are followed.
+    pub session_idle: Option<Duration>,
+    pub session_min_fills: usize,
+    pub equity_alert_below: Option<f64>,
+    pub equity_alert_above: Option<f64>,
+    pub flows_schedule: schedule::DailySchedule,
+    pub compare_vaults: Vec<H160>,
+    pub benchmark_coin: Option<String>,
+    pub digest_schedule: Option<schedule::DailySchedule>,
+    pub funding_summary: Option<costs::FundingPeriod>,
+    pub comparison_schedule: schedule::WeeklySchedule,
+    pub mutes_path: PathBuf,
+    pub routes_path: PathBuf,
+    pub http_listen_addr: Option<String>,
+    pub healthz_max_silence: Duration,
+    pub relay_token: Option<String>,
+    pub api_token: Option<String>,
+    pub order_summaries: orders::OrderSummaries,
+    pub bad_fill_bps: f64,
+    pub live_summary_interval: Option<Duration>,
+    pub discord_bot_token: Option<String>,
+    pub verbose: bool,
+    pub public_base_url: Option<String>,
+    pub notional_filter: filters::NotionalFilter,
+    pub coin_filter: filters::CoinFilter,
+    /// Categories whose fills are also alerted, checked against the config's.
+    pub alert_categories: Vec<String>,
+    pub backpressure: Option<filters::Backpressure>,
+    pub batch_interval: Duration,
+    pub aggregation: batching::Aggregation,
+    pub adaptive_batching: Option<batching::AdaptiveBatching>,
+    pub book_depth_min_usd: Option<f64>,
+    pub subscribe_failure_alert_threshold: u32,
+    pub subscription_max_silence: Duration,
+    pub latency_budget: Option<slo::LatencyBudget>,
+    pub delta_alert_usd: Option<f64>,
+    pub liquidation_alert_pct: Option<f64>,
+    /// The role mention prefixed to liquidation alerts.
+    pub liquidation_mention: Option<String>,
+    pub concentration_alert_pct: Option<f64>,
+    pub leader_stake_alert_pct: Option<f64>,
+    pub vault_refresh_interval: Duration,
+    /// Set when fills are polled in place of the WebSocket.
+    pub poll_interval: Option<Duration>,
+    /// Directory, bytes per file and files kept.
+    pub capture: Option<(PathBuf, u64, usize)>,
+    /// Posts a summary of the fills made while the watcher was down.
+    pub missed_trades_summary: bool,
+    pub slack_webhook_url: Option<String>,
+    /// Bot token and chat id.
+    pub telegram: Option<(String, String)>,
+    pub trade_json_webhook_url: Option<String>,
+    /// URI and exchange.
+    pub rabbitmq: Option<(String, String)>,
+    /// Base URL and how to authenticate.
+    pub home_assistant: Option<(String, home_assistant::Auth)>,
+    pub zmq_pub_endpoint: Option<String>,
+    /// Service account file, spreadsheet id and range.
+    pub google_sheets: Option<(String, String, String)>,
+}
+
+impl WatcherConfig {
+    /// Reads the settings, resolving `ALERT_CATEGORIES` and the coin lists
+    /// against `categories`.
+    pub fn from_env(categories: &Categories) -> anyhow::Result<Self> {
+        let crash_loop_restarts = match tenants::var("CRASH_LOOP_RESTARTS") {
+            Ok(value) => value.parse()?,
+            Err(_) => 5,
+        };
+        let endpoint_max_latency = match tenants::var("ENDPOINT_MAX_LATENCY_MS") {
+            Ok(value) => Duration::from_millis(value.parse()?),
+            Err(_) => Duration::from_secs(2),
+        };
+        let metrics_prefix =
+            tenants::var("METRICS_PREFIX").unwrap_or_else(|_| "god_watcher".to_string());
+        let discord_webhook_urls = tenants::var("DISCORD_WEBHOOK_URL")?
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect();
+        let sink_pacing = match tenants::var("SINK_PACING_MS") {
+            Ok(value) => Duration::from_millis(value.parse()?),
+            Err(_) => Duration::ZERO,
+        };
+        let sink_jitter = match tenants::var("SINK_JITTER_MS") {
+            Ok(value) => Duration::from_millis(value.parse()?),
+            Err(_) => Duration::ZERO,
+        };
+        let discord_max_attempts = match tenants::var("DISCORD_MAX_ATTEMPTS") {
+            Ok(value) => value.parse()?,
+            Err(_) => 5,
+        };
+        let queue_jump_priority = match tenants::var("BACKPRESSURE_ALERT_PRIORITY").as_deref() {
+            Ok("off") => None,
+            Ok(value) => Some(value.parse()?),
+            Err(_) => Some(Priority::High),
+        };
+        let desktop_priority = match tenants::var("DESKTOP_NOTIFICATIONS") {
+            Ok(value) => Some(value.parse()?),
+            Err(_) => None,
+        };
+        let tts_priority = match tenants::var("ALERT_TTS_PRIORITY") {
+            Ok(value) => Some(value.parse()?),
+            Err(_) => None,
+        };
+        let trigger = match tenants::var("ALERT_TRIGGER") {
+            Ok(trigger) => {
+                let priority = match tenants::var("ALERT_TRIGGER_PRIORITY") {
+                    Ok(value) => value.parse()?,
+                    Err(_) => Priority::High,
+                };
+                Some((priority, trigger))
+            }
+            Err(_) => None,
+        };
+        let gotify = match (tenants::var("GOTIFY_URL"), tenants::var("GOTIFY_TOKEN")) {
+            (Ok(server_url), Ok(app_token)) => Some((server_url, app_token)),
+            _ => None,
+        };
+        let sms_daily_cap = match tenants::var("SMS_DAILY_CAP") {
+            Ok(value) => value.parse()?,
+            Err(_) => 10,
+        };
+        let sms = match (
+            tenants::var("TWILIO_ACCOUNT_SID"),
+            tenants::var("TWILIO_AUTH_TOKEN"),
+            tenants::var("TWILIO_FROM"),
+            tenants::var("TWILIO_TO"),
+        ) {
+            (Ok(account_sid), Ok(auth_token), Ok(from), Ok(to)) => Some(alerts::SmsConfig {
+                account_sid,
+                auth_token,
+                from,
+                to,
+                daily_cap: sms_daily_cap,
+            }),
+            _ => None,
+        };
+        let mut alert_destinations = Vec::new();
+        if let Ok(value) = tenants::var("NOTIFY_URLS") {
+            for url in value
+                .split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+            {
+                alert_destinations.push(url.parse()?);
+            }
+        }
+        let mid_cache_ttl = match tenants::var("MID_CACHE_TTL_SECS") {
+            Ok(value) => Duration::from_secs(value.parse()?),
+            Err(_) => Duration::from_secs(10),
+        };
+        let position_poll_interval = match tenants::var("POSITION_POLL_INTERVAL_SECS") {
+            Ok(value) => Duration::from_secs(value.parse()?),
+            Err(_) => Duration::from_secs(60),
+        };
+        let ledger_alert_threshold_usd = match tenants::var("LEDGER_ALERT_THRESHOLD_USD") {
+            Ok(value) => value.parse()?,
+            Err(_) => 100_000.0,
+        };
+        let vault_withdrawal_alert_usd = match tenants::var("VAULT_WITHDRAWAL_ALERT_USD") {
+            Ok(value) => value.parse()?,
+            Err(_) => 1_000_000.0,
+        };
+        let correlation_window = match tenants::var("CORRELATION_WINDOW_SECS") {
+            Ok(value) => Duration::from_secs(value.parse()?),
+            Err(_) => Duration::from_secs(60),
+        };
+        let correlation_min_addresses = match tenants::var("CORRELATION_MIN_ADDRESSES") {
+            Ok(value) => value.parse()?,
+            Err(_) => 2,
+        };
+        let velocity_spike_per_minute = match tenants::var("VELOCITY_SPIKE_PER_MIN") {
+            Ok(value) => value.parse()?,
+            Err(_) => 30,
+        };
+        let velocity_silence = match tenants::var("VELOCITY_SILENCE_SECS") {
+            Ok(value) => Duration::from_secs(value.parse()?),
+            Err(_) => Duration::from_secs(60 * 60),
+        };
+        let session_idle = match tenants::var("SESSION_IDLE_SECS") {
+            Ok(value) => Some(Duration::from_secs(value.parse()?)),
+            Err(_) => None,
+        };
+        let session_min_fills = match tenants::var("SESSION_MIN_FILLS") {
+            Ok(value) => value.parse()?,
+            Err(_) => 3,
+        };
+        let equity_alert_below = match tenants::var("EQUITY_ALERT_BELOW_USD") {
+            Ok(value) => Some(value.parse()?),
+            Err(_) => None,
+        };
+        let equity_alert_above = match tenants::var("EQUITY_ALERT_ABOVE_USD") {
+            Ok(value) => Some(value.parse()?),
+            Err(_) => None,
+        };
+        let flows_schedule = tenants::var("VAULT_FLOWS_SCHEDULE")
+            .unwrap_or_else(|_| "UTC 00:00".to_string())
+            .parse()?;
+        let compare_vaults = match tenants::var("COMPARE_VAULTS") {
+            Ok(value) => parse_addresses(&value)?,
+            Err(_) => Vec::new(),
+        };
+        let digest_schedule = match tenants::var("DIGEST_SCHEDULE") {
+            Ok(value) => Some(value.parse()?),
+            Err(_) => None,
+        };
+        let funding_summary = match tenants::var("FUNDING_SUMMARY") {
+            Ok(value) => Some(value.parse()?),
+            Err(_) => None,
+        };
+        let comparison_schedule = tenants::var("COMPARISON_SCHEDULE")
+            .unwrap_or_else(|_| "Mon UTC 00:00".to_string())
+            .parse()?;
+        let healthz_max_silence = match tenants::var("HEALTHZ_MAX_SILENCE_SECS") {
+            Ok(value) => Duration::from_secs(value.parse()?),
+            Err(_) => Duration::from_secs(60),
+        };
+        let bad_fill_bps = match tenants::var("BAD_FILL_BPS") {
+            Ok(value) => value.parse()?,
+            Err(_) => 25.0,
+        };
+        let live_summary_interval = match tenants::var("LIVE_SUMMARY_INTERVAL_SECS") {
+            Ok(value) => Some(Duration::from_secs(value.parse()?)),
+            Err(_) => None,
+        };
+        let alert_categories: Vec<String> = tenants::var("ALERT_CATEGORIES")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Some(name) = alert_categories
+            .iter()
+            .find(|name| categories.coins(name).is_none())
+        {
+            return Err(anyhow::anyhow!(
+                "unknown coin category {name:?} in ALERT_CATEGORIES"
+            ));
+        }
+        let book_depth_min_usd = match tenants::var("BOOK_DEPTH_MIN_USD") {
+            Ok(value) => Some(value.parse()?),
+            Err(_) => None,
+        };
+        let subscribe_failure_alert_threshold =
+            match tenants::var("SUBSCRIBE_FAILURE_ALERT_THRESHOLD") {
+                Ok(value) => value.parse()?,
+                Err(_) => 3,
+            };
+        let subscription_max_silence = match tenants::var("SUBSCRIPTION_MAX_SILENCE_SECS") {
+            Ok(value) => Duration::from_secs(value.parse()?),
+            Err(_) => Duration::from_secs(60),
+        };
+        let latency_budget = match tenants::var("LATENCY_SLO_SECS") {
+            Ok(value) => {
+                let target_pct = match tenants::var("LATENCY_SLO_PCT") {
+                    Ok(value) => value.parse()?,
+                    Err(_) => 95.0,
+                };
+                let window = match tenants::var("LATENCY_SLO_WINDOW_SECS") {
+                    Ok(value) => Duration::from_secs(value.parse()?),
+                    Err(_) => Duration::from_secs(3600),
+                };
+                Some(slo::LatencyBudget::new(value.parse()?, target_pct, window)?)
+            }
+            Err(_) => None,
+        };
+        let delta_alert_usd = match tenants::var("DELTA_ALERT_USD") {
+            Ok(value) => Some(value.parse()?),
+            Err(_) => None,
+        };
+        let liquidation_alert_pct = match tenants::var("LIQUIDATION_ALERT_PCT") {
+            Ok(value) => Some(value.parse()?),
+            Err(_) => None,
+        };
+        let concentration_alert_pct = match tenants::var("CONCENTRATION_ALERT_PCT") {
+            Ok(value) => Some(value.parse()?),
+            Err(_) => None,
+        };
+        let leader_stake_alert_pct = match tenants::var("LEADER_STAKE_ALERT_PCT") {
+            Ok(value) => Some(value.parse()?),
+            Err(_) => None,
+        };
+        let vault_refresh_interval = match tenants::var("VAULT_REFRESH_INTERVAL_SECS") {
+            Ok(value) => Duration::from_secs(value.parse()?),
+            Err(_) => Duration::from_secs(300),
+        };
+        let poll_interval = match tenants::var("TRANSPORT").as_deref() {
+            Ok("polling") => Some(match tenants::var("POLL_INTERVAL_SECS") {
+                Ok(value) => Duration::from_secs(value.parse()?),
+                Err(_) => Duration::from_secs(10),
+            }),
+            Ok("websocket") | Err(_) => None,
+            Ok(other) => return Err(anyhow::anyhow!("unknown TRANSPORT {other:?}")),
+        };
+        let capture = match tenants::var("CAPTURE_DIR") {
+            Ok(dir) => {
+                let max_bytes = match tenants::var("CAPTURE_MAX_BYTES") {
+                    Ok(value) => value.parse()?,
+                    Err(_) => 100_000_000,
+                };
+                let keep = match tenants::var("CAPTURE_KEEP_FILES") {
+                    Ok(value) => value.parse()?,
+                    Err(_) => 24,
+                };
+                Some((dir.into(), max_bytes, keep))
+            }
+            Err(_) => None,
+        };
+        let telegram = match (
+            tenants::var("TELEGRAM_BOT_TOKEN"),
+            tenants::var("TELEGRAM_CHAT_ID"),
+        ) {
+            (Ok(bot_token), Ok(chat_id)) => Some((bot_token, chat_id)),
+            _ => None,
+        };
+        let rabbitmq = match tenants::var("RABBITMQ_URL") {
+            Ok(uri) => {
+                let exchange =
+                    tenants::var("RABBITMQ_EXCHANGE").unwrap_or_else(|_| "god_watcher".to_string());
+                Some((uri, exchange))
+            }
+            Err(_) => None,
+        };
+        let home_assistant = match tenants::var("HOME_ASSISTANT_URL") {
+            Ok(base_url) => {
+                let auth = match (
+                    tenants::var("HOME_ASSISTANT_WEBHOOK_ID"),
+                    tenants::var("HOME_ASSISTANT_TOKEN"),
+                ) {
+                    (Ok(webhook_id), _) => home_assistant::Auth::Webhook(webhook_id),
+                    (_, Ok(token)) => home_assistant::Auth::Token(token),
+                    _ => anyhow::bail!(
+                        "HOME_ASSISTANT_URL needs HOME_ASSISTANT_WEBHOOK_ID or HOME_ASSISTANT_TOKEN"
+                    ),
+                };
+                Some((base_url, auth))
+            }
+            Err(_) => None,
+        };
+        let google_sheets = match (
+            tenants::var("GOOGLE_SERVICE_ACCOUNT_FILE"),
+            tenants::var("GOOGLE_SHEETS_SPREADSHEET_ID"),
+        ) {
+            (Ok(service_account_path), Ok(spreadsheet_id)) => {
+                let range =
+                    tenants::var("GOOGLE_SHEETS_RANGE").unwrap_or_else(|_| "Fills!A:J".to_string());
+                Some((service_account_path, spreadsheet_id, range))
+            }
+            _ => None,
+        };
+
+        Ok(WatcherConfig {
+            crash_loop_restarts,
+            endpoint_max_latency,
+            metrics_prefix,
+            discord_webhook_urls,
+            debug_webhook_url: tenants::var("DEBUG_DISCORD_WEBHOOK_URL").ok(),
+            ops_webhook_url: tenants::var("OPS_DISCORD_WEBHOOK_URL").ok(),
+            sink_pacing,
+            sink_jitter,
+            discord_max_attempts,
+            queue_jump_priority,
+            desktop_priority,
+            tts_priority,
+            trigger,
+            gotify,
+            sms,
+            sms_daily_cap,
+            alert_destinations,
+            mid_cache_ttl,
+            position_poll_interval,
+            ledger_alert_threshold_usd,
+            vault_withdrawal_alert_usd,
+            correlation_window,
+            correlation_min_addresses,
+            velocity_spike_per_minute,
+            velocity_silence,
+            session_idle,
+            session_min_fills,
+            equity_alert_below,
+            equity_alert_above,
+            flows_schedule,
+            compare_vaults,
+            benchmark_coin: tenants::var("BENCHMARK_COIN").ok(),
+            digest_schedule,
+            funding_summary,
+            comparison_schedule,
+            mutes_path: tenants::var("MUTES_PATH")
+                .unwrap_or_else(|_| "mutes.json".to_string())
+                .into(),
+            routes_path: tenants::var("ROUTES_PATH")
+                .unwrap_or_else(|_| "routes.json".to_string())
+                .into(),
+            http_listen_addr: tenants::var("HTTP_LISTEN_ADDR").ok(),
+            healthz_max_silence,
+            relay_token: tenants::var("RELAY_TOKEN").ok(),
+            api_token: tenants::var("API_TOKEN").ok(),
+            order_summaries: orders::OrderSummaries::from_env()?,
+            bad_fill_bps,
+            live_summary_interval,
+            discord_bot_token: tenants::var("DISCORD_BOT_TOKEN").ok(),
+            verbose: tenants::var("MESSAGE_FORMAT").is_ok_and(|value| value == "verbose"),
+            public_base_url: permalink::base_url_from_env(),
+            notional_filter: filters::NotionalFilter::from_env()?,
+            coin_filter: filters::CoinFilter::from_env(categories)?,
+            alert_categories,
+            backpressure: filters::Backpressure::from_env()?,
+            batch_interval: batching::interval_from_env()?,
+            aggregation: batching::Aggregation::from_env()?,
+            adaptive_batching: batching::AdaptiveBatching::from_env()?,
+            book_depth_min_usd,
+            subscribe_failure_alert_threshold,
+            subscription_max_silence,
+            latency_budget,
+            delta_alert_usd,
+            liquidation_alert_pct,
+            liquidation_mention: tenants::var("LIQUIDATION_MENTION_ROLE_ID")
+                .ok()
+                .map(|role_id| format!("<@&{role_id}>")),
+            concentration_alert_pct,
+            leader_stake_alert_pct,
+            vault_refresh_interval,
+            poll_interval,
+            capture,
+            missed_trades_summary: tenants::var("MISSED_TRADES")
+                .is_ok_and(|value| value == "summary"),
+            slack_webhook_url: tenants::var("SLACK_WEBHOOK_URL").ok(),
+            telegram,
+            trade_json_webhook_url: tenants::var("TRADE_JSON_WEBHOOK_URL").ok(),
+            rabbitmq,
+            home_assistant,
+            zmq_pub_endpoint: tenants::var("ZMQ_PUB_ENDPOINT").ok(),
+            google_sheets,
+        })
+    }
+}
+
+/// A watched vault's or address's label and destination.
+type Watched = (Option<String>, Destination);
+
+/// Describes `destination` without its webhook URL, which is a secret.
+fn describe_destination(destination: &Destination) -> String {
+    let mut parts = Vec::new();
+    if destination.discord_webhook_url.is_some() {
+        parts.push("own Discord webhook".to_string());
+    }
+    if let Some(chat_id) = &destination.telegram_chat_id {
+        parts.push(format!("Telegram chat {chat_id}"));
+    }
+    if parts.is_empty() {
+        "default destination".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+fn diff_watched(
+    kind: &str,
+    old: &HashMap<H160, Watched>,
+    new: &HashMap<H160, Watched>,
+) -> Vec<String> {
+    let describe = |label: &Option<String>| label.clone().unwrap_or_else(|| "no label".to_string());
+    let mut changes = Vec::new();
+    for (address, (label, destination)) in new {
+        match old.get(address) {
+            None => changes.push(format!("+ {kind} {address:?} ({})", describe(label))),
+            Some((old_label, old_destination)) => {
+                if old_label != label {
+                    changes.push(format!(
+                        "~ {kind} {address:?}: {} -> {}",
+                        describe(old_label),
+                        describe(label)
+                    ));
+                }
+                if old_destination != destination {
+                    changes.push(format!(
+                        "~ {kind} {address:?}: {} -> {}",
+                        describe_destination(old_destination),
+                        describe_destination(destination)
+                    ));
+                }
+            }
+        }
+    }
+    for (address, (label, _)) in old {
+        if !new.contains_key(address) {
+            changes.push(format!("- {kind} {address:?} ({})", describe(label)));
+        }
+    }
+    changes.sort();
+    changes
+}
+
+fn diff_clusters(old: &Clusters, new: &Clusters) -> Vec<String> {
+    let old: HashMap<&str, &[H160]> = old.iter().collect();
+    let new: HashMap<&str, &[H160]> = new.iter().collect();
+    let mut changes = Vec::new();
+    for (name, members) in &new {
+        match old.get(name) {
+            None => changes.push(format!("+ cluster {name} ({} members)", members.len())),
+            Some(old_members) if old_members != members => changes.push(format!(
+                "~ cluster {name}: {} -> {} members",
+                old_members.len(),
+                members.len()
+            )),
+            Some(_) => (),
+        }
+    }
+    for (name, members) in &old {
+        if !new.contains_key(name) {
+            changes.push(format!("- cluster {name} ({} members)", members.len()));
+        }
+    }
+    changes.sort();
+    changes
+}
@@ -0,0 +1,46 @@
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+
+use tokio::task::JoinHandle;
+
+use crate::metrics::Metrics;
+use crate::notify::pacing::Pacer;
+use crate::notify::retry::RetryQueue;
+
+/// What one `Watcher` keeps to itself although its modules reach it from
+/// anywhere: its metrics, sink pacing and Discord retry queue. Several
+/// watchers can share a process, as tenants do, so this belongs to the tasks
+/// of the watcher that set it up rather than to statics.
+#[derive(Default)]
+pub struct Context {
+    pub(crate) metrics: OnceLock<Metrics>,
+    pub(crate) pacer: OnceLock<Pacer>,
+    pub(crate) retry_queue: OnceLock<RetryQueue>,
+}
+
+tokio::task_local! {
+    static CURRENT: Arc<Context>;
+}
+
+/// Runs `future` with `context` as the current one.
+pub async fn scope<F: Future>(context: Arc<Context>, future: F) -> F::Output {
+    CURRENT.scope(context, future).await
+}
+
+/// The context of the watcher this task belongs to, if any.
+pub fn current() -> Option<Arc<Context>> {
+    CURRENT.try_with(Arc::clone).ok()
+}
+
+/// Spawns `future` in the current context, so the watcher's metrics, pacing
+/// and retries follow it. Used in place of `tokio::spawn`.
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match current() {
+        Some(context) => tokio::spawn(CURRENT.scope(context, future)),
+        None => tokio::spawn(future),
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use ethers::types::H160;
+
+/// Detects several watched addresses trading the same coin in the same
+/// direction within a short window.
+pub struct CorrelationTracker {
+    window: Duration,
+    min_addresses: usize,
+    recent: HashMap<(String, String), Vec<(H160, Instant)>>,
+}
+
+impl CorrelationTracker {
+    pub fn new(window: Duration, min_addresses: usize) -> Self {
+        CorrelationTracker {
+            window,
+            min_addresses,
+            recent: HashMap::new(),
+        }
+    }
+
+    /// Records a fill and returns the addresses involved once enough distinct
+    /// addresses traded the coin the same way.
+    pub fn record(&mut self, user: H160, coin: &str, side: &str) -> Option<Vec<H160>> {
+        let now = Instant::now();
+        let entries = self
+            .recent
+            .entry((coin.to_string(), side.to_string()))
+            .or_default();
+        entries.retain(|(_, at)| now.duration_since(*at) < self.window);
+        if !entries.iter().any(|(address, _)| *address == user) {
+            entries.push((user, now));
+        }
+
+        if entries.len() < self.min_addresses {
+            return None;
+        }
+        let addresses = entries.iter().map(|(address, _)| *address).collect();
+        entries.clear();
+        Some(addresses)
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use ethers::types::H160;
+
+use crate::{format_usd, now_ms};
+
+/// Costs older than this are dropped; the weekly report is the longest reader.
+const RETENTION_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+/// Coins listed per report before the rest are summed up.
+const SHOWN_COINS: usize = 5;
+
+struct Cost {
+    time: u64,
+    user: H160,
+    coin: String,
+    fee: f64,
+    /// Positive when funding was paid.
+    funding: f64,
+}
+
+/// Fees and funding paid per address and coin over the past week.
+#[derive(Default)]
+pub struct CostLedger {
+    costs: VecDeque<Cost>,
+}
+
+/// Fees and funding summed per coin.
+#[derive(Default)]
+pub struct Costs {
+    pub by_coin: BTreeMap<String, (f64, f64)>,
+}
+
+impl CostLedger {
+    fn push(&mut self, cost: Cost) {
+        let cutoff = now_ms().saturating_sub(RETENTION_MS);
+        while self.costs.front().is_some_and(|cost| cost.time < cutoff) {
+            self.costs.pop_front();
+        }
+        self.costs.push_back(cost);
+    }
+
+    pub fn record_fee(&mut self, user: H160, coin: &str, time: u64, fee: f64) {
+        self.push(Cost {
+            time,
+            user,
+            coin: coin.to_string(),
+            fee,
+            funding: 0.0,
+        });
+    }
+
+    /// `usdc` is the signed payment as Hyperliquid reports it, negative when paid.
+    pub fn record_funding(&mut self, user: H160, coin: &str, time: u64, usdc: f64) {
+        self.push(Cost {
+            time,
+            user,
+            coin: coin.to_string(),
+            fee: 0.0,
+            funding: -usdc,
+        });
+    }
+
+    /// Costs since `since` in milliseconds, of `users` or of every address.
+    pub fn since(&self, since: u64, users: Option<&[H160]>) -> Costs {
+        let mut costs = Costs::default();
+        for cost in self.costs.iter().filter(|cost| cost.time >= since) {
+            if users.is_some_and(|users| !users.contains(&cost.user)) {
+                continue;
+            }
+            let entry = costs.by_coin.entry(cost.coin.clone()).or_default();
+            entry.0 += cost.fee;
+            entry.1 += cost.funding;
+        }
+        costs
+    }
+
+    /// Funding since `since` in milliseconds, per address and coin.
+    pub fn funding_since(&self, since: u64) -> Funding {
+        let mut funding = Funding::default();
+        for cost in self.costs.iter().filter(|cost| cost.time >= since) {
+            if cost.funding == 0.0 {
+                continue;
+            }
+            *funding
+                .by_user
+                .entry(cost.user)
+                .or_default()
+                .entry(cost.coin.clone())
+                .or_default() -= cost.funding;
+        }
+        funding
+    }
+}
+
+/// How often the funding summary is posted.
+#[derive(Clone, Copy)]
+pub enum FundingPeriod {
+    Hourly,
+    Daily,
+}
+
+impl FromStr for FundingPeriod {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "hourly" => Ok(FundingPeriod::Hourly),
+            "daily" => Ok(FundingPeriod::Daily),
+            _ => Err(anyhow!("unknown funding summary period {value:?}")),
+        }
+    }
+}
+
+impl FundingPeriod {
+    pub fn window_ms(&self) -> u64 {
+        match self {
+            FundingPeriod::Hourly => 60 * 60 * 1000,
+            FundingPeriod::Daily => 24 * 60 * 60 * 1000,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            FundingPeriod::Hourly => "hour",
+            FundingPeriod::Daily => "24 hours",
+        }
+    }
+}
+
+/// Funding per address and coin, positive when received.
+#[derive(Default)]
+pub struct Funding {
+    pub by_user: BTreeMap<H160, BTreeMap<String, f64>>,
+}
+
+impl Funding {
+    pub fn is_empty(&self) -> bool {
+        self.by_user.is_empty()
+    }
+
+    fn total(coins: &BTreeMap<String, f64>) -> f64 {
+        coins.values().sum()
+    }
+
+    /// The totals received and paid, then each address's net and its coins,
+    /// the largest payments first.
+    pub fn render(&self, period: FundingPeriod, labels: &BTreeMap<H160, String>) -> String {
+        let payments = self.by_user.values().flat_map(|coins| coins.values());
+        let received: f64 = payments.clone().filter(|usdc| **usdc > 0.0).sum();
+        let paid: f64 = -payments.filter(|usdc| **usdc < 0.0).sum::<f64>();
+        let mut lines = vec![format!(
+            "Funding over the last {}: received {}, paid {}, net {}",
+            period.describe(),
+            format_usd(received),
+            format_usd(paid),
+            format_usd(received - paid)
+        )];
+        let mut users: Vec<(&H160, &BTreeMap<String, f64>)> = self.by_user.iter().collect();
+        users.sort_by(|a, b| {
+            Funding::total(a.1)
+                .abs()
+                .total_cmp(&Funding::total(b.1).abs())
+                .reverse()
+        });
+        for (user, coins) in users {
+            let name = labels
+                .get(user)
+                .cloned()
+                .unwrap_or_else(|| format!("{user:?}"));
+            let mut coins: Vec<(&String, &f64)> = coins.iter().collect();
+            coins.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+            let shown: Vec<String> = coins
+                .iter()
+                .take(SHOWN_COINS)
+                .map(|(coin, usdc)| format!("{coin} {}", format_usd(**usdc)))
+                .collect();
+            let mut line = format!(
+                "- {name}: net {} ({})",
+                format_usd(coins.iter().map(|(_, usdc)| **usdc).sum()),
+                shown.join(", ")
+            );
+            if coins.len() > SHOWN_COINS {
+                line.push_str(&format!(" and {} more coins", coins.len() - SHOWN_COINS));
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+}
+
+impl Costs {
+    pub fn fees(&self) -> f64 {
+        self.by_coin.values().map(|(fee, _)| fee).sum()
+    }
+
+    pub fn funding(&self) -> f64 {
+        self.by_coin.values().map(|(_, funding)| funding).sum()
+    }
+
+    /// "Costs" lines: the totals, then the costliest coins.
+    pub fn render(&self) -> String {
+        let mut lines = vec![format!(
+            "Costs: fees ${:.2}, funding ${:.2}, total ${:.2}",
+            self.fees(),
+            self.funding(),
+            self.fees() + self.funding()
+        )];
+        let mut coins: Vec<(&String, &(f64, f64))> = self.by_coin.iter().collect();
+        coins.sort_by(|a, b| (b.1 .0 + b.1 .1).total_cmp(&(a.1 .0 + a.1 .1)));
+        for (coin, (fee, funding)) in coins.iter().take(SHOWN_COINS) {
+            lines.push(format!("- {coin}: fees ${fee:.2}, funding ${funding:.2}"));
+        }
+        if coins.len() > SHOWN_COINS {
+            lines.push(format!("- and {} more coins", coins.len() - SHOWN_COINS));
+        }
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,97 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+const NONCE_LEN: usize = 12;
+
+/// Encrypts records one line at a time with ChaCha20-Poly1305, so encrypted
+/// files stay append-only. Each line is the base64 of a fresh nonce followed
+/// by the ciphertext.
+#[derive(Clone)]
+pub struct LineCipher {
+    cipher: ChaCha20Poly1305,
+}
+
+impl LineCipher {
+    /// Takes a 32-byte key written as 64 hex characters.
+    pub fn from_hex(key: &str) -> anyhow::Result<Self> {
+        let key = ethers::utils::hex::decode(key.trim())?;
+        if key.len() != 32 {
+            return Err(anyhow::anyhow!(
+                "encryption key must be 32 bytes, got {}",
+                key.len()
+            ));
+        }
+        Ok(LineCipher {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+        })
+    }
+
+    /// Reads `STORE_ENCRYPTION_KEY`; storage stays plaintext without it.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        match crate::tenants::var("STORE_ENCRYPTION_KEY") {
+            Ok(key) => Ok(Some(LineCipher::from_hex(&key)?)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn seal(&self, plaintext: &str) -> anyhow::Result<String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| anyhow::anyhow!("failed to encrypt record"))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(STANDARD.encode(sealed))
+    }
+
+    pub fn open(&self, line: &str) -> anyhow::Result<String> {
+        let sealed = STANDARD.decode(line.trim())?;
+        if sealed.len() < NONCE_LEN {
+            return Err(anyhow::anyhow!("encrypted record is truncated"));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("failed to decrypt record; wrong key?"))?;
+        Ok(String::from_utf8(plaintext)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    #[test]
+    fn round_trips() {
+        let cipher = LineCipher::from_hex(KEY).unwrap();
+        let sealed = cipher.seal(r#"{"coin":"BTC"}"#).unwrap();
+        assert!(!sealed.contains("BTC"));
+        assert_eq!(cipher.open(&sealed).unwrap(), r#"{"coin":"BTC"}"#);
+    }
+
+    #[test]
+    fn seals_with_a_fresh_nonce() {
+        let cipher = LineCipher::from_hex(KEY).unwrap();
+        assert_ne!(cipher.seal("fill").unwrap(), cipher.seal("fill").unwrap());
+    }
+
+    #[test]
+    fn refuses_other_keys_and_damaged_lines() {
+        let cipher = LineCipher::from_hex(KEY).unwrap();
+        let sealed = cipher.seal("fill").unwrap();
+        let other = LineCipher::from_hex(&KEY.replace("00", "ff")).unwrap();
+        assert!(other.open(&sealed).is_err());
+        assert!(cipher.open(&STANDARD.encode([0u8; 4])).is_err());
+    }
+
+    #[test]
+    fn takes_32_byte_keys_only() {
+        assert!(LineCipher::from_hex(&KEY[..62]).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+use redis::aio::ConnectionManager;
+use tracing::warn;
+
+/// How long a claimed fill stays claimed; comfortably longer than any
+/// instance's batching or reconnection delay.
+const CLAIM_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Redis set of fill ids shared by watcher instances, so a fill watched by
+/// several of them is notified once.
+#[derive(Clone)]
+pub struct SharedDedup {
+    connection: ConnectionManager,
+    prefix: String,
+}
+
+impl SharedDedup {
+    /// From `REDIS_URL` and `REDIS_DEDUP_PREFIX`; `None` without a URL.
+    pub async fn from_env() -> anyhow::Result<Option<Self>> {
+        let Ok(url) = crate::tenants::var("REDIS_URL") else {
+            return Ok(None);
+        };
+        let prefix =
+            crate::tenants::var("REDIS_DEDUP_PREFIX").unwrap_or_else(|_| "god_watcher".to_string());
+        let connection = ConnectionManager::new(redis::Client::open(url)?).await?;
+        Ok(Some(SharedDedup { connection, prefix }))
+    }
+
+    /// Returns whether this instance is the first to claim `id`. Fails open,
+    /// so a Redis outage duplicates notifications rather than losing them.
+    pub async fn claim(&self, id: &str) -> bool {
+        let claimed: redis::RedisResult<Option<String>> = redis::cmd("SET")
+            .arg(format!("{}:fill:{id}", self.prefix))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(CLAIM_TTL.as_secs())
+            .query_async(&mut self.connection.clone())
+            .await;
+        match claimed {
+            Ok(reply) => reply.is_some(),
+            Err(err) => {
+                warn!("failed to claim fill {id} in Redis: {err:?}");
+                true
+            }
+        }
+    }
+
+    /// Drops the claims on `ids` and returns how many there were.
+    pub async fn release(&self, ids: &[String]) -> anyhow::Result<usize> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let mut command = redis::cmd("DEL");
+        for id in ids {
+            command.arg(format!("{}:fill:{id}", self.prefix));
+        }
+        Ok(command.query_async(&mut self.connection.clone()).await?)
+    }
+}
+
+/// Ids of the fills this instance received lately, so a fill delivered twice,
+/// such as by overlapping connections around a reconnection or a repeated
+/// snapshot, is handled once. The oldest ids are forgotten once `capacity`
+/// are remembered.
+pub struct RecentFills {
+    capacity: usize,
+    order: VecDeque<String>,
+    ids: HashSet<String>,
+}
+
+impl RecentFills {
+    pub fn new(capacity: usize) -> Self {
+        RecentFills {
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            ids: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// From `FILL_DEDUP_CAPACITY` (default: 10000); 0 disables it.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let capacity = match crate::tenants::var("FILL_DEDUP_CAPACITY") {
+            Ok(value) => value.parse()?,
+            Err(_) => 10_000,
+        };
+        Ok(RecentFills::new(capacity))
+    }
+
+    /// Returns whether `id` was not seen lately, and remembers it.
+    pub fn insert(&mut self, id: String) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        if !self.ids.insert(id.clone()) {
+            return false;
+        }
+        self.order.push_back(id);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use ethers::types::H160;
+
+use crate::clusters::Clusters;
+use crate::format_usd;
+use crate::store::StoredFill;
+
+/// Coins and addresses listed before the rest are counted in one line.
+const SHOWN_COINS: usize = 5;
+const SHOWN_ADDRESSES: usize = 10;
+
+#[derive(Default)]
+struct Activity {
+    trades: usize,
+    volume: f64,
+    realized_pnl: f64,
+    users: HashSet<H160>,
+}
+
+impl Activity {
+    fn record(&mut self, fill: &StoredFill) {
+        self.users.insert(fill.user);
+        self.trades += 1;
+        self.volume += fill.px * fill.sz;
+        self.realized_pnl += fill.closed_pnl;
+    }
+}
+
+fn busiest<K>(activity: HashMap<K, Activity>) -> Vec<(K, Activity)> {
+    let mut activity: Vec<(K, Activity)> = activity.into_iter().collect();
+    activity.sort_by(|a, b| b.1.volume.total_cmp(&a.1.volume));
+    activity
+}
+
+/// Who a digest line is about: a cluster's addresses together, or one address.
+#[derive(PartialEq, Eq, Hash)]
+enum Entity<'a> {
+    Cluster(&'a str),
+    Address(H160),
+}
+
+/// Daily summary of `fills`: trades, volume and realized PnL in total, per
+/// coin and per address, for following the vault without every fill, with
+/// the change in unrealized PnL over the same period when it is known.
+/// Addresses show as their label when they have one, and the members of a
+/// cluster as one line under its name.
+pub fn render(
+    title: &str,
+    fills: &[StoredFill],
+    labels: &BTreeMap<H160, String>,
+    clusters: &Clusters,
+    unrealized_change: Option<f64>,
+) -> String {
+    let mut total = Activity::default();
+    let mut coins: HashMap<&str, Activity> = HashMap::new();
+    let mut addresses: HashMap<Entity, Activity> = HashMap::new();
+    for fill in fills {
+        total.record(fill);
+        coins.entry(&fill.coin).or_default().record(fill);
+        let entity = match clusters.of(fill.user) {
+            Some(cluster) => Entity::Cluster(cluster),
+            None => Entity::Address(fill.user),
+        };
+        addresses.entry(entity).or_default().record(fill);
+    }
+
+    let mut content = format!(
+        "**{title} daily digest**\nTrades: {} by {} addresses, volume {}, realized PnL {}",
+        total.trades,
+        total.users.len(),
+        format_usd(total.volume),
+        format_usd(total.realized_pnl)
+    );
+    if let Some(change) = unrealized_change {
+        content.push_str(&format!(", unrealized PnL change {}", format_usd(change)));
+    }
+    if total.trades == 0 {
+        return content;
+    }
+
+    let coins = busiest(coins);
+    let top_coins: Vec<String> = coins
+        .iter()
+        .take(SHOWN_COINS)
+        .map(|(coin, activity)| {
+            format!(
+                "{coin} {} ({} trades)",
+                format_usd(activity.volume),
+                activity.trades
+            )
+        })
+        .collect();
+    content.push_str(&format!("\nTop coins: {}", top_coins.join(", ")));
+
+    let addresses = busiest(addresses);
+    for (entity, activity) in addresses.iter().take(SHOWN_ADDRESSES) {
+        let name = match entity {
+            Entity::Cluster(cluster) => format!("{cluster} ({} wallets)", activity.users.len()),
+            Entity::Address(user) => labels
+                .get(user)
+                .cloned()
+                .unwrap_or_else(|| format!("{user:?}")),
+        };
+        content.push_str(&format!(
+            "\n- {name}: {} trades, volume {}, realized PnL {}",
+            activity.trades,
+            format_usd(activity.volume),
+            format_usd(activity.realized_pnl)
+        ));
+    }
+    if addresses.len() > SHOWN_ADDRESSES {
+        content.push_str(&format!(
+            "\n- and {} more",
+            addresses.len() - SHOWN_ADDRESSES
+        ));
+    }
+
+    let notes: Vec<String> = fills.iter().filter_map(StoredFill::render_note).collect();
+    if !notes.is_empty() {
+        content.push_str(&format!("\nNotes:\n{}", notes.join("\n")));
+    }
+    content
+}
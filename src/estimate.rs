@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+use ethers::types::H160;
+use hyperliquid_rust_sdk::InfoClient;
+use tracing::warn;
+
+use crate::batching;
+use crate::categories::Categories;
+use crate::filters::{CoinFilter, NotionalFilter};
+use crate::hyperliquid::{spot, vault};
+use crate::mutes::Mutes;
+use crate::parse_addresses;
+use crate::routes::Routes;
+use crate::tenants;
+
+/// `userFills` returns at most this many of an address's latest fills.
+const USER_FILLS_LIMIT: usize = 2000;
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// How much a vault would have posted over a past window with the current
+/// mutes, routes and exclusions.
+pub struct Estimate {
+    pub vault_name: String,
+    pub days: u64,
+    pub addresses: usize,
+    pub fills: usize,
+    /// Fills of muted or filtered coins.
+    pub muted: usize,
+    /// Fills under the minimum notional, dropped or summed up per batch.
+    pub small: usize,
+    /// Messages per notifier: one per batch interval and route with fills.
+    pub notifications: usize,
+    pub busiest_day: usize,
+    /// Addresses whose fills reach back less than the window because of the
+    /// `userFills` limit.
+    pub truncated: Vec<H160>,
+}
+
+/// The settings that decide which fills the watcher would notify, and where.
+pub struct Filters {
+    pub excluded_addresses: Vec<H160>,
+    pub mutes: Mutes,
+    pub routes: Routes,
+    pub notional: NotionalFilter,
+    pub coins: CoinFilter,
+    pub batch_interval: Duration,
+}
+
+impl Filters {
+    /// Read from the same environment variables and files as the watcher's,
+    /// with the config file's coin categories.
+    pub fn from_env(categories: &Categories) -> anyhow::Result<Self> {
+        let excluded_addresses = match tenants::var("EXCLUDED_ADDRESSES") {
+            Ok(value) => parse_addresses(&value)?,
+            Err(_) => Vec::new(),
+        };
+        let mutes = Mutes::load(
+            tenants::var("MUTES_PATH")
+                .unwrap_or_else(|_| "mutes.json".to_string())
+                .into(),
+        )?;
+        let routes = Routes::load(
+            tenants::var("ROUTES_PATH")
+                .unwrap_or_else(|_| "routes.json".to_string())
+                .into(),
+        )?
+        .with_categories(categories.clone());
+        Ok(Filters {
+            excluded_addresses,
+            mutes,
+            routes,
+            notional: NotionalFilter::from_env()?,
+            coins: CoinFilter::from_env(categories)?,
+            batch_interval: batching::interval_from_env()?,
+        })
+    }
+}
+
+pub async fn estimate(
+    info_client: &InfoClient,
+    vault_address: H160,
+    days: u64,
+    filters: &Filters,
+) -> anyhow::Result<Estimate> {
+    let details = vault::VaultApi::new(info_client)
+        .details(&format!("{vault_address:?}"))
+        .await?;
+    let spot_names = match spot::SpotNames::fetch(info_client).await {
+        Ok(spot_names) => spot_names,
+        Err(err) => {
+            warn!("failed to fetch spot metadata: {err:?}");
+            spot::SpotNames::default()
+        }
+    };
+    let since = crate::now_ms().saturating_sub(days * DAY_MS);
+    let batch_ms = (filters.batch_interval.as_millis() as u64).max(1);
+
+    let users: Vec<H160> = details
+        .child_addresses
+        .into_iter()
+        .filter(|user| !filters.excluded_addresses.contains(user))
+        .collect();
+    let mut fills = 0;
+    let mut muted = 0;
+    let mut small = 0;
+    let mut truncated = Vec::new();
+    let mut batches: HashSet<(u64, Option<&str>)> = HashSet::new();
+    for user in &users {
+        let user_fills = info_client.user_fills(*user).await?;
+        if user_fills.len() >= USER_FILLS_LIMIT && user_fills.iter().all(|fill| fill.time > since) {
+            truncated.push(*user);
+        }
+        for fill in user_fills.iter().filter(|fill| fill.time >= since) {
+            let coin = spot_names.resolve(&fill.coin);
+            if filters.mutes.is_muted(&coin) || !filters.coins.allows(&coin) {
+                muted += 1;
+                continue;
+            }
+            let notional = fill.px.parse::<f64>().unwrap_or_default()
+                * fill.sz.parse::<f64>().unwrap_or_default();
+            if filters.notional.is_small(&coin, notional) {
+                small += 1;
+                if !filters.notional.summarize {
+                    continue;
+                }
+            } else {
+                fills += 1;
+            }
+            batches.insert((fill.time / batch_ms, filters.routes.get(&coin)));
+        }
+    }
+
+    let mut per_day: BTreeMap<u64, usize> = BTreeMap::new();
+    for (batch, _) in &batches {
+        *per_day.entry(batch * batch_ms / DAY_MS).or_default() += 1;
+    }
+    Ok(Estimate {
+        vault_name: details.name,
+        days,
+        addresses: users.len(),
+        fills,
+        muted,
+        small,
+        notifications: batches.len(),
+        busiest_day: per_day.values().copied().max().unwrap_or_default(),
+        truncated,
+    })
+}
+
+impl Estimate {
+    pub fn render(&self) -> String {
+        let mut content = format!(
+            "{} over the last {} days across {} addresses:\n\
+             Fills: {} notified, {} muted or filtered, {} under the minimum notional\n\
+             Notifications per sink: {} (~{:.1} per day, {} on the busiest day)",
+            self.vault_name,
+            self.days,
+            self.addresses,
+            self.fills,
+            self.muted,
+            self.small,
+            self.notifications,
+            self.notifications as f64 / self.days.max(1) as f64,
+            self.busiest_day
+        );
+        if !self.truncated.is_empty() {
+            content.push_str(&format!(
+                "\nOnly the latest {USER_FILLS_LIMIT} fills are available for {} addresses, so the real volume is higher: {:?}",
+                self.truncated.len(),
+                self.truncated
+            ));
+        }
+        content
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::categories::{self, Categories};
+use crate::tenants;
+
+/// Fills below a USD notional, overall or per coin, are left out of
+/// notifications, either silently or summed up into one line per batch.
+pub struct NotionalFilter {
+    default_usd: f64,
+    by_coin: HashMap<String, f64>,
+    pub summarize: bool,
+}
+
+impl NotionalFilter {
+    /// From `MIN_NOTIONAL_USD`, `MIN_NOTIONAL_BY_COIN` (`BTC=100000,DOGE=500`)
+    /// and `MIN_NOTIONAL_MODE` (`drop` or `summarize`).
+    pub fn from_env() -> anyhow::Result<Self> {
+        let default_usd = match tenants::var("MIN_NOTIONAL_USD") {
+            Ok(value) => value.parse()?,
+            Err(_) => 0.0,
+        };
+        let mut by_coin = HashMap::new();
+        if let Ok(value) = tenants::var("MIN_NOTIONAL_BY_COIN") {
+            for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                let (coin, usd) = entry
+                    .split_once('=')
+                    .ok_or_else(|| anyhow::anyhow!("expected COIN=USD, got {entry:?}"))?;
+                by_coin.insert(coin.trim().to_uppercase(), usd.trim().parse()?);
+            }
+        }
+        let summarize = match tenants::var("MIN_NOTIONAL_MODE").as_deref() {
+            Ok("summarize") => true,
+            Ok("drop") | Err(_) => false,
+            Ok(other) => return Err(anyhow::anyhow!("unknown MIN_NOTIONAL_MODE {other:?}")),
+        };
+        Ok(NotionalFilter {
+            default_usd,
+            by_coin,
+            summarize,
+        })
+    }
+
+    pub fn is_small(&self, coin: &str, notional_usd: f64) -> bool {
+        let threshold = self
+            .by_coin
+            .get(&coin.to_uppercase())
+            .copied()
+            .unwrap_or(self.default_usd);
+        notional_usd < threshold
+    }
+}
+
+/// Coins notifications are limited to, or kept from, as configured.
+#[derive(Default)]
+pub struct CoinFilter {
+    allowed: Option<HashSet<String>>,
+    blocked: HashSet<String>,
+}
+
+/// Comma-separated coins, where `category:<NAME>` stands for the category's coins.
+fn coin_set(value: &str, categories: &Categories) -> anyhow::Result<HashSet<String>> {
+    let mut coins = HashSet::new();
+    for entry in value
+        .split(',')
+        .map(str::trim)
+        .filter(|coin| !coin.is_empty())
+    {
+        match categories::reference(entry) {
+            Some(name) => coins.extend(
+                categories
+                    .coins(name)
+                    .ok_or_else(|| anyhow::anyhow!("unknown coin category {name:?}"))?
+                    .iter()
+                    .map(|coin| coin.to_uppercase()),
+            ),
+            None => {
+                coins.insert(entry.to_uppercase());
+            }
+        }
+    }
+    Ok(coins)
+}
+
+impl CoinFilter {
+    /// From `COIN_ALLOWLIST` and `COIN_BLOCKLIST`, comma-separated.
+    pub fn from_env(categories: &Categories) -> anyhow::Result<Self> {
+        Ok(CoinFilter {
+            allowed: match tenants::var("COIN_ALLOWLIST") {
+                Ok(value) => Some(coin_set(&value, categories)?),
+                Err(_) => None,
+            },
+            blocked: match tenants::var("COIN_BLOCKLIST") {
+                Ok(value) => coin_set(&value, categories)?,
+                Err(_) => HashSet::new(),
+            },
+        })
+    }
+
+    pub fn allows(&self, coin: &str) -> bool {
+        let coin = coin.to_uppercase();
+        !self.blocked.contains(&coin)
+            && self
+                .allowed
+                .as_ref()
+                .is_none_or(|allowed| allowed.contains(&coin))
+    }
+}
+
+/// What a batch keeps when more fills arrived than the sinks can take in one
+/// go: fills of at least `min_notional_usd` keep their own line and the rest
+/// are summed up per coin.
+pub struct Backpressure {
+    max_fills: usize,
+    min_notional_usd: f64,
+}
+
+impl Backpressure {
+    /// From `BACKPRESSURE_MAX_FILLS`, off without it, and
+    /// `BACKPRESSURE_MIN_NOTIONAL_USD` (default 10000).
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let Ok(max_fills) = tenants::var("BACKPRESSURE_MAX_FILLS") else {
+            return Ok(None);
+        };
+        let min_notional_usd = match tenants::var("BACKPRESSURE_MIN_NOTIONAL_USD") {
+            Ok(value) => value.parse()?,
+            Err(_) => 10_000.0,
+        };
+        Ok(Some(Backpressure {
+            max_fills: max_fills.parse()?,
+            min_notional_usd,
+        }))
+    }
+
+    pub fn is_saturated(&self, fills: usize) -> bool {
+        fills > self.max_fills
+    }
+
+    pub fn min_notional_usd(&self) -> f64 {
+        self.min_notional_usd
+    }
+}
@@ -0,0 +1,26 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::now_ms;
+
+/// When a WebSocket message last arrived, on the `allMids` stream, which
+/// ticks every block, or on any address's connection, pongs included.
+#[derive(Clone, Default)]
+pub struct Heartbeat {
+    last_ms: Arc<AtomicU64>,
+}
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        self.last_ms.store(now_ms(), Ordering::Relaxed);
+    }
+
+    /// Time since the last message, or `None` before the first one.
+    pub fn age(&self) -> Option<Duration> {
+        match self.last_ms.load(Ordering::Relaxed) {
+            0 => None,
+            last_ms => Some(Duration::from_millis(now_ms().saturating_sub(last_ms))),
+        }
+    }
+}
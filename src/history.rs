@@ -0,0 +1,133 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use ethers::types::H160;
+use hyperliquid_rust_sdk::{InfoClient, UserFillsResponse};
+use serde::Serialize;
+
+/// `userFillsByTime` returns at most this many fills per request.
+const FILLS_PER_PAGE: usize = 2000;
+
+/// `HISTORY_PATH`, or `history.json`.
+pub fn path_from_env() -> PathBuf {
+    crate::tenants::var("HISTORY_PATH")
+        .unwrap_or_else(|_| "history.json".to_string())
+        .into()
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct UserFillsByTimeRequest {
+    #[serde(rename = "type")]
+    type_: String,
+    user: H160,
+    start_time: u64,
+}
+
+/// Fetches the coins `user` traded, paging through its fills from the start
+/// rather than only its latest 2000 as `userFills` does. Hyperliquid keeps
+/// only an address's latest 10000 fills, so coins traded solely before those
+/// are missing.
+pub async fn fetch_traded_coins(
+    info_client: &InfoClient,
+    user: H160,
+) -> anyhow::Result<HashSet<String>> {
+    let mut coins = HashSet::new();
+    let mut start_time = 0;
+    loop {
+        let req = UserFillsByTimeRequest {
+            type_: "userFillsByTime".to_string(),
+            user,
+            start_time,
+        };
+        let payload = info_client
+            .http_client
+            .post("/info", serde_json::to_string(&req)?)
+            .await?;
+        let fills: Vec<UserFillsResponse> = serde_json::from_str(&payload)?;
+        let Some(latest) = fills.iter().map(|fill| fill.time).max() else {
+            break;
+        };
+        let full_page = fills.len() >= FILLS_PER_PAGE;
+        coins.extend(fills.into_iter().map(|fill| fill.coin));
+        if !full_page {
+            break;
+        }
+        start_time = latest + 1;
+    }
+    Ok(coins)
+}
+
+/// Coins each watched address has ever traded, persisted as JSON.
+pub struct TradeHistory {
+    path: PathBuf,
+    coins: HashMap<H160, HashSet<String>>,
+    /// Addresses whose history is being fetched.
+    seeding: HashSet<H160>,
+}
+
+impl TradeHistory {
+    pub fn load(path: PathBuf) -> anyhow::Result<Self> {
+        let coins = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(TradeHistory {
+            path,
+            coins,
+            seeding: HashSet::new(),
+        })
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        fs::write(&self.path, serde_json::to_string(&self.coins)?)?;
+        Ok(())
+    }
+
+    /// Whether `user` still needs its history fetched, claiming the fetch so
+    /// it starts once; `seed` or `seed_failed` ends the claim.
+    pub fn start_seeding(&mut self, user: H160) -> bool {
+        !self.coins.contains_key(&user) && self.seeding.insert(user)
+    }
+
+    /// Seeds the history of an address seen for the first time.
+    pub fn seed(
+        &mut self,
+        user: H160,
+        coins: impl IntoIterator<Item = String>,
+    ) -> anyhow::Result<()> {
+        self.seeding.remove(&user);
+        self.coins.entry(user).or_default().extend(coins);
+        self.save()
+    }
+
+    /// Gives up seeding `user` for now; its next fill tries again.
+    pub fn seed_failed(&mut self, user: H160) {
+        self.seeding.remove(&user);
+    }
+
+    /// Forgets `user`; returns how many coins were remembered for it.
+    pub fn purge(&mut self, user: H160) -> anyhow::Result<usize> {
+        let Some(coins) = self.coins.remove(&user) else {
+            return Ok(0);
+        };
+        self.save()?;
+        Ok(coins.len())
+    }
+
+    /// Records a traded coin and returns true if the address never traded it
+    /// before. Addresses not seeded yet are left alone, as every coin would
+    /// look new; their seed covers the fill.
+    pub fn record(&mut self, user: H160, coin: &str) -> anyhow::Result<bool> {
+        let Some(coins) = self.coins.get_mut(&user) else {
+            return Ok(false);
+        };
+        if !coins.insert(coin.to_string()) {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+}
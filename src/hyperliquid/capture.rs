@@ -0,0 +1,148 @@
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use ethers::types::H160;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tracing::warn;
+
+use crate::now_ms;
+
+/// Rewrites every capture file in `dir` without the payloads received for
+/// `user`, returning how many were removed. Captures still being written by a
+/// running watcher are not safe to purge.
+pub fn purge(dir: &Path, user: H160) -> anyhow::Result<usize> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err.into()),
+    };
+    let user = json!(user);
+    let mut removed = 0;
+    for entry in entries {
+        let path = entry?.path();
+        if !path.to_string_lossy().ends_with(".jsonl.gz") {
+            continue;
+        }
+        let mut kept = Vec::new();
+        let mut dropped = 0;
+        for line in BufReader::new(GzDecoder::new(File::open(&path)?)).lines() {
+            let line = line?;
+            let value: Value = serde_json::from_str(&line)?;
+            if value.get("user") == Some(&user) {
+                dropped += 1;
+            } else {
+                kept.push(line);
+            }
+        }
+        if dropped == 0 {
+            continue;
+        }
+        let temporary = path.with_extension("tmp");
+        let mut encoder = GzEncoder::new(File::create(&temporary)?, Compression::default());
+        for line in kept {
+            writeln!(encoder, "{line}")?;
+        }
+        encoder.finish()?;
+        fs::rename(&temporary, &path)?;
+        removed += dropped;
+    }
+    Ok(removed)
+}
+
+/// A raw payload as one of the subscriptions received it.
+pub struct RawMessage {
+    pub user: Option<H160>,
+    pub received_ms: u64,
+    pub payload: String,
+}
+
+/// Writes raw payloads as gzip-compressed JSON lines, starting a new file
+/// once the current one has taken `max_bytes` and keeping the newest `keep`.
+pub struct CaptureWriter {
+    dir: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    current: Option<(GzEncoder<File>, u64)>,
+}
+
+impl CaptureWriter {
+    pub fn new(dir: PathBuf, max_bytes: u64, keep: usize) -> anyhow::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(CaptureWriter {
+            dir,
+            max_bytes,
+            keep,
+            current: None,
+        })
+    }
+
+    fn write(&mut self, message: &RawMessage) -> anyhow::Result<()> {
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|(_, written)| *written >= self.max_bytes)
+        {
+            self.rotate()?;
+        }
+        if self.current.is_none() {
+            let path = self.dir.join(format!("capture-{}.jsonl.gz", now_ms()));
+            self.current = Some((
+                GzEncoder::new(File::create(path)?, Compression::default()),
+                0,
+            ));
+            self.prune()?;
+        }
+
+        let line = json!({
+            "received_ms": message.received_ms,
+            "user": message.user,
+            "payload": message.payload,
+        })
+        .to_string();
+        if let Some((encoder, written)) = &mut self.current {
+            writeln!(encoder, "{line}")?;
+            *written += line.len() as u64 + 1;
+        }
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> anyhow::Result<()> {
+        if let Some((encoder, _)) = self.current.take() {
+            encoder.finish()?;
+        }
+        Ok(())
+    }
+
+    fn prune(&self) -> anyhow::Result<()> {
+        let mut files: Vec<PathBuf> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("capture-") && name.ends_with(".jsonl.gz"))
+            })
+            .collect();
+        files.sort();
+        let excess = files.len().saturating_sub(self.keep);
+        for path in &files[..excess] {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    pub async fn run(mut self, mut messages: UnboundedReceiver<RawMessage>) {
+        while let Some(message) = messages.recv().await {
+            if let Err(err) = self.write(&message) {
+                warn!("failed to write captured message: {err:?}");
+            }
+        }
+        if let Err(err) = self.rotate() {
+            warn!("failed to finish capture file: {err:?}");
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use hyperliquid_rust_sdk::InfoClient;
+use tracing::warn;
+
+use crate::config::Network;
+use crate::tenants;
+
+/// Hyperliquid API endpoints of one network: the primary first, then mirrors
+/// in other regions. Clients are created for the active one, which moves on
+/// when it stops answering or lags.
+#[derive(Clone)]
+pub struct Endpoints {
+    network: Network,
+    urls: Arc<Vec<String>>,
+    active: Arc<AtomicUsize>,
+}
+
+impl Endpoints {
+    /// `HYPERLIQUID_API_URLS`, comma-separated, or the network's public API
+    /// alone.
+    pub fn from_env(network: Network) -> anyhow::Result<Self> {
+        let urls: Vec<String> = match tenants::var("HYPERLIQUID_API_URLS") {
+            Ok(value) => value
+                .split(',')
+                .map(|url| url.trim().trim_end_matches('/'))
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Err(_) => vec![network.api_url().to_string()],
+        };
+        if urls.is_empty() {
+            return Err(anyhow::anyhow!("HYPERLIQUID_API_URLS lists no endpoints"));
+        }
+        // The SDK derives the WebSocket url by swapping `http` for `ws`.
+        if let Some(url) = urls.iter().find(|url| !url.starts_with("http")) {
+            return Err(anyhow::anyhow!(
+                "endpoint {url:?} must start with http:// or https://"
+            ));
+        }
+        Ok(Endpoints {
+            network,
+            urls: Arc::new(urls),
+            active: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    pub fn active(&self) -> &str {
+        &self.urls[self.active.load(Ordering::Relaxed)]
+    }
+
+    /// The active endpoint's WebSocket, derived the way the SDK does.
+    pub fn ws_url(&self) -> String {
+        format!("ws{}/ws", &self.active()[4..])
+    }
+
+    pub fn has_fallbacks(&self) -> bool {
+        self.urls.len() > 1
+    }
+
+    /// A client for the active endpoint; its WebSocket goes to the same host.
+    pub async fn client(&self) -> Result<InfoClient, hyperliquid_rust_sdk::Error> {
+        self.client_for(self.active()).await
+    }
+
+    async fn client_for(&self, url: &str) -> Result<InfoClient, hyperliquid_rust_sdk::Error> {
+        let mut client = InfoClient::new(None, Some(self.network.base_url())).await?;
+        client.http_client.base_url = url.to_string();
+        Ok(client)
+    }
+
+    /// How long `url` takes to answer `allMids`, or `None` when it fails or
+    /// takes longer than `max_latency`.
+    pub async fn probe(&self, url: &str, max_latency: Duration) -> Option<Duration> {
+        let client = self.client_for(url).await.ok()?;
+        let started = Instant::now();
+        match tokio::time::timeout(max_latency, client.all_mids()).await {
+            Ok(Ok(_)) => Some(started.elapsed()),
+            Ok(Err(err)) => {
+                warn!("endpoint {url} failed: {err:?}");
+                None
+            }
+            Err(_) => {
+                warn!("endpoint {url} did not answer within {max_latency:?}");
+                None
+            }
+        }
+    }
+
+    /// Makes the first endpoint after the active one that answers within
+    /// `max_latency` the active one, and returns it; `None` when none does.
+    pub async fn fail_over(&self, max_latency: Duration) -> Option<String> {
+        let active = self.active.load(Ordering::Relaxed);
+        for offset in 1..self.urls.len() {
+            let index = (active + offset) % self.urls.len();
+            if self.probe(&self.urls[index], max_latency).await.is_some() {
+                self.active.store(index, Ordering::Relaxed);
+                return Some(self.urls[index].clone());
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,59 @@
+use ethers::types::H160;
+use serde::Serialize;
+
+use crate::hyperliquid::fills::Fill;
+
+pub const VENUE: &str = "hyperliquid";
+
+/// Venue-neutral description of a fill for downstream consumers.
+#[derive(Clone, Debug, Serialize)]
+pub struct FillEvent {
+    pub venue: &'static str,
+    pub user: H160,
+    pub coin: String,
+    pub side: &'static str,
+    pub px: f64,
+    pub sz: f64,
+    pub time: u64,
+    pub dir: String,
+    pub closed_pnl: f64,
+    pub fee: f64,
+    pub oid: u64,
+    pub hash: String,
+    /// Page of the fill on the watcher's HTTP server, when it is public.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permalink: Option<String>,
+}
+
+impl FillEvent {
+    pub fn new(user: H160, fill: &Fill) -> Self {
+        FillEvent {
+            venue: VENUE,
+            user,
+            coin: fill.coin.clone(),
+            side: if fill.side == "B" { "buy" } else { "sell" },
+            px: fill.px.parse().unwrap_or_default(),
+            sz: fill.sz.parse().unwrap_or_default(),
+            time: fill.time,
+            dir: fill.dir.clone(),
+            closed_pnl: fill.closed_pnl.parse().unwrap_or_default(),
+            fee: fill.fee.parse().unwrap_or_default(),
+            oid: fill.oid,
+            hash: fill.hash.clone(),
+            permalink: None,
+        }
+    }
+
+    /// `Long` for buys and `Short` for sells, as `side_label` shows raw sides.
+    pub fn side_label(&self) -> &'static str {
+        if self.side == "buy" {
+            "Long"
+        } else {
+            "Short"
+        }
+    }
+
+    pub fn subject(&self) -> String {
+        format!("fills.{}.{}", self.venue, self.coin)
+    }
+}
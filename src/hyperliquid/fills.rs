@@ -0,0 +1,66 @@
+use ethers::types::H160;
+use hyperliquid_rust_sdk::InfoClient;
+use serde::{Deserialize, Serialize};
+
+/// A fill as Hyperliquid sends it over `userEvents` and from `userFills`.
+///
+/// The SDK's structs leave out the trade id, which is all that tells apart
+/// two fills of one order at the same price and size in the same
+/// transaction.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fill {
+    pub coin: String,
+    pub side: String,
+    pub px: String,
+    pub sz: String,
+    pub time: u64,
+    pub hash: String,
+    pub start_position: String,
+    pub dir: String,
+    pub closed_pnl: String,
+    pub oid: u64,
+    pub crossed: bool,
+    #[serde(default)]
+    pub fee: String,
+    /// Zero in archives written before trade ids were kept.
+    #[serde(default)]
+    pub tid: u64,
+}
+
+/// What a `user` channel message brought.
+pub enum UserEvent {
+    Fills(Vec<Fill>),
+    /// A message with fills that did not parse, as received.
+    Unparsed(String),
+}
+
+/// The fills in the `data` of a `user` channel message, or `None` for the
+/// events without fills, such as funding.
+pub fn parse_user_event(data: &serde_json::Value, payload: &str) -> Option<UserEvent> {
+    let fills = data.get("fills")?;
+    Some(match serde_json::from_value(fills.clone()) {
+        Ok(fills) => UserEvent::Fills(fills),
+        Err(_) => UserEvent::Unparsed(payload.to_string()),
+    })
+}
+
+#[derive(Serialize)]
+struct UserFillsRequest {
+    #[serde(rename = "type")]
+    type_: String,
+    user: H160,
+}
+
+/// The latest 2000 fills of `user`, newest first.
+pub async fn fetch_user_fills(info_client: &InfoClient, user: H160) -> anyhow::Result<Vec<Fill>> {
+    let req = UserFillsRequest {
+        type_: "userFills".to_string(),
+        user,
+    };
+    let payload = info_client
+        .http_client
+        .post("/info", serde_json::to_string(&req)?)
+        .await?;
+    Ok(serde_json::from_str(&payload)?)
+}
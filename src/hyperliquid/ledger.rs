@@ -0,0 +1,150 @@
+use ethers::types::H160;
+use hyperliquid_rust_sdk::InfoClient;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LedgerUpdatesRequest {
+    #[serde(rename = "type")]
+    type_: String,
+    user: H160,
+    start_time: u64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerDelta {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub usdc: Option<String>,
+    pub net_withdrawn_usd: Option<String>,
+    /// Set on `liquidation` updates, as are the fields below.
+    pub liquidated_ntl_pos: Option<String>,
+    pub account_value: Option<String>,
+    pub leverage_type: Option<String>,
+    #[serde(default)]
+    pub liquidated_positions: Vec<LiquidatedPosition>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LiquidatedPosition {
+    pub coin: String,
+    pub szi: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LedgerUpdate {
+    pub time: u64,
+    pub delta: LedgerDelta,
+}
+
+impl LedgerUpdate {
+    /// USD amount moved by the update; vault withdrawals report it as
+    /// `netWithdrawnUsd` instead of `usdc`.
+    pub fn usdc(&self) -> f64 {
+        self.delta
+            .usdc
+            .as_deref()
+            .or(self.delta.net_withdrawn_usd.as_deref())
+            .and_then(|usdc| usdc.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// Describes a `liquidation` update, or returns `None` for other kinds.
+    pub fn render_liquidation(&self, user: H160) -> Option<String> {
+        if self.delta.type_ != "liquidation" {
+            return None;
+        }
+        let parse = |value: &Option<String>| {
+            value
+                .as_deref()
+                .and_then(|value| value.parse::<f64>().ok())
+                .unwrap_or_default()
+        };
+        let positions = self
+            .delta
+            .liquidated_positions
+            .iter()
+            .map(|position| format!("{} {}", position.szi, position.coin))
+            .collect::<Vec<String>>()
+            .join(", ");
+        Some(format!(
+            "Liquidation: {user:?} lost ${:.2} of {} positions ({positions}), account value ${:.2}",
+            parse(&self.delta.liquidated_ntl_pos),
+            self.delta.leverage_type.as_deref().unwrap_or("unknown"),
+            parse(&self.delta.account_value)
+        ))
+    }
+}
+
+/// Deposits and withdrawals into a vault by its followers.
+#[derive(Default)]
+pub struct VaultFlows {
+    pub deposits: f64,
+    pub withdrawals: f64,
+}
+
+impl VaultFlows {
+    pub fn record(&mut self, update: &LedgerUpdate) {
+        match update.delta.type_.as_str() {
+            "vaultDeposit" => self.deposits += update.usdc(),
+            "vaultWithdraw" => self.withdrawals += update.usdc(),
+            _ => (),
+        }
+    }
+
+    pub fn net(&self) -> f64 {
+        self.deposits - self.withdrawals
+    }
+}
+
+/// Fetches non-funding ledger updates (deposits, withdrawals, transfers,
+/// liquidations) of `user` at or after `start_time` in milliseconds.
+pub async fn fetch_updates(
+    info_client: &InfoClient,
+    user: H160,
+    start_time: u64,
+) -> anyhow::Result<Vec<LedgerUpdate>> {
+    let req = LedgerUpdatesRequest {
+        type_: "userNonFundingLedgerUpdates".to_string(),
+        user,
+        start_time,
+    };
+    let payload = info_client
+        .http_client
+        .post("/info", serde_json::to_string(&req)?)
+        .await?;
+    Ok(serde_json::from_str(&payload)?)
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct FundingDelta {
+    pub coin: String,
+    /// Signed: negative when the position paid funding.
+    pub usdc: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct FundingPayment {
+    pub time: u64,
+    pub delta: FundingDelta,
+}
+
+/// Fetches the funding payments of `user` at or after `start_time` in
+/// milliseconds.
+pub async fn fetch_funding(
+    info_client: &InfoClient,
+    user: H160,
+    start_time: u64,
+) -> anyhow::Result<Vec<FundingPayment>> {
+    let req = LedgerUpdatesRequest {
+        type_: "userFunding".to_string(),
+        user,
+        start_time,
+    };
+    let payload = info_client
+        .http_client
+        .post("/info", serde_json::to_string(&req)?)
+        .await?;
+    Ok(serde_json::from_str(&payload)?)
+}
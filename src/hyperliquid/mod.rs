@@ -0,0 +1,16 @@
+//! Hyperliquid data: subscriptions or polling, vaults, positions, prices and
+//! ledgers.
+
+pub mod capture;
+pub mod endpoints;
+pub mod events;
+pub mod fills;
+pub mod ledger;
+pub mod order_updates;
+pub mod orders;
+pub mod polling;
+pub mod positions;
+pub mod prices;
+pub mod spot;
+pub mod subscriptions;
+pub mod vault;
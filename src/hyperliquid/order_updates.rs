@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use ethers::types::H160;
+use serde::Deserialize;
+
+use crate::side_label;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BasicOrder {
+    pub coin: String,
+    pub side: String,
+    pub limit_px: String,
+    pub sz: String,
+    pub oid: u64,
+    pub timestamp: u64,
+    pub orig_sz: String,
+}
+
+/// One entry of the `orderUpdates` channel, which the SDK does not know.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderUpdate {
+    pub order: BasicOrder,
+    /// Such as `open`, `filled`, `canceled` or `marginCanceled`.
+    pub status: String,
+    pub status_timestamp: u64,
+}
+
+pub enum OrderChange {
+    Placed,
+    Modified,
+    Canceled,
+}
+
+/// Resting orders seen open, to tell a modification from a new order. Orders
+/// resting from before the watcher started show as placed when modified.
+#[derive(Default)]
+pub struct OpenOrders {
+    open: HashMap<(H160, u64), (String, String)>,
+}
+
+impl OpenOrders {
+    /// What `update` did to `user`'s resting orders; `None` for fills, which
+    /// are notified as such, and for anything else.
+    pub fn classify(&mut self, user: H160, update: &OrderUpdate) -> Option<OrderChange> {
+        let key = (user, update.order.oid);
+        let order = &update.order;
+        match update.status.as_str() {
+            "open" => {
+                let terms = (order.sz.clone(), order.limit_px.clone());
+                match self.open.insert(key, terms.clone()) {
+                    None => Some(OrderChange::Placed),
+                    Some(previous) if previous != terms => Some(OrderChange::Modified),
+                    Some(_) => None,
+                }
+            }
+            status if status.to_lowercase().contains("cancel") => {
+                self.open.remove(&key);
+                Some(OrderChange::Canceled)
+            }
+            _ => {
+                self.open.remove(&key);
+                None
+            }
+        }
+    }
+}
+
+/// Such as `Order placed: whale: Long BTC 0.5 @ 65000`.
+pub fn render(change: &OrderChange, update: &OrderUpdate, label: Option<&str>) -> String {
+    let order = &update.order;
+    let what = match change {
+        OrderChange::Placed => "Order placed",
+        OrderChange::Modified => "Order modified",
+        OrderChange::Canceled => "Order canceled",
+    };
+    let mut line = format!(
+        "{} {} {} @ {}",
+        side_label(&order.side),
+        order.coin,
+        order.sz,
+        order.limit_px
+    );
+    if let Some(label) = label {
+        line = format!("{label}: {line}");
+    }
+    if !matches!(update.status.as_str(), "open" | "canceled") {
+        line.push_str(&format!(" ({})", update.status));
+    }
+    format!("{what}: {line}")
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use ethers::types::H160;
+use hyperliquid_rust_sdk::InfoClient;
+use serde::{Deserialize, Serialize};
+
+use crate::hyperliquid::fills::Fill;
+use crate::tenants;
+
+/// Orders that never complete (e.g. cancelled after a partial fill) are
+/// forgotten after this long.
+const ORDER_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// What becomes of an order's fill lines once it is fully filled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OrderSummaries {
+    /// No summaries.
+    #[default]
+    Off,
+    /// A summary after the per-fill lines.
+    Add,
+    /// A summary in place of the per-fill lines.
+    Replace,
+    /// One running-total message per order, edited as it fills.
+    Live,
+}
+
+impl OrderSummaries {
+    /// From `ORDER_SUMMARIES`, `off`, `add`, `replace` or `live` (default `off`).
+    pub fn from_env() -> anyhow::Result<Self> {
+        match tenants::var("ORDER_SUMMARIES") {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(OrderSummaries::Off),
+        }
+    }
+
+    /// Whether the per-fill lines are left out.
+    pub fn replaces_fills(self) -> bool {
+        matches!(self, OrderSummaries::Replace | OrderSummaries::Live)
+    }
+}
+
+impl FromStr for OrderSummaries {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "off" => Ok(OrderSummaries::Off),
+            "add" => Ok(OrderSummaries::Add),
+            "replace" => Ok(OrderSummaries::Replace),
+            "live" => Ok(OrderSummaries::Live),
+            _ => Err(anyhow::anyhow!(
+                "unknown ORDER_SUMMARIES {value:?}, expected off, add, replace or live"
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct OrderStatusRequest {
+    #[serde(rename = "type")]
+    type_: String,
+    user: H160,
+    oid: u64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct OrderDetails {
+    orig_sz: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct OrderStatusOrder {
+    order: OrderDetails,
+}
+
+#[derive(Deserialize, Debug)]
+struct OrderStatusResponse {
+    order: Option<OrderStatusOrder>,
+}
+
+/// Fetches the original size of an order, if Hyperliquid still knows it.
+pub async fn fetch_order_size(
+    info_client: &InfoClient,
+    user: H160,
+    oid: u64,
+) -> anyhow::Result<Option<f64>> {
+    let req = OrderStatusRequest {
+        type_: "orderStatus".to_string(),
+        user,
+        oid,
+    };
+    let payload = info_client
+        .http_client
+        .post("/info", serde_json::to_string(&req)?)
+        .await?;
+    let res: OrderStatusResponse = serde_json::from_str(&payload)?;
+    Ok(res.order.and_then(|order| order.order.orig_sz.parse().ok()))
+}
+
+struct OrderProgress {
+    coin: String,
+    side: String,
+    size: Option<f64>,
+    filled: f64,
+    notional: f64,
+    updated_at: Instant,
+    message: Option<LiveMessage>,
+}
+
+/// A Discord message showing the running total of an order.
+#[derive(Clone)]
+pub struct LiveMessage {
+    pub webhook_url: String,
+    pub message_id: String,
+}
+
+pub struct CompletedOrder {
+    pub user: H160,
+    pub coin: String,
+    pub side: String,
+    pub size: f64,
+    pub average_px: f64,
+    pub message: Option<LiveMessage>,
+}
+
+pub struct RunningOrder {
+    pub coin: String,
+    pub side: String,
+    pub filled: f64,
+    pub average_px: f64,
+    pub message: Option<LiveMessage>,
+}
+
+/// Accumulates partial fills per order until the order's size is reached.
+#[derive(Default)]
+pub struct OrderTracker {
+    orders: HashMap<(H160, u64), OrderProgress>,
+}
+
+impl OrderTracker {
+    /// Records a fill and returns true when the order's size is still unknown.
+    pub fn record(&mut self, user: H160, fill: &Fill) -> bool {
+        let sz: f64 = fill.sz.parse().unwrap_or_default();
+        let px: f64 = fill.px.parse().unwrap_or_default();
+        let progress = self
+            .orders
+            .entry((user, fill.oid))
+            .or_insert_with(|| OrderProgress {
+                coin: fill.coin.clone(),
+                side: fill.side.clone(),
+                size: None,
+                filled: 0.0,
+                notional: 0.0,
+                updated_at: Instant::now(),
+                message: None,
+            });
+        progress.filled += sz;
+        progress.notional += sz * px;
+        progress.updated_at = Instant::now();
+        progress.size.is_none()
+    }
+
+    pub fn set_size(&mut self, user: H160, oid: u64, size: f64) {
+        if let Some(progress) = self.orders.get_mut(&(user, oid)) {
+            progress.size = Some(size);
+        }
+    }
+
+    pub fn running(&self, user: H160, oid: u64) -> Option<RunningOrder> {
+        let progress = self.orders.get(&(user, oid))?;
+        Some(RunningOrder {
+            coin: progress.coin.clone(),
+            side: progress.side.clone(),
+            filled: progress.filled,
+            average_px: progress.notional / progress.filled,
+            message: progress.message.clone(),
+        })
+    }
+
+    pub fn set_message(&mut self, user: H160, oid: u64, message: LiveMessage) {
+        if let Some(progress) = self.orders.get_mut(&(user, oid)) {
+            progress.message = Some(message);
+        }
+    }
+
+    /// Removes and returns orders whose fills reached their size.
+    pub fn take_completed(&mut self) -> Vec<CompletedOrder> {
+        self.orders
+            .retain(|_, progress| progress.updated_at.elapsed() < ORDER_RETENTION);
+
+        let completed_keys: Vec<(H160, u64)> = self
+            .orders
+            .iter()
+            .filter(|(_, progress)| {
+                // Sizes are decimal strings, so leave room for float rounding.
+                progress
+                    .size
+                    .is_some_and(|size| progress.filled >= size * (1.0 - 1e-9))
+            })
+            .map(|(key, _)| *key)
+            .collect();
+
+        completed_keys
+            .into_iter()
+            .filter_map(|key| {
+                let progress = self.orders.remove(&key)?;
+                Some(CompletedOrder {
+                    user: key.0,
+                    coin: progress.coin,
+                    side: progress.side,
+                    size: progress.filled,
+                    average_px: progress.notional / progress.filled,
+                    message: progress.message,
+                })
+            })
+            .collect()
+    }
+}
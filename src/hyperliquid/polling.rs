@@ -0,0 +1,163 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ethers::types::H160;
+use hyperliquid_rust_sdk::{AllMids, AllMidsData, InfoClient, Message};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+use tracing::warn;
+
+use crate::hyperliquid::endpoints::Endpoints;
+use crate::hyperliquid::fills::{self, Fill, UserEvent};
+use crate::hyperliquid::subscriptions::SubscriptionManager;
+
+/// The latest fill time seen of an address and the trade ids at that time,
+/// so fills arriving later in the same millisecond are still told apart.
+#[derive(Default)]
+struct Seen {
+    time: u64,
+    tids: HashSet<u64>,
+}
+
+impl Seen {
+    /// Returns the fills not seen yet, oldest first, and marks them seen.
+    fn take_new(&mut self, mut fills: Vec<Fill>) -> Vec<Fill> {
+        fills.retain(|fill| fill.time >= self.time && !self.tids.contains(&fill.tid));
+        fills.sort_by_key(|fill| fill.time);
+        for fill in &fills {
+            if fill.time > self.time {
+                self.time = fill.time;
+                self.tids.clear();
+            }
+            if fill.time == self.time {
+                self.tids.insert(fill.tid);
+            }
+        }
+        fills
+    }
+}
+
+/// Polls `userFills` of every address in `subscriptions` each `interval` and
+/// sends the new fills to `events` as the subscriptions would, in place of the
+/// WebSocket subscriptions. An address's first poll only sets where to start
+/// from; the startup backfill has already stored what came before. Each round
+/// polls the endpoint active at the time, so it follows a failover.
+pub async fn poll_fills(
+    endpoints: Endpoints,
+    subscriptions: Arc<Mutex<SubscriptionManager>>,
+    events: UnboundedSender<(H160, UserEvent)>,
+    interval: Duration,
+) {
+    let mut seen: HashMap<H160, Seen> = HashMap::new();
+    loop {
+        let info_client = match endpoints.client().await {
+            Ok(info_client) => info_client,
+            Err(err) => {
+                warn!("failed to create polling client: {err:?}");
+                sleep(interval).await;
+                continue;
+            }
+        };
+        let users = subscriptions.lock().await.users();
+        seen.retain(|user, _| users.contains(user));
+        for user in users {
+            let result = fills::fetch_user_fills(&info_client, user).await;
+            subscriptions
+                .lock()
+                .await
+                .record_poll(user, result.as_ref().map(|_| ()));
+            let fills = match result {
+                Ok(fills) => fills,
+                Err(err) => {
+                    warn!("failed to poll fills of {user:?}: {err:?}");
+                    continue;
+                }
+            };
+            let first_poll = !seen.contains_key(&user);
+            let fills = seen.entry(user).or_default().take_new(fills);
+            if first_poll || fills.is_empty() {
+                continue;
+            }
+            if events.send((user, UserEvent::Fills(fills))).is_err() {
+                return;
+            }
+        }
+        sleep(interval).await;
+    }
+}
+
+/// Polls `allMids` each `interval` and sends it to `mids` the way the
+/// WebSocket subscription would.
+pub async fn poll_mids(
+    info_client: Arc<Mutex<InfoClient>>,
+    mids: UnboundedSender<Message>,
+    interval: Duration,
+) {
+    loop {
+        let result = info_client.lock().await.all_mids().await;
+        match result {
+            Ok(all_mids) => {
+                let message = Message::AllMids(AllMids {
+                    data: AllMidsData { mids: all_mids },
+                });
+                if mids.send(message).is_err() {
+                    return;
+                }
+            }
+            Err(err) => warn!("failed to poll mids: {err:?}"),
+        }
+        sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(time: u64, tid: u64) -> Fill {
+        Fill {
+            coin: "BTC".to_string(),
+            side: "B".to_string(),
+            px: "60000".to_string(),
+            sz: "0.1".to_string(),
+            time,
+            hash: "0x00".to_string(),
+            start_position: "0".to_string(),
+            dir: "Open Long".to_string(),
+            closed_pnl: "0".to_string(),
+            oid: 1,
+            crossed: true,
+            fee: "0".to_string(),
+            tid,
+        }
+    }
+
+    fn tids(fills: &[Fill]) -> Vec<u64> {
+        fills.iter().map(|fill| fill.tid).collect()
+    }
+
+    #[test]
+    fn returns_new_fills_oldest_first() {
+        let mut seen = Seen::default();
+        let fills = seen.take_new(vec![fill(3, 30), fill(1, 10), fill(2, 20)]);
+        assert_eq!(tids(&fills), vec![10, 20, 30]);
+    }
+
+    #[test]
+    fn skips_fills_already_seen() {
+        let mut seen = Seen::default();
+        seen.take_new(vec![fill(1, 10), fill(2, 20)]);
+        assert!(seen.take_new(vec![fill(2, 20), fill(1, 10)]).is_empty());
+    }
+
+    #[test]
+    fn keeps_later_fills_of_the_latest_millisecond() {
+        let mut seen = Seen::default();
+        seen.take_new(vec![fill(5, 50)]);
+        let fills = seen.take_new(vec![fill(4, 40), fill(5, 50), fill(5, 51), fill(6, 60)]);
+        assert_eq!(tids(&fills), vec![51, 60]);
+        assert!(seen.take_new(vec![fill(5, 52)]).is_empty());
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use ethers::types::H160;
+use hyperliquid_rust_sdk::UserStateResponse;
+
+use crate::hyperliquid::fills::Fill;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Leverage {
+    pub type_: String,
+    pub value: u32,
+}
+
+pub struct Position {
+    pub leverage: Leverage,
+    pub margin_used: f64,
+    pub szi: f64,
+    pub position_value: f64,
+    pub unrealized_pnl: f64,
+    pub liquidation_px: Option<f64>,
+}
+
+impl Position {
+    /// The mark price the clearinghouse valued the position at.
+    pub fn mark_px(&self) -> Option<f64> {
+        (self.szi != 0.0).then(|| self.position_value / self.szi.abs())
+    }
+
+    /// How far the mark price may move against the position before it is
+    /// liquidated, as a percentage of the mark price.
+    pub fn liquidation_distance_pct(&self) -> Option<f64> {
+        let mark_px = self.mark_px().filter(|mark_px| *mark_px > 0.0)?;
+        let liquidation_px = self.liquidation_px?;
+        Some((mark_px - liquidation_px).abs() / mark_px * 100.0)
+    }
+}
+
+pub struct LeverageChange {
+    pub user: H160,
+    pub coin: String,
+    pub from: Leverage,
+    pub to: Leverage,
+}
+
+/// Per-user view of positions built from periodic `clearinghouseState` polls.
+#[derive(Default)]
+pub struct PositionTracker {
+    positions: HashMap<(H160, String), Position>,
+    leverages: HashMap<(H160, String), Leverage>,
+    account_values: HashMap<H160, f64>,
+}
+
+impl PositionTracker {
+    /// Records the latest state of `user` and returns the leverage settings that
+    /// changed since the previous poll. Coins seen for the first time are not
+    /// reported.
+    pub fn update(&mut self, user: H160, state: &UserStateResponse) -> Vec<LeverageChange> {
+        let account_value = state
+            .margin_summary
+            .account_value
+            .parse()
+            .unwrap_or_default();
+        self.account_values.insert(user, account_value);
+        self.positions
+            .retain(|(position_user, _), _| *position_user != user);
+
+        let mut changes = Vec::new();
+        for asset_position in state.asset_positions.iter() {
+            let position = &asset_position.position;
+            let leverage = Leverage {
+                type_: position.leverage.type_string.clone(),
+                value: position.leverage.value,
+            };
+
+            self.positions.insert(
+                (user, position.coin.clone()),
+                Position {
+                    leverage: leverage.clone(),
+                    margin_used: position.margin_used.parse().unwrap_or_default(),
+                    szi: position.szi.parse().unwrap_or_default(),
+                    position_value: position.position_value.parse().unwrap_or_default(),
+                    unrealized_pnl: position.unrealized_pnl.parse().unwrap_or_default(),
+                    liquidation_px: position
+                        .liquidation_px
+                        .as_deref()
+                        .and_then(|px| px.parse().ok()),
+                },
+            );
+
+            let key = (user, position.coin.clone());
+            if let Some(previous) = self.leverages.insert(key, leverage.clone()) {
+                if previous != leverage {
+                    changes.push(LeverageChange {
+                        user,
+                        coin: position.coin.clone(),
+                        from: previous,
+                        to: leverage,
+                    });
+                }
+            }
+        }
+        changes
+    }
+
+    pub fn account_value(&self, user: H160) -> Option<f64> {
+        self.account_values.get(&user).copied()
+    }
+
+    /// Drops the positions, leverages and account value of `user`, which is no
+    /// longer watched, so they leave the totals.
+    pub fn forget(&mut self, user: H160) {
+        self.positions
+            .retain(|(position_user, _), _| *position_user != user);
+        self.leverages
+            .retain(|(position_user, _), _| *position_user != user);
+        self.account_values.remove(&user);
+    }
+
+    /// Sums signed position sizes per coin across all tracked addresses.
+    pub fn net_positions(&self) -> BTreeMap<String, f64> {
+        let mut net = BTreeMap::new();
+        for ((_, coin), position) in self.positions.iter() {
+            *net.entry(coin.clone()).or_insert(0.0) += position.szi;
+        }
+        net
+    }
+
+    /// Sums signed position notionals per coin across all tracked addresses.
+    pub fn net_exposures(&self) -> BTreeMap<String, f64> {
+        let mut net = BTreeMap::new();
+        for ((_, coin), position) in self.positions.iter() {
+            *net.entry(coin.clone()).or_insert(0.0) +=
+                position.szi.signum() * position.position_value;
+        }
+        net
+    }
+
+    /// The coin with the largest net exposure, as a percentage of total equity.
+    pub fn concentration(&self) -> Option<(String, f64)> {
+        let equity = self.total_account_value();
+        if equity <= 0.0 {
+            return None;
+        }
+        self.net_exposures()
+            .into_iter()
+            .map(|(coin, exposure)| (coin, exposure.abs() / equity * 100.0))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    pub fn total_unrealized_pnl(&self) -> f64 {
+        self.positions
+            .values()
+            .map(|position| position.unrealized_pnl)
+            .sum()
+    }
+
+    pub fn total_account_value(&self) -> f64 {
+        self.account_values.values().sum()
+    }
+
+    pub fn get(&self, user: H160, coin: &str) -> Option<&Position> {
+        self.positions.get(&(user, coin.to_string()))
+    }
+
+    /// The positions of `user` within `within_pct` of their liquidation
+    /// price, with that distance.
+    pub fn near_liquidation(&self, user: H160, within_pct: f64) -> Vec<(&str, &Position, f64)> {
+        self.positions
+            .iter()
+            .filter(|((position_user, _), _)| *position_user == user)
+            .filter_map(|((_, coin), position)| {
+                let distance = position.liquidation_distance_pct()?;
+                (distance <= within_pct).then_some((coin.as_str(), position, distance))
+            })
+            .collect()
+    }
+}
+
+/// What a fill did to its address's position in the coin.
+pub struct PositionChange {
+    /// Such as "Open Long", "Reduce Short" or "Flip Long → Short".
+    pub label: String,
+    /// Signed size before the fill.
+    pub before: f64,
+    /// Signed size after the fill.
+    pub after: f64,
+    /// The largest absolute size seen of the address in the coin since the
+    /// watcher started, this fill included.
+    pub max_seen: f64,
+}
+
+/// Blocks in the position gauge.
+const GAUGE_WIDTH: usize = 5;
+
+impl PositionChange {
+    /// ▲ when the fill grew the position, ▼ when it shrank it.
+    pub fn arrow(&self) -> &'static str {
+        if self.after.abs() > self.before.abs() {
+            "▲"
+        } else {
+            "▼"
+        }
+    }
+
+    /// The position against the largest one seen, such as
+    /// `LONG ███░░ 62% of max seen`; `None` once flat.
+    pub fn gauge(&self) -> Option<String> {
+        if self.after == 0.0 || self.max_seen <= 0.0 {
+            return None;
+        }
+        let ratio = (self.after.abs() / self.max_seen).min(1.0);
+        let filled = (ratio * GAUGE_WIDTH as f64).round() as usize;
+        Some(format!(
+            "{} {}{} {:.0}% of max seen",
+            side(self.after).to_uppercase(),
+            "█".repeat(filled),
+            "░".repeat(GAUGE_WIDTH - filled),
+            ratio * 100.0
+        ))
+    }
+}
+
+/// Digits after the decimal point of a decimal string such as `"0.0100"`.
+fn decimals(value: &str) -> usize {
+    value
+        .split_once('.')
+        .map_or(0, |(_, fraction)| fraction.len())
+}
+
+fn side(szi: f64) -> &'static str {
+    if szi > 0.0 {
+        "Long"
+    } else {
+        "Short"
+    }
+}
+
+/// Signed position sizes per address and coin, seeded from
+/// `clearinghouseState` and moved by every fill, to tell opens from closes.
+#[derive(Default)]
+pub struct FillPositions {
+    sizes: HashMap<(H160, String), f64>,
+    max_sizes: HashMap<(H160, String), f64>,
+    seeded: HashSet<H160>,
+}
+
+impl FillPositions {
+    pub fn is_seeded(&self, user: H160) -> bool {
+        self.seeded.contains(&user)
+    }
+
+    pub fn seed(&mut self, user: H160, state: &UserStateResponse) {
+        self.seeded.insert(user);
+        self.sizes
+            .retain(|(position_user, _), _| *position_user != user);
+        for asset_position in state.asset_positions.iter() {
+            let position = &asset_position.position;
+            let key = (user, position.coin.clone());
+            let szi: f64 = position.szi.parse().unwrap_or_default();
+            let max_size = self.max_sizes.entry(key.clone()).or_default();
+            *max_size = max_size.max(szi.abs());
+            self.sizes.insert(key, szi);
+        }
+    }
+
+    /// Drops the sizes of `user`, which is no longer watched; the largest sizes
+    /// seen go too, so a re-added address starts its gauges afresh.
+    pub fn forget(&mut self, user: H160) {
+        self.seeded.remove(&user);
+        self.sizes
+            .retain(|(position_user, _), _| *position_user != user);
+        self.max_sizes
+            .retain(|(position_user, _), _| *position_user != user);
+    }
+
+    /// Applies a fill. Its `startPosition`, when Hyperliquid sends one, wins
+    /// over the tracked size so drift from missed fills heals itself.
+    pub fn apply(&mut self, user: H160, fill: &Fill) -> PositionChange {
+        let key = (user, fill.coin.clone());
+        let before = fill
+            .start_position
+            .parse()
+            .unwrap_or_else(|_| self.sizes.get(&key).copied().unwrap_or_default());
+        let sz: f64 = fill.sz.parse().unwrap_or_default();
+        // Round away float noise at the asset's size precision, which a fill
+        // of a round size understates but its start position carries in full.
+        let decimals = decimals(&fill.sz).max(decimals(&fill.start_position));
+        let scale = 10f64.powi(decimals as i32);
+        let after = ((before + if fill.side == "B" { sz } else { -sz }) * scale).round() / scale;
+        // Closing a short lands on -0, which would print as such.
+        let after = if after == 0.0 { 0.0 } else { after };
+        self.sizes.insert(key.clone(), after);
+        let max_size = self.max_sizes.entry(key).or_default();
+        *max_size = max_size.max(before.abs()).max(after.abs());
+        let max_seen = *max_size;
+
+        let label = if before == 0.0 {
+            format!("Open {}", side(after))
+        } else if after == 0.0 {
+            format!("Close {}", side(before))
+        } else if before.signum() != after.signum() {
+            format!("Flip {} → {}", side(before), side(after))
+        } else if after.abs() > before.abs() {
+            format!("Add {}", side(after))
+        } else {
+            format!("Reduce {}", side(after))
+        };
+        PositionChange {
+            label,
+            before,
+            after,
+            max_seen,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(side: &str, sz: &str, start_position: &str) -> Fill {
+        Fill {
+            coin: "ETH".to_string(),
+            side: side.to_string(),
+            px: "3000".to_string(),
+            sz: sz.to_string(),
+            time: 1,
+            hash: "0x00".to_string(),
+            start_position: start_position.to_string(),
+            dir: String::new(),
+            closed_pnl: "0".to_string(),
+            oid: 1,
+            crossed: true,
+            fee: "0".to_string(),
+            tid: 1,
+        }
+    }
+
+    #[test]
+    fn labels_opens_adds_reduces_and_closes() {
+        let user = H160::zero();
+        let mut positions = FillPositions::default();
+
+        let change = positions.apply(user, &fill("B", "1.5", "0.0"));
+        assert_eq!((change.label.as_str(), change.after), ("Open Long", 1.5));
+        let change = positions.apply(user, &fill("B", "0.5", "1.5"));
+        assert_eq!((change.label.as_str(), change.after), ("Add Long", 2.0));
+        let change = positions.apply(user, &fill("A", "0.5", "2.0"));
+        assert_eq!((change.label.as_str(), change.after), ("Reduce Long", 1.5));
+        let change = positions.apply(user, &fill("A", "1.5", "1.5"));
+        assert_eq!((change.label.as_str(), change.after), ("Close Long", 0.0));
+        assert_eq!(change.max_seen, 2.0);
+    }
+
+    #[test]
+    fn labels_flips() {
+        let mut positions = FillPositions::default();
+        let change = positions.apply(H160::zero(), &fill("A", "3", "1"));
+        assert_eq!(change.label, "Flip Long → Short");
+        assert_eq!((change.before, change.after), (1.0, -2.0));
+    }
+
+    #[test]
+    fn closing_a_short_lands_on_positive_zero() {
+        let mut positions = FillPositions::default();
+        let change = positions.apply(H160::zero(), &fill("B", "2.5", "-2.5"));
+        assert_eq!(change.label, "Close Short");
+        assert!(change.after.is_sign_positive());
+    }
+
+    #[test]
+    fn rounds_to_the_size_precision() {
+        let mut positions = FillPositions::default();
+        // 0.1 + 0.2 is 0.30000000000000004 in floats.
+        let change = positions.apply(H160::zero(), &fill("B", "0.2", "0.1"));
+        assert_eq!(change.after, 0.3);
+        // A round size keeps the precision the start position carries.
+        let change = positions.apply(H160::zero(), &fill("B", "1", "0.30001"));
+        assert_eq!(change.after, 1.30001);
+    }
+
+    #[test]
+    fn falls_back_to_the_tracked_size() {
+        let user = H160::zero();
+        let mut positions = FillPositions::default();
+        positions.apply(user, &fill("B", "2", "0"));
+        let change = positions.apply(user, &fill("A", "0.5", ""));
+        assert_eq!((change.before, change.after), (2.0, 1.5));
+    }
+}
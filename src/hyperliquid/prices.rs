@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use hyperliquid_rust_sdk::{InfoClient, Level};
+use tracing::warn;
+
+/// Mid prices kept fresh by the `allMids` stream, falling back to a REST fetch
+/// once they are older than the TTL.
+pub struct MidCache {
+    ttl: Duration,
+    fetched_at: Option<Instant>,
+    mids: HashMap<String, f64>,
+}
+
+impl MidCache {
+    pub fn new(ttl: Duration) -> Self {
+        MidCache {
+            ttl,
+            fetched_at: None,
+            mids: HashMap::new(),
+        }
+    }
+
+    fn is_stale(&self) -> bool {
+        match self.fetched_at {
+            Some(fetched_at) => fetched_at.elapsed() >= self.ttl,
+            None => true,
+        }
+    }
+
+    pub fn update(&mut self, mids: HashMap<String, String>) {
+        self.mids = mids
+            .into_iter()
+            .filter_map(|(coin, mid)| mid.parse().ok().map(|mid| (coin, mid)))
+            .collect();
+        self.fetched_at = Some(Instant::now());
+    }
+
+    pub async fn get(&mut self, info_client: &InfoClient, coin: &str) -> Option<f64> {
+        if self.is_stale() {
+            match info_client.all_mids().await {
+                Ok(mids) => self.update(mids),
+                Err(err) => warn!("failed to fetch mids: {err:?}"),
+            }
+        }
+        self.mids.get(coin).copied()
+    }
+}
+
+/// Basis points by which a fill was worse than the mid: above it for buys
+/// (`B`), below it for sells (`A`). Negative when the fill improved on the mid.
+pub fn bps_through_mid(side: &str, px: f64, mid: f64) -> f64 {
+    let deviation = (px - mid) / mid * 10_000.0;
+    if side == "B" {
+        deviation
+    } else {
+        -deviation
+    }
+}
+
+/// Resting notional on each side of an L2 book snapshot, as far as the
+/// snapshot reaches.
+pub struct BookDepth {
+    pub bid_usd: f64,
+    pub ask_usd: f64,
+}
+
+impl BookDepth {
+    pub async fn fetch(info_client: &InfoClient, coin: &str) -> anyhow::Result<Self> {
+        let snapshot = info_client.l2_snapshot(coin.to_string()).await?;
+        let side_usd = |levels: Option<&Vec<Level>>| -> f64 {
+            levels
+                .into_iter()
+                .flatten()
+                .map(|level| {
+                    level.px.parse::<f64>().unwrap_or_default()
+                        * level.sz.parse::<f64>().unwrap_or_default()
+                })
+                .sum()
+        };
+        Ok(BookDepth {
+            bid_usd: side_usd(snapshot.levels.first()),
+            ask_usd: side_usd(snapshot.levels.get(1)),
+        })
+    }
+
+    /// From -100 (only asks) to 100 (only bids).
+    pub fn imbalance_pct(&self) -> f64 {
+        let total = self.bid_usd + self.ask_usd;
+        if total == 0.0 {
+            return 0.0;
+        }
+        (self.bid_usd - self.ask_usd) / total * 100.0
+    }
+
+    pub fn render(&self) -> String {
+        let imbalance = self.imbalance_pct();
+        let heavier = if imbalance >= 0.0 { "bid" } else { "ask" };
+        format!(
+            "book ${:.0} bids / ${:.0} asks, {:.0}% {heavier}-heavy",
+            self.bid_usd,
+            self.ask_usd,
+            imbalance.abs()
+        )
+    }
+}
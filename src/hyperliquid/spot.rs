@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use hyperliquid_rust_sdk::InfoClient;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Deserialize, Clone, Debug)]
+struct SpotToken {
+    name: String,
+    index: usize,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct SpotPair {
+    name: String,
+    tokens: [usize; 2],
+    index: usize,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct SpotMeta {
+    tokens: Vec<SpotToken>,
+    universe: Vec<SpotPair>,
+}
+
+/// Maps spot universe identifiers (`@107`) to readable pair names (`HYPE/USDC`).
+#[derive(Clone, Default)]
+pub struct SpotNames {
+    names: HashMap<String, String>,
+}
+
+impl SpotNames {
+    pub async fn fetch(info_client: &InfoClient) -> anyhow::Result<Self> {
+        let payload = info_client
+            .http_client
+            .post("/info", json!({ "type": "spotMeta" }).to_string())
+            .await?;
+        let meta: SpotMeta = serde_json::from_str(&payload)?;
+
+        let tokens: HashMap<usize, String> = meta
+            .tokens
+            .into_iter()
+            .map(|token| (token.index, token.name))
+            .collect();
+
+        let mut names = HashMap::new();
+        for pair in meta.universe {
+            let name = match (tokens.get(&pair.tokens[0]), tokens.get(&pair.tokens[1])) {
+                (Some(base), Some(quote)) => format!("{base}/{quote}"),
+                _ => pair.name,
+            };
+            names.insert(format!("@{}", pair.index), name);
+        }
+        Ok(SpotNames { names })
+    }
+
+    /// Returns true when the coin is a spot identifier missing from the cache.
+    pub fn is_unknown(&self, coin: &str) -> bool {
+        coin.starts_with('@') && !self.names.contains_key(coin)
+    }
+
+    pub fn resolve(&self, coin: &str) -> String {
+        self.names
+            .get(coin)
+            .cloned()
+            .unwrap_or_else(|| coin.to_string())
+    }
+}
@@ -0,0 +1,458 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use ethers::types::H160;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, timeout};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tracing::warn;
+
+use crate::context::spawn;
+use crate::health::Heartbeat;
+use crate::hyperliquid::capture::RawMessage;
+use crate::hyperliquid::endpoints::Endpoints;
+use crate::hyperliquid::fills::{self, UserEvent};
+use crate::hyperliquid::order_updates::OrderUpdate;
+use crate::now_ms;
+
+/// Hyperliquid closes connections that send nothing for a minute; each ping
+/// is answered with a pong, which also shows the connection is alive.
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+
+/// How long connecting and confirming a subscription may take.
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Deserialize)]
+struct Envelope {
+    channel: String,
+    #[serde(default)]
+    data: Value,
+}
+
+/// A `userEvents` subscription, and an `orderUpdates` one when orders are
+/// followed, on a dedicated connection.
+///
+/// Hyperliquid only allows one `userEvents` subscription per connection and
+/// its messages do not carry the user, so every watched address gets its own
+/// connection and its messages are tagged before being forwarded. The SDK's
+/// connections cannot tell when they went quiet, so these are opened directly
+/// and remember, as does the watcher's heartbeat, when they last heard from
+/// the server. Dropping one closes its connection.
+struct UserSubscription {
+    last_message: Arc<AtomicU64>,
+    task: JoinHandle<()>,
+}
+
+impl UserSubscription {
+    async fn subscribe(user: H160, connector: &Connector) -> anyhow::Result<Self> {
+        let ws_url = connector.endpoints.ws_url();
+        let (mut ws, _) = timeout(SUBSCRIBE_TIMEOUT, connect_async(ws_url)).await??;
+        let mut kinds = vec!["userEvents"];
+        if connector.orders.is_some() {
+            kinds.push("orderUpdates");
+        }
+        for kind in &kinds {
+            let request = json!({
+                "method": "subscribe",
+                "subscription": { "type": kind, "user": user },
+            });
+            ws.send(WsMessage::Text(request.to_string())).await?;
+        }
+        timeout(SUBSCRIBE_TIMEOUT, confirm(&mut ws, kinds.len())).await??;
+
+        let last_message = Arc::new(AtomicU64::new(now_ms()));
+        let task = spawn(forward(
+            ws,
+            user,
+            connector.events.clone(),
+            connector.orders.clone(),
+            connector.capture.clone(),
+            Arc::clone(&last_message),
+            connector.heartbeat.clone(),
+        ));
+        Ok(UserSubscription { last_message, task })
+    }
+
+    /// Whether the connection closed or has been silent for over
+    /// `max_silence`, pongs included.
+    fn is_stale(&self, max_silence: Duration) -> bool {
+        let silence = now_ms().saturating_sub(self.last_message.load(Ordering::Relaxed));
+        self.task.is_finished() || silence > max_silence.as_millis() as u64
+    }
+}
+
+impl Drop for UserSubscription {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Waits for the server to acknowledge `subscriptions` subscriptions.
+async fn confirm(ws: &mut WsStream, mut subscriptions: usize) -> anyhow::Result<()> {
+    while let Some(frame) = ws.next().await {
+        let WsMessage::Text(payload) = frame? else {
+            continue;
+        };
+        let Ok(envelope) = serde_json::from_str::<Envelope>(&payload) else {
+            continue;
+        };
+        match envelope.channel.as_str() {
+            "subscriptionResponse" => {
+                subscriptions -= 1;
+                if subscriptions == 0 {
+                    return Ok(());
+                }
+            }
+            "error" => return Err(anyhow!("subscription refused: {}", envelope.data)),
+            _ => (),
+        }
+    }
+    Err(anyhow!(
+        "connection closed before the subscription was confirmed"
+    ))
+}
+
+/// Pings the server and forwards `user`'s events and order updates until the
+/// connection fails, noting the time of every frame received and teeing every
+/// payload into `capture` when set.
+async fn forward(
+    mut ws: WsStream,
+    user: H160,
+    events: UnboundedSender<(H160, UserEvent)>,
+    orders: Option<UnboundedSender<(H160, OrderUpdate)>>,
+    capture: Option<UnboundedSender<RawMessage>>,
+    last_message: Arc<AtomicU64>,
+    heartbeat: Heartbeat,
+) {
+    let mut ping = interval(PING_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = ping.tick() => {
+                let ping = json!({ "method": "ping" }).to_string();
+                if ws.send(WsMessage::Text(ping)).await.is_err() {
+                    return;
+                }
+            }
+            frame = ws.next() => {
+                let payload = match frame {
+                    Some(Ok(WsMessage::Text(payload))) => payload,
+                    Some(Ok(_)) => {
+                        last_message.store(now_ms(), Ordering::Relaxed);
+                        heartbeat.beat();
+                        continue;
+                    }
+                    Some(Err(err)) => {
+                        warn!("connection of {user:?} failed: {err:?}");
+                        return;
+                    }
+                    None => return,
+                };
+                last_message.store(now_ms(), Ordering::Relaxed);
+                heartbeat.beat();
+                if let Some(capture) = &capture {
+                    // A stopped capture writer must not stop the notifications.
+                    let _ = capture.send(RawMessage {
+                        user: Some(user),
+                        received_ms: now_ms(),
+                        payload: payload.clone(),
+                    });
+                }
+                let Ok(envelope) = serde_json::from_str::<Envelope>(&payload) else {
+                    continue;
+                };
+                if envelope.channel == "orderUpdates" {
+                    let Some(orders) = &orders else {
+                        continue;
+                    };
+                    let updates = match serde_json::from_value::<Vec<OrderUpdate>>(envelope.data) {
+                        Ok(updates) => updates,
+                        Err(err) => {
+                            warn!("failed to parse order updates of {user:?}: {err:?}");
+                            continue;
+                        }
+                    };
+                    for update in updates {
+                        if orders.send((user, update)).is_err() {
+                            return;
+                        }
+                    }
+                    continue;
+                }
+                if envelope.channel != "user" {
+                    continue;
+                }
+                // Funding, liquidation and cancel events carry no fills.
+                let Some(event) = fills::parse_user_event(&envelope.data, &payload) else {
+                    continue;
+                };
+                if events.send((user, event)).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubscriptionState {
+    Active,
+    Retrying,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SubscriptionStatus {
+    pub state: SubscriptionState,
+    /// When the address became active, or when its current run of failures began.
+    pub since: u64,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+}
+
+impl SubscriptionStatus {
+    fn active() -> Self {
+        SubscriptionStatus {
+            state: SubscriptionState::Active,
+            since: now_ms(),
+            consecutive_failures: 0,
+            last_error: None,
+        }
+    }
+
+    fn record_success(&mut self) {
+        if self.state != SubscriptionState::Active {
+            *self = SubscriptionStatus::active();
+        }
+    }
+
+    fn record_failure(&mut self, err: &anyhow::Error) {
+        if self.state != SubscriptionState::Retrying {
+            self.state = SubscriptionState::Retrying;
+            self.since = now_ms();
+        }
+        self.consecutive_failures += 1;
+        self.last_error = Some(format!("{err:?}"));
+    }
+}
+
+/// Opens subscriptions without holding the `SubscriptionManager`, so slow or
+/// failing connections do not keep the API, the vault refresh and polling
+/// waiting on it.
+#[derive(Clone)]
+pub struct Connector {
+    endpoints: Endpoints,
+    polling: bool,
+    events: UnboundedSender<(H160, UserEvent)>,
+    orders: Option<UnboundedSender<(H160, OrderUpdate)>>,
+    capture: Option<UnboundedSender<RawMessage>>,
+    heartbeat: Heartbeat,
+}
+
+impl Connector {
+    /// Subscribes `users` on new connections to the endpoint now active, to
+    /// be swapped in with `SubscriptionManager::swap_in`.
+    pub async fn connect(&self, users: Vec<H160>) -> Reconnection {
+        let mut reconnection = Reconnection::default();
+        if self.polling {
+            return reconnection;
+        }
+        for user in users {
+            match UserSubscription::subscribe(user, self).await {
+                Ok(subscription) => reconnection.connected.push((user, subscription)),
+                Err(err) => reconnection.failures.push((user, err)),
+            }
+        }
+        reconnection
+    }
+}
+
+/// New connections made by `Connector::connect`, and the addresses that failed.
+#[derive(Default)]
+pub struct Reconnection {
+    connected: Vec<(H160, UserSubscription)>,
+    failures: Vec<(H160, anyhow::Error)>,
+}
+
+/// Every watched address's subscription and status, keyed by address.
+///
+/// Addresses whose subscription failed stay watched and are retried with the
+/// stale ones.
+///
+/// When polling, no connections are opened and the statuses follow the
+/// outcome of each address's polls instead.
+pub struct SubscriptionManager {
+    connector: Connector,
+    subscriptions: BTreeMap<H160, UserSubscription>,
+    statuses: BTreeMap<H160, SubscriptionStatus>,
+}
+
+impl SubscriptionManager {
+    pub fn new(endpoints: Endpoints, events: UnboundedSender<(H160, UserEvent)>) -> Self {
+        SubscriptionManager {
+            connector: Connector {
+                endpoints,
+                polling: false,
+                events,
+                orders: None,
+                capture: None,
+                heartbeat: Heartbeat::default(),
+            },
+            subscriptions: BTreeMap::new(),
+            statuses: BTreeMap::new(),
+        }
+    }
+
+    /// Leaves fetching fills to `polling::poll_fills`.
+    pub fn polling(mut self) -> Self {
+        self.connector.polling = true;
+        self
+    }
+
+    /// Also subscribes each address's order updates and sends them to
+    /// `orders`, on the same connection as its fills.
+    pub fn with_order_updates(mut self, orders: UnboundedSender<(H160, OrderUpdate)>) -> Self {
+        self.connector.orders = Some(orders);
+        self
+    }
+
+    /// Tees every payload any connection receives into `capture`.
+    pub fn with_capture(mut self, capture: UnboundedSender<RawMessage>) -> Self {
+        self.connector.capture = Some(capture);
+        self
+    }
+
+    /// Beats `heartbeat` on every frame any connection receives.
+    pub fn with_heartbeat(mut self, heartbeat: Heartbeat) -> Self {
+        self.connector.heartbeat = heartbeat;
+        self
+    }
+
+    /// Starts watching `user` unless already watched.
+    pub async fn subscribe(&mut self, user: H160) -> anyhow::Result<()> {
+        if self.statuses.contains_key(&user) {
+            return Ok(());
+        }
+        let result = self.connect(user).await;
+        let mut status = SubscriptionStatus::active();
+        if let Err(err) = &result {
+            status.record_failure(err);
+        }
+        self.statuses.insert(user, status);
+        result
+    }
+
+    async fn connect(&mut self, user: H160) -> anyhow::Result<()> {
+        if self.connector.polling {
+            return Ok(());
+        }
+        let subscription = UserSubscription::subscribe(user, &self.connector).await?;
+        self.subscriptions.insert(user, subscription);
+        Ok(())
+    }
+
+    /// Stops watching `user`; returns false if it wasn't watched.
+    pub fn unsubscribe(&mut self, user: H160) -> bool {
+        self.subscriptions.remove(&user);
+        self.statuses.remove(&user).is_some()
+    }
+
+    pub fn connector(&self) -> Connector {
+        self.connector.clone()
+    }
+
+    /// The addresses whose connection closed or has been silent for over
+    /// `max_silence`, and the ones that never connected. Healthy connections
+    /// are left out.
+    pub fn stale(&self, max_silence: Duration) -> Vec<H160> {
+        if self.connector.polling {
+            return Vec::new();
+        }
+        let stale: Vec<H160> = self
+            .users()
+            .into_iter()
+            .filter(|user| {
+                self.subscriptions
+                    .get(user)
+                    .is_none_or(|subscription| subscription.is_stale(max_silence))
+            })
+            .collect();
+        for user in &stale {
+            if self.subscriptions.contains_key(user) {
+                warn!("Connection of {user:?} is stale, reconnecting");
+            }
+        }
+        stale
+    }
+
+    /// Swaps in the new connections for the old ones, all at once, so no
+    /// address goes unwatched in between, and returns the addresses that
+    /// failed. Those keep whatever connection they had; addresses no longer
+    /// watched drop theirs.
+    pub fn swap_in(&mut self, reconnection: Reconnection) -> Vec<(H160, anyhow::Error)> {
+        for (user, subscription) in reconnection.connected {
+            if let Some(status) = self.statuses.get_mut(&user) {
+                status.record_success();
+                self.subscriptions.insert(user, subscription);
+            }
+        }
+        let mut failures = Vec::new();
+        for (user, err) in reconnection.failures {
+            if let Some(status) = self.statuses.get_mut(&user) {
+                status.record_failure(&err);
+                failures.push((user, err));
+            }
+        }
+        failures
+    }
+
+    /// Starts watching `users` with the connections `connector().connect`
+    /// opened for them outside the lock, and returns the ones that failed.
+    /// Those stay watched and are retried with the stale ones.
+    pub fn add(
+        &mut self,
+        users: &[H160],
+        reconnection: Reconnection,
+    ) -> Vec<(H160, anyhow::Error)> {
+        for user in users {
+            self.statuses
+                .entry(*user)
+                .or_insert_with(SubscriptionStatus::active);
+        }
+        self.swap_in(reconnection)
+    }
+
+    /// Stops watching every address.
+    pub fn unsubscribe_all(&mut self) {
+        self.subscriptions.clear();
+        self.statuses.clear();
+    }
+
+    /// Records how a poll of `user`'s fills went.
+    pub fn record_poll(&mut self, user: H160, result: Result<(), &anyhow::Error>) {
+        if let Some(status) = self.statuses.get_mut(&user) {
+            match result {
+                Ok(()) => status.record_success(),
+                Err(err) => status.record_failure(err),
+            }
+        }
+    }
+
+    pub fn users(&self) -> Vec<H160> {
+        self.statuses.keys().copied().collect()
+    }
+
+    pub fn statuses(&self) -> &BTreeMap<H160, SubscriptionStatus> {
+        &self.statuses
+    }
+}
@@ -0,0 +1,212 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use ethers::types::H160;
+use hyperliquid_rust_sdk::InfoClient;
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+use tracing::warn;
+
+const ATTEMPTS: u32 = 3;
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct RelationshipData {
+    #[serde(default)]
+    child_addresses: Vec<String>,
+}
+
+/// Only parent vaults such as HLP carry child addresses; normal vaults have
+/// no `data` at all.
+#[derive(Deserialize, Clone, Debug)]
+struct Relationship {
+    #[serde(default)]
+    data: RelationshipData,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Info {
+    name: String,
+    leader: H160,
+    description: String,
+    portfolio: Vec<(String, Portfolio)>,
+    #[serde(default)]
+    followers: Vec<Follower>,
+    relationship: Relationship,
+}
+
+/// Account value and PnL over one of the `day`, `week`, `month` or `allTime`
+/// windows, as `(time_ms, value)` samples.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Portfolio {
+    pub account_value_history: Vec<(u64, String)>,
+    pub pnl_history: Vec<(u64, String)>,
+    #[serde(default)]
+    pub vlm: String,
+}
+
+impl Portfolio {
+    fn latest(history: &[(u64, String)]) -> Option<f64> {
+        history.last().and_then(|(_, value)| value.parse().ok())
+    }
+
+    pub fn account_value(&self) -> Option<f64> {
+        Portfolio::latest(&self.account_value_history)
+    }
+
+    pub fn pnl(&self) -> Option<f64> {
+        Portfolio::latest(&self.pnl_history)
+    }
+
+    pub fn volume(&self) -> f64 {
+        self.vlm.parse().unwrap_or_default()
+    }
+
+    /// PnL over the window as a percentage of the account value it started with.
+    pub fn return_pct(&self) -> Option<f64> {
+        let start = Portfolio::starting_value(&self.account_value_history)?;
+        Some(self.pnl()? / start * 100.0)
+    }
+
+    /// Deepest peak-to-trough fall of starting value plus running PnL, in
+    /// percent. Using PnL keeps deposits and withdrawals out of the curve.
+    pub fn max_drawdown_pct(&self) -> Option<f64> {
+        let start = Portfolio::starting_value(&self.account_value_history)?;
+        let mut peak = start;
+        let mut max_drawdown: f64 = 0.0;
+        for (_, pnl) in &self.pnl_history {
+            let value = start + pnl.parse::<f64>().unwrap_or_default();
+            peak = peak.max(value);
+            if peak > 0.0 {
+                max_drawdown = max_drawdown.max((peak - value) / peak * 100.0);
+            }
+        }
+        Some(max_drawdown)
+    }
+
+    fn starting_value(history: &[(u64, String)]) -> Option<f64> {
+        history
+            .first()
+            .and_then(|(_, value)| value.parse().ok())
+            .filter(|value: &f64| *value > 0.0)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Follower {
+    pub user: H160,
+    pub vault_equity: String,
+}
+
+impl Follower {
+    pub fn equity(&self) -> f64 {
+        self.vault_equity.parse().unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct VaultDetails {
+    pub name: String,
+    pub leader: H160,
+    pub description: String,
+    pub portfolio: Vec<(String, Portfolio)>,
+    pub followers: Vec<Follower>,
+    pub child_addresses: Vec<H160>,
+}
+
+impl VaultDetails {
+    pub fn leader_equity(&self) -> f64 {
+        self.followers
+            .iter()
+            .filter(|follower| follower.user == self.leader)
+            .map(Follower::equity)
+            .sum()
+    }
+
+    pub fn tvl(&self) -> Option<f64> {
+        self.portfolio("allTime")?.account_value()
+    }
+
+    /// The leader's own share of the vault, in percent.
+    pub fn leader_stake_pct(&self) -> Option<f64> {
+        let tvl = self.tvl().filter(|tvl| *tvl > 0.0)?;
+        Some(self.leader_equity() / tvl * 100.0)
+    }
+
+    pub fn portfolio(&self, window: &str) -> Option<&Portfolio> {
+        self.portfolio
+            .iter()
+            .find(|(name, _)| name == window)
+            .map(|(_, portfolio)| portfolio)
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct InfoRequest<'a> {
+    #[serde(rename = "type")]
+    type_: &'static str,
+    vault_address: &'a str,
+}
+
+/// Typed access to the vault endpoints of `/info`, retrying transient
+/// failures with a short backoff.
+pub struct VaultApi<'a> {
+    info_client: &'a InfoClient,
+}
+
+impl<'a> VaultApi<'a> {
+    pub fn new(info_client: &'a InfoClient) -> Self {
+        VaultApi { info_client }
+    }
+
+    async fn post(&self, request: &InfoRequest<'_>) -> anyhow::Result<String> {
+        let body = serde_json::to_string(request)?;
+        let mut attempt = 1;
+        loop {
+            match self
+                .info_client
+                .http_client
+                .post("/info", body.clone())
+                .await
+            {
+                Ok(payload) => return Ok(payload),
+                Err(err) if attempt < ATTEMPTS => {
+                    warn!(
+                        "{} request failed (attempt {attempt}): {err:?}",
+                        request.type_
+                    );
+                    sleep(Duration::from_millis(500 * 2u64.pow(attempt - 1))).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    pub async fn details(&self, vault_address: &str) -> anyhow::Result<VaultDetails> {
+        let payload = self
+            .post(&InfoRequest {
+                type_: "vaultDetails",
+                vault_address,
+            })
+            .await?;
+        let info: Info = serde_json::from_str(&payload)?;
+
+        let mut child_addresses = Vec::new();
+        for address in info.relationship.data.child_addresses {
+            child_addresses.push(H160::from_str(address.as_str())?);
+        }
+        Ok(VaultDetails {
+            name: info.name,
+            leader: info.leader,
+            description: info.description,
+            portfolio: info.portfolio,
+            followers: info.followers,
+            child_addresses,
+        })
+    }
+}
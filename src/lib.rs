@@ -0,0 +1,92 @@
+//! Watches Hyperliquid vaults and addresses and notifies their fills.
+//!
+//! [`Watcher`] runs the whole watcher; the modules can also be used on their own.
+
+pub mod api;
+pub mod archive;
+pub mod batching;
+pub mod categories;
+pub mod clusters;
+pub mod commands;
+pub mod comparison;
+pub mod config;
+pub mod context;
+pub mod correlation;
+pub mod costs;
+pub mod crypto;
+pub mod dedup;
+pub mod digest;
+pub mod estimate;
+pub mod filters;
+pub mod health;
+pub mod history;
+pub mod hyperliquid;
+pub mod metrics;
+pub mod mutes;
+pub mod notify;
+pub mod permalink;
+pub mod reconcile;
+pub mod restarts;
+pub mod routes;
+pub mod schedule;
+pub mod sessions;
+pub mod slo;
+pub mod sqlite;
+pub mod stats;
+pub mod storage;
+pub mod store;
+pub mod summary;
+pub mod tenants;
+pub mod velocity;
+pub mod watcher;
+pub mod watchlist;
+
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ethers::types::H160;
+
+pub use watcher::Watcher;
+
+/// `0x1234…abcd`, for showing an address where the whole of it should not be.
+pub fn short_address(address: H160) -> String {
+    let full = format!("{address:?}");
+    format!("{}…{}", &full[..6], &full[full.len() - 4..])
+}
+
+/// Hyperliquid reports buys as `B` (bid) and sells as `A` (ask).
+pub fn side_label(side: &str) -> &'static str {
+    match side {
+        "B" => "Long",
+        "A" => "Short",
+        _ => "Unknown",
+    }
+}
+
+/// Formats dollars with cents, or with four significant digits below a dollar
+/// so small fees do not round to zero.
+pub fn format_usd(value: f64) -> String {
+    let sign = if value < 0.0 { "-" } else { "" };
+    let value = value.abs();
+    let decimals = if value >= 1.0 || value == 0.0 {
+        2
+    } else {
+        (3 - value.log10().floor() as i32).clamp(2, 8) as usize
+    };
+    format!("{sign}${value:.decimals$}")
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+pub fn parse_addresses(value: &str) -> anyhow::Result<Vec<H160>> {
+    let mut addresses = Vec::new();
+    for address in value.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+        addresses.push(H160::from_str(address)?);
+    }
+    Ok(addresses)
+}
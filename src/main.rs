@@ -1,179 +1,421 @@
+#[cfg(windows)]
+mod service;
+
+use std::collections::BTreeSet;
 use std::env;
-use std::str::FromStr;
-use std::sync::Arc;
-use std::time::Duration;
+use std::path::PathBuf;
 
+use clap::{Args, Parser, Subcommand};
 use ethers::types::H160;
-use hyperliquid_rust_sdk::{BaseUrl, InfoClient, Message, Subscription, TradeInfo};
-use serde::{Deserialize, Serialize};
-use serde_json::json;
-use tokio::spawn;
-use tokio::sync::Mutex;
-use tokio::{sync::mpsc::unbounded_channel, time::sleep};
-use tracing::{info, warn, Level};
+use god_watcher::config::{Config, Network};
+use god_watcher::hyperliquid::capture;
+use god_watcher::notify::dry_run;
+use god_watcher::storage::Store;
+use god_watcher::{
+    archive, commands, crypto, dedup, estimate, history, now_ms, reconcile, restarts, stats,
+    storage, store, tenants, watchlist, Watcher,
+};
+use hyperliquid_rust_sdk::InfoClient;
+use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
-#[derive(Deserialize, Clone, Debug)]
-#[serde(rename_all = "camelCase")]
-struct RelationshipData {
-    child_addresses: Vec<String>,
+/// Notifies the fills of Hyperliquid vaults and addresses.
+///
+/// Without a subcommand the watcher runs, as with `watch`.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// Watch targets; HLP alone is watched when the file does not exist.
+    #[arg(
+        long,
+        global = true,
+        env = "CONFIG_PATH",
+        default_value = "god_watcher.toml"
+    )]
+    config: PathBuf,
+    /// mainnet or testnet; overrides the config file's `network` (default: mainnet).
+    #[arg(long, global = true)]
+    network: Option<Network>,
+    /// One of error, warn, info, debug or trace.
+    #[arg(long, global = true, default_value_t = Level::INFO)]
+    log_level: Level,
+    /// Logs what would be sent to Discord and the other sinks instead of
+    /// sending it.
+    #[arg(long, global = true)]
+    dry_run: bool,
+    #[command(flatten)]
+    watch: WatchArgs,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
-struct Relationship {
-    data: RelationshipData,
+impl Cli {
+    /// The config file with `--network` applied.
+    fn load_config(&self) -> anyhow::Result<Config> {
+        let mut config = Config::load(&self.config)?;
+        if let Some(network) = self.network {
+            config.network = network;
+        }
+        Ok(config)
+    }
 }
 
-#[derive(Deserialize, Clone, Debug)]
-struct Info {
-    relationship: Relationship,
+/// Options of `watch`, also accepted without the subcommand.
+#[derive(Args)]
+struct WatchArgs {
+    /// Detaches from the terminal (Unix only).
+    #[arg(long, global = true)]
+    daemon: bool,
+    #[arg(
+        long,
+        global = true,
+        default_value = "god_watcher.pid",
+        requires = "daemon"
+    )]
+    pidfile: PathBuf,
+    /// Where a daemon's output goes.
+    #[arg(long, global = true, requires = "daemon")]
+    log_file: Option<PathBuf>,
+    /// Overrides `METRICS_PREFIX`.
+    #[arg(long, global = true)]
+    metrics_prefix: Option<String>,
+    /// Serves the HTTP API read-only and sanitized, for sharing publicly.
+    #[arg(long, global = true)]
+    public: bool,
+    /// Runs a watcher for each tenant listed in this file, in place of
+    /// `--config`.
+    #[arg(long, global = true, env = "TENANTS_PATH")]
+    tenants: Option<PathBuf>,
 }
 
-#[derive(Serialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct InfoRequest {
-    #[serde(rename = "type")]
-    type_: String,
-    vault_address: String,
+#[derive(Subcommand)]
+enum Command {
+    /// Watches the configured vaults and addresses (the default).
+    Watch,
+    /// Stores the recent fills of every watched address missed while the
+    /// watcher was down.
+    Backfill,
+    /// Prints volume, PnL and fees per coin from the fill store.
+    Report {
+        /// How far back, such as `12h` or `7d`.
+        #[arg(long, default_value = "1d")]
+        window: String,
+        #[arg(long)]
+        address: Option<H160>,
+    },
+    /// Validates the config file and prints what it watches.
+    CheckConfig,
+    /// Adds historical fills from node archives or CSV files to the fill store.
+    Import {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Removes everything stored locally about one address. Run it while the
+    /// watcher is stopped so it does not write the data back.
+    Purge {
+        #[arg(long)]
+        address: H160,
+    },
+    /// Replays a vault's recent fills through the current mutes, routes and
+    /// exclusions and prints how many notifications they would have produced.
+    Estimate {
+        #[arg(long)]
+        vault: H160,
+        #[arg(long, default_value_t = 7)]
+        days: u64,
+    },
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<()> {
+    #[cfg(windows)]
+    match env::args().nth(1).as_deref() {
+        Some("--install-service") => return service::install(),
+        Some("--uninstall-service") => return service::uninstall(),
+        Some("--service") => return service::run(),
+        _ => (),
+    }
+
+    let mut cli = Cli::parse();
+    if cli.dry_run {
+        dry_run::enable();
+    }
+
+    match cli.command.take().unwrap_or(Command::Watch) {
+        Command::Watch => {
+            let watch = &cli.watch;
+            // Forking must happen before the runtime spawns its worker threads.
+            #[cfg(unix)]
+            if watch.daemon {
+                daemonize(watch)?;
+            }
+            init_tracing(cli.log_level)?;
+            if let Some(tenants_path) = &watch.tenants {
+                return tenants::run(
+                    tenants::load(tenants_path)?,
+                    cli.network,
+                    watch.metrics_prefix.clone(),
+                );
+            }
+            let result = runtime()?.block_on(run(&cli, watch));
+            if let Err(err) = &result {
+                restarts::record_exit_error(err);
+            }
+            result
+        }
+        Command::Backfill => {
+            init_tracing(cli.log_level)?;
+            runtime()?.block_on(backfill(&cli))
+        }
+        Command::Report { window, address } => {
+            init_tracing(cli.log_level)?;
+            report(&cli, &window, address)
+        }
+        Command::CheckConfig => {
+            init_tracing(cli.log_level)?;
+            check_config(&cli)
+        }
+        Command::Import { files } => {
+            init_tracing(cli.log_level)?;
+            import(&files)
+        }
+        Command::Purge { address } => {
+            init_tracing(cli.log_level)?;
+            purge(&cli, address)
+        }
+        Command::Estimate { vault, days } => {
+            init_tracing(cli.log_level)?;
+            runtime()?.block_on(estimate(&cli, vault, days))
+        }
+    }
+}
+
+fn runtime() -> anyhow::Result<tokio::runtime::Runtime> {
+    Ok(tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?)
+}
+
+#[cfg(unix)]
+fn daemonize(watch: &WatchArgs) -> anyhow::Result<()> {
+    let mut daemon = daemonize::Daemonize::new()
+        .pid_file(&watch.pidfile)
+        .working_directory(env::current_dir()?);
+    if let Some(log_file) = &watch.log_file {
+        let log_file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_file)?;
+        daemon = daemon.stdout(log_file.try_clone()?).stderr(log_file);
+    }
+    daemon.start()?;
+    Ok(())
+}
+
+fn init_tracing(level: Level) -> anyhow::Result<()> {
     let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
+        .with_max_level(level)
         .with_line_number(true)
         .finish();
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
     tracing_log::LogTracer::init()?;
+    Ok(())
+}
 
-    info!("Initializing client...");
-    let mut info_client = InfoClient::new(None, Some(BaseUrl::Mainnet)).await?;
+fn open_store() -> anyhow::Result<store::FillStore> {
+    store::FillStore::open(store::path_from_env(), crypto::LineCipher::from_env()?)
+}
 
-    let vault_address = "0xdfc24b077bc1425ad1dea75bcb6f8158e10df303".to_string();
-    let req = InfoRequest {
-        type_: "vaultDetails".to_string(),
-        vault_address,
-    };
-    let info_payload = info_client
-        .http_client
-        .post("/info", serde_json::to_string(&req)?)
-        .await?;
-    let info: Info = serde_json::from_str(&info_payload)?;
-    let addresses = info.relationship.data.child_addresses;
-
-    info!("Subscribing user events...");
-    let (sender, mut receiver) = unbounded_channel();
-
-    let mut subscribed_users: Vec<H160> = Vec::new();
-    let mut subscription_ids: Vec<u32> = Vec::new();
-    for address in addresses {
-        let user = H160::from_str(address.as_str())?;
-        let res = info_client
-            .subscribe(Subscription::UserEvents { user }, sender.clone())
-            .await;
-        match res {
-            Ok(u32) => {
-                subscribed_users.push(user);
-                subscription_ids.push(u32);
-            }
-            Err(e) => warn!("failed to subscribe: {e:?}"),
-        }
+/// Imports historical fill files into the store, then exits.
+fn import(paths: &[PathBuf]) -> anyhow::Result<()> {
+    let mut store = open_store()?;
+    for path in paths {
+        let added = archive::import(&mut store, path)?;
+        info!("Imported {added} new fills from {}", path.display());
     }
+    Ok(())
+}
 
-    spawn(async move {
-        loop {
-            sleep(Duration::from_secs(30)).await;
-
-            info!("Resubscribing...");
-
-            let mut failed_subscription_ids: Vec<u32> = Vec::new();
-            let mut new_subscription_ids: Vec<u32> = Vec::new();
-            for (i, subscription_id) in subscription_ids.iter().enumerate() {
-                match info_client.unsubscribe(*subscription_id).await {
-                    Ok(()) => match subscribed_users.get(i) {
-                        Some(user) => {
-                            let subscribe_res = info_client
-                                .subscribe(Subscription::UserEvents { user: *user }, sender.clone())
-                                .await;
-                            if subscribe_res.is_err() {
-                                warn!("failed to subscribe {subscription_id:?}");
-                                failed_subscription_ids.push(*subscription_id);
-                                continue;
-                            }
-
-                            new_subscription_ids.push(subscribe_res.unwrap())
-                        }
-                        None => continue,
-                    },
-                    Err(err) => {
-                        warn!("failed to unsubscribe {subscription_id:?}: {err:?}");
-                        failed_subscription_ids.push(*subscription_id);
-                        continue;
-                    }
-                }
-            }
+/// Removes everything stored about one address, of every tenant when run
+/// with `--tenants`, then exits.
+fn purge(cli: &Cli, address: H160) -> anyhow::Result<()> {
+    let runtime = runtime()?;
+    match &cli.watch.tenants {
+        Some(tenants_path) => tenants::for_each(&tenants::load(tenants_path)?, |tenant| {
+            info!("Purging tenant {}", tenant.name);
+            runtime.block_on(purge_address(address))
+        })?,
+        None => runtime.block_on(purge_address(address))?,
+    }
+    info!("Data already sent to remote sinks such as Google Sheets is not affected");
+    Ok(())
+}
+
+/// Removes `address` from the stores, history, captures and Redis claims
+/// the environment configures.
+async fn purge_address(address: H160) -> anyhow::Result<()> {
+    let mut store = open_store()?;
+    let mut database = storage::open_from_env()?;
+    let everything = storage::Query {
+        since: 0,
+        user: Some(address),
+        limit: usize::MAX,
+    };
+    // Claims are keyed by fill id, so the ids are gathered before the fills go.
+    let mut ids: BTreeSet<String> = store
+        .query(&everything)?
+        .iter()
+        .map(store::StoredFill::id)
+        .collect();
+    if let Some(database) = &database {
+        ids.extend(
+            database
+                .query(&everything)?
+                .iter()
+                .map(store::StoredFill::id),
+        );
+    }
 
-            new_subscription_ids.append(&mut failed_subscription_ids);
-            new_subscription_ids.dedup();
-            subscription_ids = new_subscription_ids;
+    let fills = store.delete_user(address)?;
+    let database_fills = match &mut database {
+        Some(database) => database.delete_user(address)?,
+        None => 0,
+    };
+    let coins = history::TradeHistory::load(history::path_from_env())?.purge(address)?;
+    let captured = match tenants::var("CAPTURE_DIR") {
+        Ok(capture_dir) => capture::purge(capture_dir.as_ref(), address)?,
+        Err(_) => 0,
+    };
+    let claims = match dedup::SharedDedup::from_env().await? {
+        Some(shared_dedup) => {
+            let ids: Vec<String> = ids.into_iter().collect();
+            shared_dedup.release(&ids).await?
         }
-    });
+        None => 0,
+    };
+    info!(
+        "Purged {address:?}: {fills} stored fills, {database_fills} database rows, {coins} remembered coins, {captured} captured payloads, {claims} Redis claims"
+    );
+    Ok(())
+}
 
-    let trades: Arc<Mutex<Vec<TradeInfo>>> = Arc::new(Mutex::new(Vec::new()));
+async fn estimate(cli: &Cli, vault: H160, days: u64) -> anyhow::Result<()> {
+    let config = cli.load_config()?;
+    let filters = estimate::Filters::from_env(&config.categories)?;
 
-    let client = reqwest::Client::new();
-    let discord_webhook_url = env::var("DISCORD_WEBHOOK_URL")?;
+    let network = config.network;
+    let info_client = InfoClient::new(None, Some(network.base_url())).await?;
+    let estimate = estimate::estimate(&info_client, vault, days, &filters).await?;
+    info!("{}", estimate.render());
+    Ok(())
+}
 
-    let trades_arc_spawn = Arc::clone(&trades);
-    spawn(async move {
-        loop {
-            sleep(Duration::from_secs(5)).await;
+/// Fetches the latest fills of every watched address into the fill store, and
+/// the SQLite database when configured. A dry run only fetches them.
+async fn backfill(cli: &Cli) -> anyhow::Result<()> {
+    let config = cli.load_config()?;
+    let info_client = InfoClient::new(None, Some(config.network.base_url())).await?;
+    let watchlist = watchlist::Watchlist::fetch(&info_client, config).await?;
+    let users = watchlist.users();
+    if dry_run::is_enabled() {
+        let mut fills = 0;
+        for user in &users {
+            fills += info_client.user_fills(*user).await?.len();
+        }
+        info!(
+            "dry run, not stored: {fills} recent fills of {} addresses",
+            users.len()
+        );
+        return Ok(());
+    }
+
+    let mut store = open_store()?;
+    let backfill = reconcile::backfill(&info_client, &mut store, &users).await;
+    if let Some(mut database) = storage::open_from_env()? {
+        database.save_fills(store.fills())?;
+    }
+    info!(
+        "Backfilled {} new fills of {} addresses",
+        backfill.added.len(),
+        users.len()
+    );
+    if let Some(missed) = backfill.render_missed() {
+        println!("{missed}");
+    }
+    Ok(())
+}
 
-            let mut trades = trades_arc_spawn.lock().await;
-            let message = Vec::from_iter(trades.iter().map(|trade| {
-                let side = match trade.side.as_str() {
-                    "A" => "Long",
-                    "B" => "Short",
-                    _ => "Unknown",
-                };
-                format!("{} {} {}", side, trade.coin, trade.sz)
-            }))
-            .join("\n");
+/// Prints a digest of the stored fills within `window`, grouped by the config
+/// file's coin categories.
+fn report(cli: &Cli, window: &str, address: Option<H160>) -> anyhow::Result<()> {
+    let categories = cli.load_config()?.categories;
+    let window = commands::parse_duration(window)?;
+    let since = now_ms().saturating_sub(window.as_millis() as u64);
+    let store = open_store()?;
+    let stats = stats::compute(store.fills(), since, address);
+    println!("{}", stats.render_by_category(&categories));
+    let notes: Vec<String> = store
+        .fills()
+        .iter()
+        .filter(|fill| fill.time >= since && address.is_none_or(|address| fill.user == address))
+        .filter_map(store::StoredFill::render_note)
+        .collect();
+    if !notes.is_empty() {
+        println!("Notes:\n{}", notes.join("\n"));
+    }
+    Ok(())
+}
 
-            trades.clear();
+/// Loads the config file and the environment the watcher needs, then prints
+/// what would be watched.
+fn check_config(cli: &Cli) -> anyhow::Result<()> {
+    let config = cli.load_config()?;
+    if env::var("DISCORD_WEBHOOK_URL").is_err() {
+        return Err(anyhow::anyhow!("DISCORD_WEBHOOK_URL is not set"));
+    }
+    estimate::Filters::from_env(&config.categories)?;
+    crypto::LineCipher::from_env()?;
 
-            if message.len() == 0 {
-                continue;
-            }
+    println!(
+        "{} ({}) on {:?}",
+        cli.config.display(),
+        config.hash()?,
+        config.network
+    );
+    for vault in &config.vaults {
+        println!(
+            "vault {:?}{}",
+            vault.address,
+            vault
+                .label
+                .as_ref()
+                .map(|label| format!(" ({label})"))
+                .unwrap_or_default()
+        );
+    }
+    for address in &config.addresses {
+        println!(
+            "address {:?}{}",
+            address.address,
+            address
+                .label
+                .as_ref()
+                .map(|label| format!(" ({label})"))
+                .unwrap_or_default()
+        );
+    }
+    for (name, members) in config.clusters.iter() {
+        println!("cluster {name}: {members:?}");
+    }
+    Ok(())
+}
 
-            match client
-                .post(&discord_webhook_url)
-                .json(&json!({"content":message}))
-                .send()
-                .await
-            {
-                Ok(res) => {
-                    let status_code = res.status();
-                    if res.error_for_status().is_err() {
-                        warn!("unexpected status code: {status_code:?}")
-                    }
-                }
-                Err(err) => {
-                    warn!("failed to send to webhook: {err:?}");
-                    continue;
-                }
-            }
-        }
-    });
-
-    let trades_arc = Arc::clone(&trades);
-    loop {
-        match receiver.recv().await {
-            Some(Message::User(mut user)) => {
-                let mut trades = trades_arc.lock().await;
-                trades.append(&mut user.data.fills);
-            }
-            _ => (),
-        }
+async fn run(cli: &Cli, watch: &WatchArgs) -> anyhow::Result<()> {
+    let mut watcher = Watcher::new(cli.load_config()?).with_config_path(cli.config.clone());
+    if let Some(prefix) = watch.metrics_prefix.clone() {
+        watcher = watcher.with_metrics_prefix(prefix);
+    }
+    if watch.public {
+        watcher = watcher.with_public_api();
     }
+    watcher.run().await
 }
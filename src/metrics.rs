@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use ethers::types::H160;
+use prometheus::core::Metric;
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+use crate::context;
+
+/// Fill-to-notification latency buckets, in seconds.
+const LATENCY_BUCKETS: &[f64] = &[0.5, 1.0, 2.0, 3.0, 5.0, 7.5, 10.0, 15.0, 30.0, 60.0];
+
+/// The watcher's Prometheus metrics. Every metric carries a constant `vault`
+/// label and the configured name prefix, so one dashboard can slice a fleet
+/// of watchers by vault, address, coin and sink. Each watcher keeps its own,
+/// in its `context::Context`.
+pub struct Metrics {
+    registry: Registry,
+    fills: IntCounterVec,
+    notifications: IntCounterVec,
+    notification_latency: HistogramVec,
+    subscriptions: IntGaugeVec,
+    account_value: GaugeVec,
+}
+
+impl Metrics {
+    fn new(prefix: &str, vault: &str) -> anyhow::Result<Self> {
+        let labels = HashMap::from([("vault".to_string(), vault.to_string())]);
+        let registry = Registry::new_custom(Some(prefix.to_string()), Some(labels))?;
+
+        let fills = IntCounterVec::new(
+            Opts::new("fills_total", "Fills received from watched addresses"),
+            &["address", "coin"],
+        )?;
+        let notifications = IntCounterVec::new(
+            Opts::new(
+                "notifications_total",
+                "Notification requests by sink and outcome",
+            ),
+            &["sink", "outcome"],
+        )?;
+        let notification_latency = HistogramVec::new(
+            HistogramOpts::new(
+                "notification_latency_seconds",
+                "Time from a fill to its notification being delivered",
+            )
+            .buckets(LATENCY_BUCKETS.to_vec()),
+            &["sink"],
+        )?;
+        let subscriptions = IntGaugeVec::new(
+            Opts::new("subscriptions", "Watched addresses by subscription state"),
+            &["state"],
+        )?;
+        let account_value = GaugeVec::new(
+            Opts::new("account_value_usd", "Account value of each watched address"),
+            &["address"],
+        )?;
+
+        registry.register(Box::new(fills.clone()))?;
+        registry.register(Box::new(notifications.clone()))?;
+        registry.register(Box::new(notification_latency.clone()))?;
+        registry.register(Box::new(subscriptions.clone()))?;
+        registry.register(Box::new(account_value.clone()))?;
+        Ok(Metrics {
+            registry,
+            fills,
+            notifications,
+            notification_latency,
+            subscriptions,
+            account_value,
+        })
+    }
+}
+
+/// Sets up the current watcher's metrics; outside a watcher there are none.
+pub fn init(prefix: &str, vault: &str) -> anyhow::Result<()> {
+    if let Some(context) = context::current() {
+        let _ = context.metrics.set(Metrics::new(prefix, vault)?);
+    }
+    Ok(())
+}
+
+/// Runs `f` on the current watcher's metrics, if it has any.
+fn with_metrics<R>(f: impl FnOnce(&Metrics) -> R) -> Option<R> {
+    let context = context::current()?;
+    context.metrics.get().map(f)
+}
+
+pub fn record_fill(address: H160, coin: &str) {
+    with_metrics(|metrics| {
+        metrics
+            .fills
+            .with_label_values(&[&format!("{address:?}"), coin])
+            .inc();
+    });
+}
+
+pub fn record_notification(sink: &str, ok: bool) {
+    with_metrics(|metrics| {
+        let outcome = if ok { "ok" } else { "error" };
+        metrics
+            .notifications
+            .with_label_values(&[sink, outcome])
+            .inc();
+    });
+}
+
+pub fn observe_latency(sink: &str, seconds: f64) {
+    with_metrics(|metrics| {
+        metrics
+            .notification_latency
+            .with_label_values(&[sink])
+            .observe(seconds);
+    });
+}
+
+pub fn set_subscriptions(active: usize, retrying: usize) {
+    with_metrics(|metrics| {
+        metrics
+            .subscriptions
+            .with_label_values(&["active"])
+            .set(active as i64);
+        metrics
+            .subscriptions
+            .with_label_values(&["retrying"])
+            .set(retrying as i64);
+    });
+}
+
+pub fn set_account_value(address: H160, value: f64) {
+    with_metrics(|metrics| {
+        metrics
+            .account_value
+            .with_label_values(&[&format!("{address:?}")])
+            .set(value);
+    });
+}
+
+/// Returns how many latencies were observed for `sink` in total and how many of
+/// them within `threshold_secs`, which must be one of the histogram's buckets.
+pub fn latency_counts(sink: &str, threshold_secs: f64) -> Option<(u64, u64)> {
+    with_metrics(|metrics| {
+        let metric = metrics
+            .notification_latency
+            .with_label_values(&[sink])
+            .metric();
+        let histogram = metric.get_histogram();
+        let within = histogram
+            .get_bucket()
+            .iter()
+            .find(|bucket| bucket.get_upper_bound() == threshold_secs)?
+            .get_cumulative_count();
+        Some((histogram.get_sample_count(), within))
+    })
+    .flatten()
+}
+
+pub fn is_latency_bucket(seconds: f64) -> bool {
+    LATENCY_BUCKETS.contains(&seconds)
+}
+
+/// Renders every metric in the Prometheus text format.
+pub fn render() -> anyhow::Result<String> {
+    let gathered = with_metrics(|metrics| metrics.registry.gather()).unwrap_or_default();
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&gathered, &mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
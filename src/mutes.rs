@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::now_ms;
+
+/// Coins temporarily silenced at runtime, persisted as JSON with their expiry
+/// in milliseconds since the epoch.
+pub struct Mutes {
+    path: PathBuf,
+    until: HashMap<String, u64>,
+}
+
+impl Mutes {
+    pub fn load(path: PathBuf) -> anyhow::Result<Self> {
+        let until = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Mutes { path, until })
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        fs::write(&self.path, serde_json::to_string(&self.until)?)?;
+        Ok(())
+    }
+
+    pub fn mute(&mut self, coin: &str, duration: Duration) -> anyhow::Result<u64> {
+        let until = now_ms().saturating_add(duration.as_millis() as u64);
+        self.until.insert(coin.to_uppercase(), until);
+        self.save()?;
+        Ok(until)
+    }
+
+    pub fn unmute(&mut self, coin: &str) -> anyhow::Result<bool> {
+        let removed = self.until.remove(&coin.to_uppercase()).is_some();
+        self.save()?;
+        Ok(removed)
+    }
+
+    pub fn is_muted(&self, coin: &str) -> bool {
+        self.until
+            .get(&coin.to_uppercase())
+            .is_some_and(|until| *until > now_ms())
+    }
+
+    /// Returns active mutes, dropping expired ones.
+    pub fn active(&mut self) -> Vec<(String, u64)> {
+        let now = now_ms();
+        self.until.retain(|_, until| *until > now);
+        let mut active: Vec<(String, u64)> = self
+            .until
+            .iter()
+            .map(|(coin, until)| (coin.clone(), *until))
+            .collect();
+        active.sort();
+        active
+    }
+}
@@ -0,0 +1,367 @@
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use chrono::{NaiveDate, Utc};
+use tokio::task::spawn_blocking;
+use tracing::warn;
+
+use crate::context::spawn;
+use crate::notify::{apprise, discord, gotify, pacing, sms};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+    Critical,
+}
+
+impl Priority {
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Critical => "critical",
+        }
+    }
+
+    /// Gotify priorities run from 0 to 10; clients typically only pop up
+    /// notifications from 4 and make noise from 8.
+    fn gotify(self) -> u8 {
+        match self {
+            Priority::Low => 2,
+            Priority::Normal => 5,
+            Priority::High => 8,
+            Priority::Critical => 10,
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "low" => Ok(Priority::Low),
+            "normal" => Ok(Priority::Normal),
+            "high" => Ok(Priority::High),
+            "critical" => Ok(Priority::Critical),
+            _ => Err(anyhow!("unknown priority {value:?}")),
+        }
+    }
+}
+
+/// Delivers alerts to the Discord webhook and to any extra sinks configured
+/// for their priority.
+#[derive(Clone)]
+pub struct Alerter {
+    client: reqwest::Client,
+    pool: discord::WebhookPool,
+    username: String,
+    desktop_min_priority: Option<Priority>,
+    queue_jump_priority: Option<Priority>,
+    webhooks: Vec<String>,
+    gotify: Vec<GotifyConfig>,
+    json_urls: Vec<(String, apprise::PayloadFormat)>,
+    ntfy_urls: Vec<String>,
+    sms: Option<SmsSink>,
+    tts_priority: Option<Priority>,
+    trigger: Option<(Priority, String)>,
+    ops_webhook_url: Option<String>,
+}
+
+#[derive(Clone)]
+struct GotifyConfig {
+    server_url: String,
+    app_token: String,
+}
+
+#[derive(Clone)]
+pub struct SmsConfig {
+    pub account_sid: String,
+    pub auth_token: String,
+    pub from: String,
+    pub to: String,
+    pub daily_cap: u32,
+}
+
+/// Counts texts sent per UTC day so the cap holds across every clone of the
+/// alerter.
+#[derive(Clone)]
+struct SmsSink {
+    config: SmsConfig,
+    sent: Arc<Mutex<(NaiveDate, u32)>>,
+}
+
+impl SmsSink {
+    fn try_reserve(&self) -> bool {
+        let today = Utc::now().date_naive();
+        let mut sent = self.sent.lock().unwrap();
+        if sent.0 != today {
+            *sent = (today, 0);
+        }
+        if sent.1 >= self.config.daily_cap {
+            return false;
+        }
+        sent.1 += 1;
+        true
+    }
+}
+
+impl Alerter {
+    pub fn new(client: reqwest::Client, pool: discord::WebhookPool, username: String) -> Self {
+        Alerter {
+            client,
+            pool,
+            username,
+            desktop_min_priority: None,
+            queue_jump_priority: None,
+            webhooks: Vec::new(),
+            gotify: Vec::new(),
+            json_urls: Vec::new(),
+            ntfy_urls: Vec::new(),
+            sms: None,
+            tts_priority: None,
+            trigger: None,
+            ops_webhook_url: None,
+        }
+    }
+
+    /// Also raises native desktop notifications for alerts of at least `priority`.
+    pub fn with_desktop(mut self, priority: Priority) -> Self {
+        self.desktop_min_priority = Some(priority);
+        self
+    }
+
+    /// Sends alerts of at least `priority` ahead of paced trade notifications.
+    pub fn with_queue_jump(mut self, priority: Priority) -> Self {
+        self.queue_jump_priority = Some(priority);
+        self
+    }
+
+    /// Also posts every alert to another Discord webhook.
+    pub fn with_webhook(mut self, webhook_url: String) -> Self {
+        self.webhooks.push(webhook_url);
+        self
+    }
+
+    /// Also pushes every alert to a Gotify server.
+    pub fn with_gotify(mut self, server_url: String, app_token: String) -> Self {
+        self.gotify.push(GotifyConfig {
+            server_url,
+            app_token,
+        });
+        self
+    }
+
+    /// Also posts every alert as JSON in the given shape.
+    pub fn with_json(mut self, url: String, format: apprise::PayloadFormat) -> Self {
+        self.json_urls.push((url, format));
+        self
+    }
+
+    /// Also publishes every alert to an ntfy topic.
+    pub fn with_ntfy(mut self, url: String) -> Self {
+        self.ntfy_urls.push(url);
+        self
+    }
+
+    /// Also texts critical alerts, up to `config.daily_cap` messages per UTC day.
+    pub fn with_sms(mut self, config: SmsConfig) -> Self {
+        self.sms = Some(SmsSink {
+            config,
+            sent: Arc::new(Mutex::new((Utc::now().date_naive(), 0))),
+        });
+        self
+    }
+
+    /// Sets Discord's `tts` flag on alerts of at least `priority`, so they are
+    /// read aloud.
+    pub fn with_tts(mut self, priority: Priority) -> Self {
+        self.tts_priority = Some(priority);
+        self
+    }
+
+    /// Appends `trigger` to the Discord message of alerts of at least
+    /// `priority`, for bots that play a sound on it.
+    pub fn with_trigger(mut self, priority: Priority, trigger: String) -> Self {
+        self.trigger = Some((priority, trigger));
+        self
+    }
+
+    /// Posts operational notices to `webhook_url` rather than as alerts.
+    pub fn with_ops_webhook(mut self, webhook_url: String) -> Self {
+        self.ops_webhook_url = Some(webhook_url);
+        self
+    }
+
+    /// Posts an operational notice to the ops webhook. Without one it goes out
+    /// as an alert of `priority`, or only to the Discord webhook when
+    /// `priority` is `None`.
+    pub async fn post_ops(&self, priority: Option<Priority>, content: &str) {
+        match (&self.ops_webhook_url, priority) {
+            (Some(webhook_url), _) => {
+                discord::post(&self.client, webhook_url, Some(&self.username), content).await;
+            }
+            (None, Some(priority)) => self.send(priority, content).await,
+            (None, None) => {
+                self.pool
+                    .post(&self.client, Some(&self.username), content)
+                    .await;
+            }
+        }
+    }
+
+    /// Posts a notice to the ops webhook only, dropping it without one.
+    pub async fn post_ops_only(&self, content: &str) {
+        if let Some(webhook_url) = &self.ops_webhook_url {
+            discord::post(&self.client, webhook_url, Some(&self.username), content).await;
+        }
+    }
+
+    pub async fn send(&self, priority: Priority, content: &str) {
+        self.send_routed(priority, content, None).await;
+    }
+
+    /// Like `send`, with `mention` such as a role's `<@&id>` put before the
+    /// Discord message only.
+    pub async fn send_mentioning(&self, priority: Priority, content: &str, mention: Option<&str>) {
+        pacing::prioritized(self.deliver(priority, content, None, mention)).await;
+    }
+
+    /// Like `send`, but posts to `webhook_url` in place of the default Discord
+    /// webhook when a route applies.
+    pub async fn send_routed(&self, priority: Priority, content: &str, webhook_url: Option<&str>) {
+        if self
+            .queue_jump_priority
+            .is_some_and(|min_priority| priority >= min_priority)
+        {
+            pacing::prioritized(self.deliver(priority, content, webhook_url, None)).await;
+        } else {
+            self.deliver(priority, content, webhook_url, None).await;
+        }
+    }
+
+    async fn deliver(
+        &self,
+        priority: Priority,
+        content: &str,
+        webhook_url: Option<&str>,
+        mention: Option<&str>,
+    ) {
+        if self
+            .desktop_min_priority
+            .is_some_and(|min_priority| priority >= min_priority)
+        {
+            let summary = self.username.clone();
+            let body = content.to_string();
+            let shown = spawn_blocking(move || {
+                notify_rust::Notification::new()
+                    .summary(&summary)
+                    .body(&body)
+                    .show()
+                    .map(|_| ())
+            })
+            .await;
+            match shown {
+                Ok(Ok(())) => (),
+                Ok(Err(err)) => warn!("failed to show desktop notification: {err:?}"),
+                Err(err) => warn!("desktop notification task failed: {err:?}"),
+            }
+        }
+
+        for config in &self.gotify {
+            gotify::post(
+                &self.client,
+                &config.server_url,
+                &config.app_token,
+                &self.username,
+                content,
+                priority.gotify(),
+            )
+            .await;
+        }
+
+        if let Some(sink) = self.sms.as_ref().filter(|_| priority == Priority::Critical) {
+            if sink.try_reserve() {
+                let config = &sink.config;
+                sms::send(
+                    &self.client,
+                    &config.account_sid,
+                    &config.auth_token,
+                    &config.from,
+                    &config.to,
+                    &format!("{}: {content}", self.username),
+                )
+                .await;
+            } else {
+                warn!("daily sms cap reached, not texting alert");
+            }
+        }
+
+        for (url, format) in &self.json_urls {
+            apprise::post_json(
+                &self.client,
+                url,
+                *format,
+                &self.username,
+                content,
+                priority,
+            )
+            .await;
+        }
+
+        for url in &self.ntfy_urls {
+            apprise::post_ntfy(&self.client, url, &self.username, content, priority).await;
+        }
+
+        let mut discord_content = match &self.trigger {
+            Some((min_priority, trigger)) if priority >= *min_priority => {
+                format!("{content} {trigger}")
+            }
+            _ => content.to_string(),
+        };
+        if let Some(mention) = mention {
+            discord_content = format!("{mention} {discord_content}");
+        }
+        let tts = self
+            .tts_priority
+            .is_some_and(|min_priority| priority >= min_priority);
+        match webhook_url {
+            Some(webhook_url) => {
+                self.post_discord(webhook_url, &discord_content, tts).await;
+            }
+            None if tts => {
+                self.pool
+                    .post_tts(&self.client, Some(&self.username), &discord_content)
+                    .await;
+            }
+            None => {
+                self.pool
+                    .post(&self.client, Some(&self.username), &discord_content)
+                    .await;
+            }
+        }
+        for webhook_url in &self.webhooks {
+            self.post_discord(webhook_url, &discord_content, tts).await;
+        }
+    }
+
+    async fn post_discord(&self, webhook_url: &str, content: &str, tts: bool) {
+        if tts {
+            discord::post_tts(&self.client, webhook_url, Some(&self.username), content).await;
+        } else {
+            discord::post(&self.client, webhook_url, Some(&self.username), content).await;
+        }
+    }
+
+    /// Sends an alert in the background.
+    pub fn spawn(&self, priority: Priority, content: String) {
+        let alerter = self.clone();
+        spawn(async move {
+            alerter.send(priority, &content).await;
+        });
+    }
+}
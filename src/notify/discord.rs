@@ -0,0 +1,456 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::metrics;
+use crate::notify::{dry_run, pacing, retry};
+
+#[derive(Deserialize)]
+pub struct PostedMessage {
+    pub id: String,
+    pub channel_id: String,
+}
+
+fn payload(username: Option<&str>, content: &str) -> Value {
+    let mut payload = json!({ "content": content });
+    if let Some(username) = username {
+        payload["username"] = json!(username);
+    }
+    payload
+}
+
+/// Consecutive failures after which a webhook is benched for `BENCH_DURATION`.
+const MAX_FAILURES: u32 = 3;
+const BENCH_DURATION: Duration = Duration::from_secs(60);
+
+struct PooledWebhook {
+    url: String,
+    failures: u32,
+    benched_until: Option<Instant>,
+}
+
+struct PoolState {
+    webhooks: Vec<PooledWebhook>,
+    next: usize,
+}
+
+/// Webhooks for the same channel used round-robin, so bursts are spread over
+/// each webhook's rate limit. Webhooks that keep failing sit out for a while.
+#[derive(Clone)]
+pub struct WebhookPool {
+    state: Arc<Mutex<PoolState>>,
+}
+
+impl WebhookPool {
+    pub fn new(urls: Vec<String>) -> anyhow::Result<Self> {
+        if urls.is_empty() {
+            return Err(anyhow!("webhook pool needs at least one url"));
+        }
+        let webhooks = urls
+            .into_iter()
+            .map(|url| PooledWebhook {
+                url,
+                failures: 0,
+                benched_until: None,
+            })
+            .collect();
+        Ok(WebhookPool {
+            state: Arc::new(Mutex::new(PoolState { webhooks, next: 0 })),
+        })
+    }
+
+    /// Returns the next webhook that isn't benched, or simply the next one
+    /// when all of them are.
+    pub fn pick(&self) -> String {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let len = state.webhooks.len();
+        let start = state.next;
+        let index = (0..len)
+            .map(|offset| (start + offset) % len)
+            .find(|index| {
+                state.webhooks[*index]
+                    .benched_until
+                    .is_none_or(|until| until <= now)
+            })
+            .unwrap_or(start % len);
+        state.next = (index + 1) % len;
+        state.webhooks[index].url.clone()
+    }
+
+    pub fn report(&self, url: &str, ok: bool) {
+        let mut state = self.state.lock().unwrap();
+        let Some(webhook) = state.webhooks.iter_mut().find(|webhook| webhook.url == url) else {
+            return;
+        };
+        if ok {
+            webhook.failures = 0;
+            webhook.benched_until = None;
+            return;
+        }
+        webhook.failures += 1;
+        if webhook.failures >= MAX_FAILURES {
+            warn!(
+                "benching webhook after {} consecutive failures",
+                webhook.failures
+            );
+            webhook.benched_until = Some(Instant::now() + BENCH_DURATION);
+        }
+    }
+
+    /// Posts through the next webhook in the pool and records the outcome.
+    pub async fn post(
+        &self,
+        client: &reqwest::Client,
+        username: Option<&str>,
+        content: &str,
+    ) -> bool {
+        let webhook_url = self.pick();
+        let ok = post(client, &webhook_url, username, content).await;
+        self.report(&webhook_url, ok);
+        ok
+    }
+
+    /// Like `post`, read aloud by Discord's text-to-speech.
+    pub async fn post_tts(
+        &self,
+        client: &reqwest::Client,
+        username: Option<&str>,
+        content: &str,
+    ) -> bool {
+        let webhook_url = self.pick();
+        let ok = post_tts(client, &webhook_url, username, content).await;
+        self.report(&webhook_url, ok);
+        ok
+    }
+
+    /// Like `post`, with embeds in place of text.
+    pub async fn post_embeds(
+        &self,
+        client: &reqwest::Client,
+        username: Option<&str>,
+        embeds: &[Value],
+    ) -> bool {
+        let webhook_url = self.pick();
+        let ok = post_embeds(client, &webhook_url, username, embeds).await;
+        self.report(&webhook_url, ok);
+        ok
+    }
+
+    /// Like `post_with_id`; later edits must go to the returned webhook url.
+    pub async fn post_with_id(
+        &self,
+        client: &reqwest::Client,
+        username: Option<&str>,
+        content: &str,
+    ) -> Option<(String, PostedMessage)> {
+        let webhook_url = self.pick();
+        let posted = post_with_id(client, &webhook_url, username, content).await;
+        self.report(&webhook_url, posted.is_some());
+        posted.map(|posted| (webhook_url, posted))
+    }
+}
+
+/// Discord rejects message content over this many characters.
+const MAX_CONTENT_LEN: usize = 2000;
+/// Room left in each part for `\n(part 12/34)`.
+const PART_INDICATOR_LEN: usize = 16;
+
+/// Splits `content` at line breaks into messages within Discord's limit,
+/// marked `(part 1/N)` and so on when there is more than one. Lines too long
+/// for a message on their own are cut.
+pub fn split_content(content: &str) -> Vec<String> {
+    if content.chars().count() <= MAX_CONTENT_LEN {
+        return vec![content.to_string()];
+    }
+    let limit = MAX_CONTENT_LEN - PART_INDICATOR_LEN;
+    let pieces = content.lines().flat_map(|line| {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.len() <= limit {
+            vec![line.to_string()]
+        } else {
+            chars.chunks(limit).map(String::from_iter).collect()
+        }
+    });
+
+    let mut parts: Vec<Vec<String>> = Vec::new();
+    let mut part_len = 0;
+    for piece in pieces {
+        let piece_len = piece.chars().count();
+        match parts.last_mut() {
+            Some(part) if part_len + 1 + piece_len <= limit => {
+                part.push(piece);
+                part_len += 1 + piece_len;
+            }
+            _ => {
+                parts.push(vec![piece]);
+                part_len = piece_len;
+            }
+        }
+    }
+    let total = parts.len();
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(index, lines)| format!("{}\n(part {}/{total})", lines.join("\n"), index + 1))
+        .collect()
+}
+
+/// Returns whether the webhook accepted the message, every part of it when
+/// it had to be split.
+pub async fn post(
+    client: &reqwest::Client,
+    webhook_url: &str,
+    username: Option<&str>,
+    content: &str,
+) -> bool {
+    post_parts(client, webhook_url, username, content, false).await
+}
+
+/// Like `post`, with the `tts` flag set so Discord reads the message aloud.
+pub async fn post_tts(
+    client: &reqwest::Client,
+    webhook_url: &str,
+    username: Option<&str>,
+    content: &str,
+) -> bool {
+    post_parts(client, webhook_url, username, content, true).await
+}
+
+async fn post_parts(
+    client: &reqwest::Client,
+    webhook_url: &str,
+    username: Option<&str>,
+    content: &str,
+    tts: bool,
+) -> bool {
+    let mut ok = true;
+    for part in split_content(content) {
+        let mut payload = payload(username, &part);
+        if tts {
+            payload["tts"] = json!(true);
+        }
+        ok &= post_payload(client, webhook_url, &payload).await;
+    }
+    ok
+}
+
+/// Posts up to ten embeds and no text.
+pub async fn post_embeds(
+    client: &reqwest::Client,
+    webhook_url: &str,
+    username: Option<&str>,
+    embeds: &[Value],
+) -> bool {
+    let mut payload = json!({ "embeds": embeds });
+    if let Some(username) = username {
+        payload["username"] = json!(username);
+    }
+    post_payload(client, webhook_url, &payload).await
+}
+
+/// Posts that fail for a reason that may pass are retried later when the
+/// retry queue is on; the caller still sees the failure.
+async fn post_payload(client: &reqwest::Client, webhook_url: &str, payload: &Value) -> bool {
+    if dry_run::intercept("discord", payload) {
+        return true;
+    }
+    pacing::wait().await;
+    match send_payload(client, webhook_url, payload).await {
+        Outcome::Sent => true,
+        Outcome::Retry(retry_after) => {
+            retry::enqueue(client, webhook_url, payload, retry_after);
+            false
+        }
+        Outcome::Failed => false,
+    }
+}
+
+/// How a post went, with network errors, 429 and 5xx worth another try.
+pub(crate) enum Outcome {
+    Sent,
+    /// With Discord's `Retry-After`, if it sent one.
+    Retry(Option<Duration>),
+    Failed,
+}
+
+pub(crate) async fn send_payload(
+    client: &reqwest::Client,
+    webhook_url: &str,
+    payload: &Value,
+) -> Outcome {
+    let outcome = match client.post(webhook_url).json(payload).send().await {
+        Ok(res) => {
+            let status_code = res.status();
+            if !status_code.is_client_error() && !status_code.is_server_error() {
+                Outcome::Sent
+            } else {
+                warn!("unexpected status code: {status_code:?}");
+                if status_code == reqwest::StatusCode::TOO_MANY_REQUESTS
+                    || status_code.is_server_error()
+                {
+                    Outcome::Retry(retry_after(&res))
+                } else {
+                    Outcome::Failed
+                }
+            }
+        }
+        Err(err) => {
+            warn!("failed to send to webhook: {err:?}");
+            Outcome::Retry(None)
+        }
+    };
+    metrics::record_notification("discord", matches!(outcome, Outcome::Sent));
+    outcome
+}
+
+/// `Retry-After` in seconds, which Discord sends with fractions.
+fn retry_after(res: &reqwest::Response) -> Option<Duration> {
+    res.headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+/// Posts a message and returns it so it can be edited later.
+pub async fn post_with_id(
+    client: &reqwest::Client,
+    webhook_url: &str,
+    username: Option<&str>,
+    content: &str,
+) -> Option<PostedMessage> {
+    // Nothing is posted, so there is no message to edit later.
+    if dry_run::intercept("discord", content) {
+        return None;
+    }
+    pacing::wait().await;
+    let res = client
+        .post(webhook_url)
+        .query(&[("wait", "true")])
+        .json(&payload(username, content))
+        .send()
+        .await
+        .and_then(|res| res.error_for_status());
+    let posted = match res {
+        Ok(res) => match res.json::<PostedMessage>().await {
+            Ok(message) => Some(message),
+            Err(err) => {
+                warn!("failed to read posted message: {err:?}");
+                None
+            }
+        },
+        Err(err) => {
+            warn!("failed to send to webhook: {err:?}");
+            None
+        }
+    };
+    metrics::record_notification("discord", posted.is_some());
+    posted
+}
+
+pub async fn edit(client: &reqwest::Client, webhook_url: &str, message_id: &str, content: &str) {
+    let mut url = match reqwest::Url::parse(webhook_url) {
+        Ok(url) => url,
+        Err(err) => {
+            warn!("invalid webhook url: {err:?}");
+            return;
+        }
+    };
+    if let Ok(mut segments) = url.path_segments_mut() {
+        segments.push("messages").push(message_id);
+    }
+
+    if dry_run::intercept("discord", format!("edit of {message_id}: {content}")) {
+        return;
+    }
+    pacing::wait().await;
+    match client
+        .patch(url)
+        .json(&json!({ "content": content }))
+        .send()
+        .await
+    {
+        Ok(res) => {
+            let status_code = res.status();
+            if res.error_for_status().is_err() {
+                warn!("unexpected status code: {status_code:?}")
+            }
+        }
+        Err(err) => warn!("failed to edit webhook message: {err:?}"),
+    }
+}
+
+/// Pins a message; webhooks cannot pin, so this needs a bot token with the
+/// Manage Messages permission in the channel.
+pub async fn pin(client: &reqwest::Client, bot_token: &str, channel_id: &str, message_id: &str) {
+    let url = format!("https://discord.com/api/v10/channels/{channel_id}/pins/{message_id}");
+    if dry_run::intercept("discord", format!("pin of {message_id}")) {
+        return;
+    }
+    pacing::wait().await;
+    match client
+        .put(url)
+        .header("Authorization", format!("Bot {bot_token}"))
+        .header("Content-Length", "0")
+        .send()
+        .await
+    {
+        Ok(res) => {
+            let status_code = res.status();
+            if res.error_for_status().is_err() {
+                warn!("unexpected status code: {status_code:?}")
+            }
+        }
+        Err(err) => warn!("failed to pin message: {err:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_short_content_whole() {
+        assert_eq!(split_content("BTC Long 1.5"), vec!["BTC Long 1.5"]);
+    }
+
+    #[test]
+    fn splits_at_line_breaks_within_the_limit() {
+        let lines: Vec<String> = (0..30)
+            .map(|i| format!("{i:02}{}", "x".repeat(98)))
+            .collect();
+        let parts = split_content(&lines.join("\n"));
+
+        assert_eq!(parts.len(), 2);
+        let mut rejoined = Vec::new();
+        for (index, part) in parts.iter().enumerate() {
+            assert!(part.chars().count() <= MAX_CONTENT_LEN);
+            let (body, marker) = part.rsplit_once('\n').unwrap();
+            assert_eq!(marker, format!("(part {}/2)", index + 1));
+            rejoined.extend(body.lines().map(str::to_string));
+        }
+        assert_eq!(rejoined, lines);
+    }
+
+    #[test]
+    fn cuts_lines_too_long_for_one_message() {
+        let line = "é".repeat(5000);
+        let parts = split_content(&line);
+
+        assert_eq!(parts.len(), 3);
+        let mut rejoined = String::new();
+        for part in &parts {
+            assert!(part.chars().count() <= MAX_CONTENT_LEN);
+            rejoined.push_str(part.rsplit_once('\n').unwrap().0);
+        }
+        assert_eq!(rejoined, line);
+    }
+}
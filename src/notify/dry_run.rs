@@ -0,0 +1,41 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::info;
+
+/// Set once at startup by `--dry-run`.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Logs what would have gone to `sink` and returns true during a dry run, in
+/// which case the caller returns as if it had sent it.
+pub fn intercept(sink: &str, payload: impl Display) -> bool {
+    if !is_enabled() {
+        return false;
+    }
+    info!("dry run, not sent to {sink}: {payload}");
+    true
+}
+
+/// `intercept` with the body of a request that is about to be sent.
+pub fn intercept_request(sink: &str, request: &reqwest::RequestBuilder) -> bool {
+    if !is_enabled() {
+        return false;
+    }
+    let body = request
+        .try_clone()
+        .and_then(|request| request.build().ok())
+        .and_then(|request| {
+            let bytes = request.body()?.as_bytes()?;
+            Some(String::from_utf8_lossy(bytes).into_owned())
+        })
+        .unwrap_or_default();
+    intercept(sink, body)
+}
@@ -0,0 +1,41 @@
+use serde_json::json;
+use tracing::warn;
+
+use crate::metrics;
+use crate::notify::{dry_run, pacing};
+
+pub async fn post(
+    client: &reqwest::Client,
+    server_url: &str,
+    app_token: &str,
+    title: &str,
+    message: &str,
+    priority: u8,
+) {
+    let url = format!("{}/message", server_url.trim_end_matches('/'));
+    if dry_run::intercept("gotify", format!("{title}: {message}")) {
+        return;
+    }
+    pacing::wait().await;
+    let ok = match client
+        .post(url)
+        .header("X-Gotify-Key", app_token)
+        .json(&json!({ "title": title, "message": message, "priority": priority }))
+        .send()
+        .await
+    {
+        Ok(res) => {
+            let status_code = res.status();
+            let ok = res.error_for_status().is_ok();
+            if !ok {
+                warn!("unexpected status code from gotify: {status_code:?}")
+            }
+            ok
+        }
+        Err(err) => {
+            warn!("failed to send to gotify: {err:?}");
+            false
+        }
+    };
+    metrics::record_notification("gotify", ok);
+}
@@ -0,0 +1,90 @@
+use serde_json::json;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tracing::warn;
+
+use crate::hyperliquid::events::FillEvent;
+use crate::metrics;
+use crate::notify::{dry_run, pacing};
+
+const EVENT_TYPE: &str = "god_watcher_fill";
+const SENSOR: &str = "sensor.god_watcher_last_fill";
+
+pub enum Auth {
+    /// Posts to `/api/webhook/{id}` for a webhook-triggered automation.
+    Webhook(String),
+    /// Fires `god_watcher_fill` events and updates `sensor.god_watcher_last_fill`
+    /// through the REST API with a long-lived access token.
+    Token(String),
+}
+
+async fn post(request: reqwest::RequestBuilder) {
+    if dry_run::intercept_request("home assistant", &request) {
+        return;
+    }
+    pacing::wait().await;
+    let ok = match request.send().await {
+        Ok(res) => {
+            let status_code = res.status();
+            let ok = res.error_for_status().is_ok();
+            if !ok {
+                warn!("unexpected status code from home assistant: {status_code:?}")
+            }
+            ok
+        }
+        Err(err) => {
+            warn!("failed to send to home assistant: {err:?}");
+            false
+        }
+    };
+    metrics::record_notification("home_assistant", ok);
+}
+
+pub async fn publish(
+    client: reqwest::Client,
+    base_url: String,
+    auth: Auth,
+    mut events: Receiver<FillEvent>,
+) {
+    let base_url = base_url.trim_end_matches('/');
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("home assistant sink dropped {skipped} events");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        match &auth {
+            Auth::Webhook(webhook_id) => {
+                post(
+                    client
+                        .post(format!("{base_url}/api/webhook/{webhook_id}"))
+                        .json(&event),
+                )
+                .await
+            }
+            Auth::Token(token) => {
+                post(
+                    client
+                        .post(format!("{base_url}/api/events/{EVENT_TYPE}"))
+                        .bearer_auth(token)
+                        .json(&event),
+                )
+                .await;
+                let state = json!({
+                    "state": format!("{} {} {} @ {}", event.side, event.sz, event.coin, event.px),
+                    "attributes": event,
+                });
+                post(
+                    client
+                        .post(format!("{base_url}/api/states/{SENSOR}"))
+                        .bearer_auth(token)
+                        .json(&state),
+                )
+                .await;
+            }
+        }
+    }
+}
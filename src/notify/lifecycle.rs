@@ -0,0 +1,93 @@
+use ethers::types::H160;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::config::Network;
+use crate::notify::dry_run;
+use crate::{metrics, now_ms, tenants};
+
+/// What happened to the watcher itself, as opposed to the trades it watches.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Started {
+        version: &'static str,
+        network: Network,
+        config_hash: String,
+    },
+    /// The startup subscriptions and backfill are done.
+    Subscribed {
+        subscribed: usize,
+        retrying: usize,
+        backfilled: usize,
+    },
+    /// A retrying subscription is active again.
+    Reconnected {
+        user: H160,
+    },
+    /// `reason` is `subscription`, `latency_slo` or `failover`.
+    Degraded {
+        reason: &'static str,
+        detail: String,
+    },
+    Shutdown,
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    #[serde(flatten)]
+    event: &'a Event,
+    vault: &'a str,
+    time: u64,
+}
+
+/// Posts lifecycle events as JSON to `LIFECYCLE_WEBHOOK_URL`, such as
+/// `{"event":"reconnected","user":"0x...","vault":"HLP","time":1700000000000}`,
+/// so external monitoring can follow the watcher. Does nothing when unset.
+#[derive(Clone)]
+pub struct LifecycleWebhook {
+    client: reqwest::Client,
+    url: Option<String>,
+    vault_name: String,
+}
+
+impl LifecycleWebhook {
+    pub fn from_env(client: reqwest::Client, vault_name: String) -> Self {
+        LifecycleWebhook {
+            client,
+            url: tenants::var("LIFECYCLE_WEBHOOK_URL").ok(),
+            vault_name,
+        }
+    }
+
+    /// Sent right away rather than paced: monitoring wants it on time, and it
+    /// does not go to Discord.
+    pub async fn emit(&self, event: Event) {
+        let Some(url) = &self.url else {
+            return;
+        };
+        let request = self.client.post(url).json(&Payload {
+            event: &event,
+            vault: &self.vault_name,
+            time: now_ms(),
+        });
+        if dry_run::intercept_request("lifecycle", &request) {
+            return;
+        }
+        let ok = match request.send().await {
+            Ok(res) => {
+                let status_code = res.status();
+                let ok = res.error_for_status().is_ok();
+                if !ok {
+                    warn!("unexpected status code from lifecycle webhook: {status_code:?}")
+                }
+                ok
+            }
+            Err(err) => {
+                warn!("failed to send lifecycle event: {err:?}");
+                false
+            }
+        };
+        metrics::record_notification("lifecycle", ok);
+    }
+}
@@ -0,0 +1,17 @@
+//! Notification sinks and event publishers.
+
+pub mod alerts;
+pub mod apprise;
+pub mod discord;
+pub mod dry_run;
+pub mod gotify;
+pub mod home_assistant;
+pub mod lifecycle;
+pub mod notifier;
+pub mod pacing;
+pub mod rabbitmq;
+pub mod relay;
+pub mod retry;
+pub mod sheets;
+pub mod sms;
+pub mod zmq;
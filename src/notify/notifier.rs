@@ -0,0 +1,406 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use ethers::types::H160;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::config::Destination;
+use crate::hyperliquid::events::FillEvent;
+use crate::notify::{discord, dry_run, pacing};
+use crate::{metrics, tenants};
+
+/// One batch of fills, rendered as lines, ready to go out.
+#[derive(Clone, Debug, Serialize)]
+pub struct TradeEvent {
+    pub title: String,
+    /// One line per fill, in the order of `fills`.
+    pub lines: Vec<String>,
+    /// Lines about orders rather than single fills, such as completed orders.
+    pub notes: Vec<String>,
+    pub fills: Vec<FillEvent>,
+    /// Labels of the addresses in `fills` that have one.
+    pub labels: BTreeMap<H160, String>,
+    /// Where the batch goes in place of each notifier's default, by its
+    /// addresses or its coin.
+    #[serde(skip)]
+    pub route: Destination,
+}
+
+impl TradeEvent {
+    fn text(&self) -> String {
+        self.lines
+            .iter()
+            .chain(&self.notes)
+            .cloned()
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// Discord allows ten embeds per message, 1024 characters per field value and
+/// 6000 characters across all embeds.
+const MAX_EMBEDS: usize = 10;
+const MAX_FIELD_LEN: usize = 1024;
+const MAX_DESCRIPTION_LEN: usize = 4096;
+const MAX_EMBEDS_LEN: usize = 6000;
+
+const LONG_COLOR: u32 = 0x2ecc71;
+const SHORT_COLOR: u32 = 0xe74c3c;
+
+/// Room left in each message's author line for ` (part 12/34)`.
+const PART_INDICATOR_LEN: usize = 16;
+
+/// One embed per address, colored by the side of most of its fills, with its
+/// fills' lines, which carry what the watcher adds such as positions, fees and
+/// book depth, and columns for coin, size, price and notional, plus one for any
+/// notes, split
+/// into as many messages as Discord's limits take. Returns `None` when a
+/// single embed does not fit them.
+fn embeds(event: &TradeEvent) -> Option<Vec<Vec<Value>>> {
+    let mut by_user: BTreeMap<H160, (Vec<&FillEvent>, Vec<&str>)> = BTreeMap::new();
+    for (index, fill) in event.fills.iter().enumerate() {
+        let (fills, lines) = by_user.entry(fill.user).or_default();
+        fills.push(fill);
+        if let Some(line) = event.lines.get(index) {
+            lines.push(line);
+        }
+    }
+
+    let mut embeds: Vec<(Value, usize)> = Vec::new();
+    for (user, (fills, lines)) in by_user {
+        let longs = fills.iter().filter(|fill| fill.side == "buy").count();
+        let color = if longs * 2 >= fills.len() {
+            LONG_COLOR
+        } else {
+            SHORT_COLOR
+        };
+        let column = |render: &dyn Fn(&FillEvent) -> String| -> String {
+            fills
+                .iter()
+                .map(|fill| render(fill))
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+        let columns = [
+            (
+                "Coin",
+                column(&|fill| match &fill.permalink {
+                    Some(permalink) => {
+                        format!("[{} {}]({permalink})", fill.side_label(), fill.coin)
+                    }
+                    None => format!("{} {}", fill.side_label(), fill.coin),
+                }),
+            ),
+            ("Size", column(&|fill| fill.sz.to_string())),
+            ("Price", column(&|fill| fill.px.to_string())),
+            (
+                "Notional",
+                column(&|fill| format!("${:.2}", fill.px * fill.sz)),
+            ),
+        ];
+        if columns.iter().any(|(_, value)| value.len() > MAX_FIELD_LEN) {
+            return None;
+        }
+        let description = lines.join("\n");
+        if description.len() > MAX_DESCRIPTION_LEN {
+            return None;
+        }
+        let title = match event.labels.get(&user) {
+            Some(label) => format!("{label} ({user:?})"),
+            None => format!("{user:?}"),
+        };
+        let last_time = fills.iter().map(|fill| fill.time).max().unwrap_or_default();
+        let footer = Utc
+            .timestamp_millis_opt(last_time as i64)
+            .single()?
+            .format("%Y-%m-%d %H:%M:%S UTC")
+            .to_string();
+        let len = title.len()
+            + description.len()
+            + footer.len()
+            + columns
+                .iter()
+                .map(|(name, value)| name.len() + value.len())
+                .sum::<usize>();
+        let fields: Vec<Value> = columns
+            .iter()
+            .map(|(name, value)| json!({ "name": name, "value": value, "inline": true }))
+            .collect();
+        let embed = json!({
+            "title": title,
+            "description": description,
+            "color": color,
+            "fields": fields,
+            "footer": { "text": footer },
+        });
+        embeds.push((embed, len));
+    }
+    if !event.notes.is_empty() {
+        let description = event.notes.join("\n");
+        let len = description.len();
+        embeds.push((json!({ "description": description }), len));
+    }
+
+    let mut messages: Vec<Vec<Value>> = Vec::new();
+    let mut message_len = 0;
+    for (embed, len) in embeds {
+        match messages.last_mut() {
+            Some(message) if message.len() < MAX_EMBEDS && message_len + len <= MAX_EMBEDS_LEN => {
+                message.push(embed);
+                message_len += len;
+            }
+            _ => {
+                message_len = event.title.len() + PART_INDICATOR_LEN + len;
+                if message_len > MAX_EMBEDS_LEN {
+                    return None;
+                }
+                messages.push(vec![embed]);
+            }
+        }
+    }
+    let total = messages.len();
+    for (index, message) in messages.iter_mut().enumerate() {
+        let name = match total {
+            1 => event.title.clone(),
+            _ => format!("{} (part {}/{total})", event.title, index + 1),
+        };
+        message[0]["author"] = json!({ "name": name });
+    }
+    Some(messages).filter(|messages| !messages.is_empty())
+}
+
+/// A destination for batches of fills.
+/// What a notifier is sent, from `<NAME>_EVENTS` with the notifier's name in
+/// upper case, such as `SLACK_EVENTS=fills,orders` (default: fills).
+#[derive(Clone, Copy, Debug)]
+pub struct EventKinds {
+    /// Batches of fills.
+    pub fills: bool,
+    /// Orders placed, modified or canceled.
+    pub orders: bool,
+}
+
+impl EventKinds {
+    pub fn from_env(name: &str) -> anyhow::Result<Self> {
+        let key = format!("{}_EVENTS", name.to_uppercase());
+        let Ok(value) = tenants::var(&key) else {
+            return Ok(EventKinds {
+                fills: true,
+                orders: false,
+            });
+        };
+        let mut kinds = EventKinds {
+            fills: false,
+            orders: false,
+        };
+        for kind in value
+            .split(',')
+            .map(str::trim)
+            .filter(|kind| !kind.is_empty())
+        {
+            match kind {
+                "fills" => kinds.fills = true,
+                "orders" => kinds.orders = true,
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "unknown event {kind:?} in {key}, expected fills or orders"
+                    ))
+                }
+            }
+        }
+        Ok(kinds)
+    }
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Label for metrics and logs.
+    fn name(&self) -> &'static str;
+
+    /// Returns whether the batch was delivered.
+    async fn send(&self, event: &TradeEvent) -> bool;
+}
+
+/// Posts embeds, or text when the batch does not fit in embeds, to the
+/// event's routed webhook or, without one, through the webhook pool.
+pub struct DiscordNotifier {
+    pub client: reqwest::Client,
+    pub pool: discord::WebhookPool,
+    pub username: String,
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    async fn send(&self, event: &TradeEvent) -> bool {
+        if let Some(messages) = embeds(event) {
+            let mut ok = true;
+            for embeds in &messages {
+                ok &= match &event.route.discord_webhook_url {
+                    Some(webhook_url) => {
+                        discord::post_embeds(
+                            &self.client,
+                            webhook_url,
+                            Some(&self.username),
+                            embeds,
+                        )
+                        .await
+                    }
+                    None => {
+                        self.pool
+                            .post_embeds(&self.client, Some(&self.username), embeds)
+                            .await
+                    }
+                };
+            }
+            return ok;
+        }
+
+        let content = format!("**{}**\n{}", event.title, event.text());
+        match &event.route.discord_webhook_url {
+            Some(webhook_url) => {
+                discord::post(&self.client, webhook_url, Some(&self.username), &content).await
+            }
+            None => {
+                self.pool
+                    .post(&self.client, Some(&self.username), &content)
+                    .await
+            }
+        }
+    }
+}
+
+/// Posts to a Slack incoming webhook.
+pub struct SlackNotifier {
+    pub client: reqwest::Client,
+    pub webhook_url: String,
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    async fn send(&self, event: &TradeEvent) -> bool {
+        let text = format!("*{}*\n{}", event.title, event.text());
+        post(
+            self.name(),
+            self.client
+                .post(&self.webhook_url)
+                .json(&json!({ "text": text })),
+        )
+        .await
+    }
+}
+
+/// Escapes text for Telegram's MarkdownV2, where every reserved character
+/// outside of formatting must be backslash-escaped or the message is rejected.
+pub fn escape_markdown_v2(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "_*[]()~`>#+-=|{}.!\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Sends to a Telegram chat through the Bot API, with the title in bold, or to
+/// the chat the event is routed to.
+pub struct TelegramNotifier {
+    pub client: reqwest::Client,
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    async fn send(&self, event: &TradeEvent) -> bool {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+        let text = format!(
+            "*{}*\n{}",
+            escape_markdown_v2(&event.title),
+            escape_markdown_v2(&event.text())
+        );
+        post(
+            self.name(),
+            self.client.post(url).json(&json!({
+                "chat_id": event.route.telegram_chat_id.as_deref().unwrap_or(&self.chat_id),
+                "text": text,
+                "parse_mode": "MarkdownV2",
+            })),
+        )
+        .await
+    }
+}
+
+/// Posts the whole event, fills included, as JSON.
+pub struct JsonNotifier {
+    pub client: reqwest::Client,
+    pub url: String,
+}
+
+#[async_trait]
+impl Notifier for JsonNotifier {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    async fn send(&self, event: &TradeEvent) -> bool {
+        post(self.name(), self.client.post(&self.url).json(event)).await
+    }
+}
+
+async fn post(sink: &str, request: reqwest::RequestBuilder) -> bool {
+    if dry_run::intercept_request(sink, &request) {
+        return true;
+    }
+    pacing::wait().await;
+    let ok = match request.send().await {
+        Ok(res) => {
+            let status_code = res.status();
+            let ok = res.error_for_status().is_ok();
+            if !ok {
+                warn!("unexpected status code from {sink}: {status_code:?}")
+            }
+            ok
+        }
+        Err(err) => {
+            warn!("failed to send to {sink}: {err:?}");
+            false
+        }
+    };
+    metrics::record_notification(sink, ok);
+    ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_markdown_v2_reserved_characters() {
+        assert_eq!(
+            escape_markdown_v2("kPEPE-USD 1.5 (x2) #1!"),
+            r"kPEPE\-USD 1\.5 \(x2\) \#1\!"
+        );
+        assert_eq!(escape_markdown_v2(r"a\b_c*d"), r"a\\b\_c\*d");
+    }
+
+    #[test]
+    fn leaves_plain_text_alone() {
+        assert_eq!(escape_markdown_v2("BTC Long 0x12ab"), "BTC Long 0x12ab");
+    }
+}
@@ -0,0 +1,70 @@
+use std::future::Future;
+use std::time::Duration;
+
+use rand::Rng;
+use tokio::sync::Mutex;
+use tokio::time::{sleep_until, Instant};
+
+use crate::context;
+
+/// Spaces outbound sink requests at least `interval` apart plus a random
+/// `jitter`, so a burst across many addresses trickles out instead of hitting
+/// Discord and friends all at once. Each watcher paces its own requests.
+pub(crate) struct Pacer {
+    interval: Duration,
+    jitter: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+tokio::task_local! {
+    static PRIORITIZED: bool;
+}
+
+/// Paces the current watcher's requests.
+pub fn configure(interval: Duration, jitter: Duration) {
+    if interval.is_zero() && jitter.is_zero() {
+        return;
+    }
+    let Some(context) = context::current() else {
+        return;
+    };
+    let _ = context.pacer.set(Pacer {
+        interval,
+        jitter,
+        next_slot: Mutex::new(Instant::now()),
+    });
+}
+
+/// Runs `future` with its requests jumping the queue of paced requests.
+pub async fn prioritized<F: Future>(future: F) -> F::Output {
+    PRIORITIZED.scope(true, future).await
+}
+
+/// Waits for this request's slot; returns immediately when pacing is off.
+///
+/// Requests made within `prioritized` go out right away and push the requests
+/// queued after them back by one slot, so the overall rate still holds.
+pub async fn wait() {
+    let Some(context) = context::current() else {
+        return;
+    };
+    let Some(pacer) = context.pacer.get() else {
+        return;
+    };
+    if PRIORITIZED
+        .try_with(|prioritized| *prioritized)
+        .unwrap_or(false)
+    {
+        let mut next_slot = pacer.next_slot.lock().await;
+        *next_slot = (*next_slot).max(Instant::now()) + pacer.interval;
+        return;
+    }
+    let slot = {
+        let mut next_slot = pacer.next_slot.lock().await;
+        let slot = (*next_slot).max(Instant::now());
+        let jitter = rand::thread_rng().gen_range(Duration::ZERO..=pacer.jitter);
+        *next_slot = slot + pacer.interval + jitter;
+        slot
+    };
+    sleep_until(slot).await;
+}
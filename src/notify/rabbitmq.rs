@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use lapin::options::{BasicPublishOptions, ExchangeDeclareOptions};
+use lapin::types::FieldTable;
+use lapin::{BasicProperties, Channel, Connection, ConnectionProperties, ExchangeKind};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::hyperliquid::events::FillEvent;
+use crate::notify::dry_run;
+
+async fn connect(uri: &str, exchange: &str) -> anyhow::Result<(Connection, Channel)> {
+    let connection = Connection::connect(uri, ConnectionProperties::default()).await?;
+    let channel = connection.create_channel().await?;
+    channel
+        .exchange_declare(
+            exchange,
+            ExchangeKind::Topic,
+            ExchangeDeclareOptions {
+                durable: true,
+                ..Default::default()
+            },
+            FieldTable::default(),
+        )
+        .await?;
+    Ok((connection, channel))
+}
+
+/// Publishes fill events to a topic exchange under `fills.{venue}.{coin}`,
+/// reconnecting whenever the broker goes away.
+pub async fn publish(uri: String, exchange: String, mut events: Receiver<FillEvent>) {
+    loop {
+        let (_connection, channel) = match connect(&uri, &exchange).await {
+            Ok(connected) => connected,
+            Err(err) => {
+                warn!("failed to connect to rabbitmq: {err:?}");
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        info!("publishing fills to rabbitmq exchange {exchange}");
+
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("rabbitmq publisher dropped {skipped} events");
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            let payload = match serde_json::to_vec(&event) {
+                Ok(payload) => payload,
+                Err(err) => {
+                    warn!("failed to encode fill event: {err:?}");
+                    continue;
+                }
+            };
+            if dry_run::intercept("rabbitmq", event.subject()) {
+                continue;
+            }
+            let published = channel
+                .basic_publish(
+                    &exchange,
+                    &event.subject(),
+                    BasicPublishOptions::default(),
+                    &payload,
+                    BasicProperties::default().with_content_type("application/json".into()),
+                )
+                .await;
+            if let Err(err) = published {
+                warn!("failed to publish to rabbitmq: {err:?}");
+                break;
+            }
+        }
+    }
+}
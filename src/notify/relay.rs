@@ -0,0 +1,53 @@
+use serde::Deserialize;
+
+use crate::api::ApiState;
+use crate::notify::alerts::Priority;
+
+/// JSON body of a relayed alert. Anything that isn't JSON is relayed as-is.
+#[derive(Deserialize)]
+struct RelayMessage {
+    #[serde(alias = "text", alias = "content")]
+    message: String,
+    #[serde(alias = "ticker", alias = "symbol")]
+    coin: Option<String>,
+    priority: Option<String>,
+    source: Option<String>,
+}
+
+/// Relays an inbound alert through the watcher's mutes, routes and sinks.
+/// Returns false when the alert was dropped because its coin is muted.
+pub async fn relay(state: &ApiState, body: &str) -> anyhow::Result<bool> {
+    let message = match serde_json::from_str::<RelayMessage>(body) {
+        Ok(message) => message,
+        Err(_) => RelayMessage {
+            message: body.trim().to_string(),
+            coin: None,
+            priority: None,
+            source: None,
+        },
+    };
+    let priority = match &message.priority {
+        Some(priority) => priority.parse()?,
+        None => Priority::Normal,
+    };
+
+    let webhook_url = match &message.coin {
+        Some(coin) => {
+            if state.mutes.lock().await.is_muted(coin) {
+                return Ok(false);
+            }
+            state.routes.lock().await.get(coin).map(str::to_string)
+        }
+        None => None,
+    };
+
+    let content = match &message.source {
+        Some(source) => format!("Relay ({source}): {}", message.message),
+        None => format!("Relay: {}", message.message),
+    };
+    state
+        .alerter
+        .send_routed(priority, &content, webhook_url.as_deref())
+        .await;
+    Ok(true)
+}
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::time::{sleep_until, Instant};
+use tracing::{info, warn};
+
+use crate::context::{self, spawn};
+use crate::notify::discord::{self, Outcome};
+use crate::notify::pacing;
+
+/// The first retry waits this long, and each one after it twice as long as
+/// the one before, up to `MAX_DELAY`, unless Discord asks for longer.
+const BASE_DELAY: Duration = Duration::from_secs(2);
+const MAX_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// A Discord post that failed with a network error, 429 or 5xx.
+struct QueuedPost {
+    client: reqwest::Client,
+    webhook_url: String,
+    payload: Value,
+    /// Attempts made so far, the first one included.
+    attempts: u32,
+    due: Instant,
+}
+
+/// A watcher's queue of failed posts, retried by a task on its runtime.
+pub(crate) struct RetryQueue {
+    max_attempts: u32,
+    sender: UnboundedSender<QueuedPost>,
+}
+
+/// Starts retrying the current watcher's failed Discord posts until
+/// `max_attempts` attempts in all have been made; without it, or with one
+/// attempt, they are dropped as before. Must be called from within the
+/// runtime.
+pub fn configure(max_attempts: u32) {
+    if max_attempts <= 1 {
+        return;
+    }
+    let Some(context) = context::current() else {
+        return;
+    };
+    let (sender, receiver) = unbounded_channel();
+    if context
+        .retry_queue
+        .set(RetryQueue {
+            max_attempts,
+            sender,
+        })
+        .is_ok()
+    {
+        spawn(run(receiver, max_attempts));
+    }
+}
+
+fn delay(attempts: u32, retry_after: Option<Duration>) -> Duration {
+    let backoff = BASE_DELAY
+        .saturating_mul(1 << attempts.saturating_sub(1).min(16))
+        .min(MAX_DELAY);
+    backoff.max(retry_after.unwrap_or_default())
+}
+
+/// Queues a post whose first attempt just failed; returns false when retries
+/// are off and it is dropped.
+pub fn enqueue(
+    client: &reqwest::Client,
+    webhook_url: &str,
+    payload: &Value,
+    retry_after: Option<Duration>,
+) -> bool {
+    let Some(context) = context::current() else {
+        return false;
+    };
+    let Some(queue) = context.retry_queue.get() else {
+        return false;
+    };
+    let pending = QueuedPost {
+        client: client.clone(),
+        webhook_url: webhook_url.to_string(),
+        payload: payload.clone(),
+        attempts: 1,
+        due: Instant::now() + delay(1, retry_after),
+    };
+    if queue.sender.send(pending).is_err() {
+        return false;
+    }
+    info!(
+        "queued a failed webhook post, {} attempts left",
+        queue.max_attempts - 1
+    );
+    true
+}
+
+async fn run(mut receiver: UnboundedReceiver<QueuedPost>, max_attempts: u32) {
+    let mut queued: Vec<QueuedPost> = Vec::new();
+    loop {
+        let next_due = queued.iter().map(|pending| pending.due).min();
+        tokio::select! {
+            received = receiver.recv() => match received {
+                Some(pending) => queued.push(pending),
+                None => return,
+            },
+            () = sleep_until(next_due.unwrap_or_else(Instant::now)), if next_due.is_some() => {
+                let now = Instant::now();
+                let (due, waiting): (Vec<QueuedPost>, Vec<QueuedPost>) =
+                    queued.drain(..).partition(|pending| pending.due <= now);
+                queued = waiting;
+                for mut pending in due {
+                    pacing::wait().await;
+                    let outcome =
+                        discord::send_payload(&pending.client, &pending.webhook_url, &pending.payload)
+                            .await;
+                    pending.attempts += 1;
+                    match outcome {
+                        Outcome::Sent => info!(
+                            "delivered a webhook post on attempt {}",
+                            pending.attempts
+                        ),
+                        Outcome::Retry(retry_after) if pending.attempts < max_attempts => {
+                            pending.due = Instant::now() + delay(pending.attempts, retry_after);
+                            queued.push(pending);
+                        }
+                        Outcome::Retry(_) | Outcome::Failed => warn!(
+                            "dropping a webhook post after {} attempts",
+                            pending.attempts
+                        ),
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use ethers::types::H160;
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+use tracing::warn;
+
+use crate::hyperliquid::fills::Fill;
+use crate::notify::dry_run;
+use crate::{now_ms, side_label};
+
+const SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets";
+
+#[derive(Deserialize)]
+struct ServiceAccount {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// Appends fill rows to a Google Sheet as a service account.
+pub struct SheetsAppender {
+    client: reqwest::Client,
+    account: ServiceAccount,
+    spreadsheet_id: String,
+    range: String,
+    token: Option<(String, Instant)>,
+}
+
+impl SheetsAppender {
+    pub fn new(
+        client: reqwest::Client,
+        service_account_path: &str,
+        spreadsheet_id: String,
+        range: String,
+    ) -> anyhow::Result<Self> {
+        let account = serde_json::from_str(&std::fs::read_to_string(service_account_path)?)?;
+        Ok(SheetsAppender {
+            client,
+            account,
+            spreadsheet_id,
+            range,
+            token: None,
+        })
+    }
+
+    pub async fn append(&mut self, fills: &[(H160, Fill)]) -> anyhow::Result<()> {
+        if fills.is_empty() || dry_run::intercept("google sheets", format!("{} fills", fills.len()))
+        {
+            return Ok(());
+        }
+
+        let values: Vec<Vec<String>> = fills
+            .iter()
+            .map(|(user, fill)| {
+                vec![
+                    fill.time.to_string(),
+                    format!("{user:?}"),
+                    fill.coin.clone(),
+                    side_label(&fill.side).to_string(),
+                    fill.px.clone(),
+                    fill.sz.clone(),
+                    fill.dir.clone(),
+                    fill.closed_pnl.clone(),
+                    fill.fee.clone(),
+                    fill.hash.clone(),
+                ]
+            })
+            .collect();
+
+        let token = self.access_token().await?;
+        let url = format!(
+            "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}:append",
+            self.spreadsheet_id, self.range
+        );
+        self.client
+            .post(url)
+            .bearer_auth(token)
+            .query(&[
+                ("valueInputOption", "RAW"),
+                ("insertDataOption", "INSERT_ROWS"),
+            ])
+            .json(&json!({ "values": values }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn access_token(&mut self) -> anyhow::Result<String> {
+        if let Some((token, expires_at)) = &self.token {
+            if Instant::now() < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+
+        let iat = now_ms() / 1000;
+        let claims = Claims {
+            iss: &self.account.client_email,
+            scope: SCOPE,
+            aud: &self.account.token_uri,
+            iat,
+            exp: iat + 3600,
+        };
+        let key = EncodingKey::from_rsa_pem(self.account.private_key.as_bytes())?;
+        let assertion = encode(&Header::new(Algorithm::RS256), &claims, &key)?;
+
+        let res: TokenResponse = self
+            .client
+            .post(&self.account.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if res.access_token.is_empty() {
+            return Err(anyhow!(
+                "empty access token from {}",
+                self.account.token_uri
+            ));
+        }
+
+        // Refresh a minute early so a batch never goes out with a stale token.
+        let expires_at = Instant::now() + Duration::from_secs(res.expires_in.saturating_sub(60));
+        self.token = Some((res.access_token.clone(), expires_at));
+        Ok(res.access_token)
+    }
+
+    /// Appends whatever has gathered in `fills` every minute.
+    pub async fn run(mut self, fills: Arc<Mutex<Vec<(H160, Fill)>>>) {
+        loop {
+            sleep(Duration::from_secs(60)).await;
+            let batch = std::mem::take(&mut *fills.lock().await);
+            if let Err(err) = self.append(&batch).await {
+                warn!(
+                    "failed to append {} fills to google sheets: {err:?}",
+                    batch.len()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use tracing::warn;
+
+use crate::metrics;
+use crate::notify::{dry_run, pacing};
+
+/// Sends a text message through the Twilio Messages API.
+pub async fn send(
+    client: &reqwest::Client,
+    account_sid: &str,
+    auth_token: &str,
+    from: &str,
+    to: &str,
+    body: &str,
+) {
+    let url = format!("https://api.twilio.com/2010-04-01/Accounts/{account_sid}/Messages.json");
+    if dry_run::intercept("sms", format!("to {to}: {body}")) {
+        return;
+    }
+    pacing::wait().await;
+    let ok = match client
+        .post(url)
+        .basic_auth(account_sid, Some(auth_token))
+        .form(&[("From", from), ("To", to), ("Body", body)])
+        .send()
+        .await
+    {
+        Ok(res) => {
+            let status_code = res.status();
+            let ok = res.error_for_status().is_ok();
+            if !ok {
+                warn!("unexpected status code from twilio: {status_code:?}")
+            }
+            ok
+        }
+        Err(err) => {
+            warn!("failed to send sms: {err:?}");
+            false
+        }
+    };
+    metrics::record_notification("sms", ok);
+}
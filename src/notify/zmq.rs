@@ -0,0 +1,42 @@
+use bytes::Bytes;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tracing::{info, warn};
+use zeromq::{PubSocket, Socket, SocketSend, ZmqMessage};
+
+use crate::hyperliquid::events::FillEvent;
+use crate::notify::dry_run;
+
+/// Broadcasts fill events on a PUB socket as two frames: the
+/// `fills.{venue}.{coin}` topic for subscription filtering, then the
+/// msgpack-encoded event.
+pub async fn publish(endpoint: String, mut events: Receiver<FillEvent>) -> anyhow::Result<()> {
+    let mut socket = PubSocket::new();
+    socket.bind(&endpoint).await?;
+    info!("publishing fills on zeromq {endpoint}");
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("zeromq publisher dropped {skipped} events");
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+        let payload = match rmp_serde::to_vec_named(&event) {
+            Ok(payload) => payload,
+            Err(err) => {
+                warn!("failed to encode fill event: {err:?}");
+                continue;
+            }
+        };
+        if dry_run::intercept("zeromq", event.subject()) {
+            continue;
+        }
+        let mut message = ZmqMessage::from(event.subject());
+        message.push_back(Bytes::from(payload));
+        if let Err(err) = socket.send(message).await {
+            warn!("failed to publish to zeromq: {err:?}");
+        }
+    }
+}
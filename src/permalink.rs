@@ -0,0 +1,130 @@
+use chrono::{TimeZone, Utc};
+
+use crate::store::StoredFill;
+use crate::{short_address, side_label};
+
+/// Earlier fills of the same address and coin shown below a fill.
+const CONTEXT_FILLS: usize = 5;
+
+/// `PUBLIC_BASE_URL`, the address the HTTP server is reachable at from chat.
+pub fn base_url_from_env() -> Option<String> {
+    crate::tenants::var("PUBLIC_BASE_URL")
+        .ok()
+        .map(|url| url.trim_end_matches('/').to_string())
+}
+
+pub fn url(base_url: &str, fill: &StoredFill) -> String {
+    format!("{base_url}/fill/{}", fill.id())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn time(ms: u64) -> String {
+    Utc.timestamp_millis_opt(ms as i64)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S%.3f UTC").to_string())
+        .unwrap_or_default()
+}
+
+fn row(fill: &StoredFill) -> String {
+    format!(
+        "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+        time(fill.time),
+        side_label(&fill.side),
+        escape(&fill.coin),
+        fill.sz,
+        fill.px,
+        escape(&fill.dir)
+    )
+}
+
+fn table(fills: &[&StoredFill]) -> String {
+    let rows: String = fills.iter().map(|fill| row(fill)).collect();
+    format!(
+        "<table><tr><th>Time</th><th>Side</th><th>Coin</th><th>Size</th><th>Price</th><th>Direction</th></tr>{rows}</table>"
+    )
+}
+
+/// HTML page with every stored detail of `fill`, the other fills of its
+/// order and the address's previous fills of the coin. A `public` page
+/// shortens the address and leaves out the note and the transaction, which
+/// would give the address away.
+pub fn render(fill: &StoredFill, fills: &[StoredFill], explorer_url: &str, public: bool) -> String {
+    let note = fill
+        .note
+        .as_deref()
+        .filter(|_| !public)
+        .map(|note| ("Note", escape(note)));
+    let transaction = (!public).then(|| {
+        (
+            "Transaction",
+            format!(
+                "<a href=\"{explorer_url}/tx/{0}\">{0}</a>",
+                escape(&fill.hash)
+            ),
+        )
+    });
+    let address = if public {
+        short_address(fill.user)
+    } else {
+        format!("{:?}", fill.user)
+    };
+    let details = [
+        ("Address", address),
+        ("Time", time(fill.time)),
+        ("Side", side_label(&fill.side).to_string()),
+        ("Coin", escape(&fill.coin)),
+        ("Size", fill.sz.to_string()),
+        ("Price", fill.px.to_string()),
+        ("Notional", format!("${:.2}", fill.px * fill.sz)),
+        ("Direction", escape(&fill.dir)),
+        ("Closed PnL", format!("${:.2}", fill.closed_pnl)),
+        ("Fee", format!("${:.4}", fill.fee)),
+        ("Order", fill.oid.to_string()),
+    ];
+    let details: String = details
+        .iter()
+        .chain(transaction.iter())
+        .chain(note.iter())
+        .map(|(name, value)| format!("<tr><th>{name}</th><td>{value}</td></tr>"))
+        .collect();
+
+    let order: Vec<&StoredFill> = fills
+        .iter()
+        .filter(|other| other.user == fill.user && other.oid == fill.oid)
+        .collect();
+    let mut earlier: Vec<&StoredFill> = fills
+        .iter()
+        .filter(|other| {
+            other.user == fill.user
+                && other.coin == fill.coin
+                && other.oid != fill.oid
+                && other.time < fill.time
+        })
+        .collect();
+    earlier.sort_by_key(|other| other.time);
+    let earlier = &earlier[earlier.len().saturating_sub(CONTEXT_FILLS)..];
+
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{} {} {}</title></head><body>\
+         <h1>{} {} {}</h1><table>{details}</table>\
+         <h2>Fills of order {}</h2>{}\
+         <h2>Earlier {} fills</h2>{}\
+         </body></html>",
+        side_label(&fill.side),
+        fill.sz,
+        escape(&fill.coin),
+        side_label(&fill.side),
+        fill.sz,
+        escape(&fill.coin),
+        fill.oid,
+        table(&order),
+        escape(&fill.coin),
+        table(earlier)
+    )
+}
@@ -0,0 +1,128 @@
+use std::collections::{BTreeMap, HashSet};
+
+use ethers::types::H160;
+use hyperliquid_rust_sdk::InfoClient;
+use tracing::warn;
+
+use crate::hyperliquid::fills;
+use crate::hyperliquid::positions::PositionTracker;
+use crate::stats;
+use crate::storage::Store;
+use crate::store::{FillStore, StoredFill};
+
+/// Net positions beyond this many coins are summarized in one line.
+const SHOWN_POSITIONS: usize = 10;
+
+/// What the watcher found when it started, so operators can tell it resumed
+/// cleanly.
+pub struct Reconciliation {
+    pub subscribed: usize,
+    pub retrying: usize,
+    pub backfilled: usize,
+    pub net_exposures: BTreeMap<String, f64>,
+    pub config_hash: String,
+}
+
+/// Fills `backfill` stored that were not stored yet.
+#[derive(Default)]
+pub struct Backfill {
+    pub added: Vec<StoredFill>,
+    /// The added fills newer than their address's latest stored fill, which
+    /// happened while the watcher was down. Addresses without stored fills
+    /// have no such mark, so their history does not count as missed.
+    pub missed: Vec<StoredFill>,
+}
+
+impl Backfill {
+    /// "Missed trades" digest of `missed`, or `None` without any.
+    pub fn render_missed(&self) -> Option<String> {
+        let first = self.missed.iter().map(|fill| fill.time).min()?;
+        let addresses: HashSet<H160> = self.missed.iter().map(|fill| fill.user).collect();
+        Some(format!(
+            "Missed trades while down: {} fills of {} addresses\n{}",
+            self.missed.len(),
+            addresses.len(),
+            stats::compute(&self.missed, first, None).render()
+        ))
+    }
+}
+
+/// Stores the recent fills of `users` missed while the watcher was down,
+/// skipping the ones already stored.
+pub async fn backfill(info_client: &InfoClient, store: &mut FillStore, users: &[H160]) -> Backfill {
+    let mut backfill = Backfill::default();
+    for user in users {
+        let fills = match fills::fetch_user_fills(info_client, *user).await {
+            Ok(fills) => fills,
+            Err(err) => {
+                warn!("failed to backfill fills of {user:?}: {err:?}");
+                continue;
+            }
+        };
+        let fills: Vec<StoredFill> = fills
+            .iter()
+            .map(|fill| StoredFill::new(*user, fill))
+            .filter(|fill| !store.contains(fill))
+            .collect();
+        let high_water_mark = store.high_water_mark(*user).ok().flatten();
+        if let Err(err) = store.append(&fills) {
+            warn!("failed to store backfilled fills of {user:?}: {err:?}");
+            continue;
+        }
+        if let Some(high_water_mark) = high_water_mark {
+            backfill.missed.extend(
+                fills
+                    .iter()
+                    .filter(|fill| fill.time > high_water_mark)
+                    .cloned(),
+            );
+        }
+        backfill.added.extend(fills);
+    }
+    backfill
+}
+
+/// Net notional per coin across `users` right now.
+pub async fn net_exposures(info_client: &InfoClient, users: &[H160]) -> BTreeMap<String, f64> {
+    let mut tracker = PositionTracker::default();
+    for user in users {
+        match info_client.user_state(*user).await {
+            Ok(state) => {
+                tracker.update(*user, &state);
+            }
+            Err(err) => warn!("failed to fetch clearinghouse state of {user:?}: {err:?}"),
+        }
+    }
+    tracker.net_exposures()
+}
+
+impl Reconciliation {
+    pub fn render(&self) -> String {
+        let mut content = format!(
+            "Subscribed: {} addresses ({} retrying)\nBackfilled: {} fills since last run\nConfig: {}",
+            self.subscribed, self.retrying, self.backfilled, self.config_hash
+        );
+        let mut exposures: Vec<(&String, &f64)> = self
+            .net_exposures
+            .iter()
+            .filter(|(_, notional)| **notional != 0.0)
+            .collect();
+        if exposures.is_empty() {
+            content.push_str("\nNet positions: none");
+            return content;
+        }
+        exposures.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+        content.push_str("\nNet positions:");
+        for (coin, notional) in exposures.iter().take(SHOWN_POSITIONS) {
+            let side = if **notional > 0.0 { "Long" } else { "Short" };
+            content.push_str(&format!("\n- {coin}: {side} ${:.2}", notional.abs()));
+        }
+        if exposures.len() > SHOWN_POSITIONS {
+            content.push_str(&format!(
+                "\n- and {} more",
+                exposures.len() - SHOWN_POSITIONS
+            ));
+        }
+        content
+    }
+}
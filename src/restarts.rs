@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::now_ms;
+
+const LOOP_WINDOW: Duration = Duration::from_secs(3600);
+const KEPT_ERRORS: usize = 5;
+
+/// `RESTART_LOG_PATH`, or `restarts.json`.
+pub fn log_path() -> PathBuf {
+    crate::tenants::var("RESTART_LOG_PATH")
+        .unwrap_or_else(|_| "restarts.json".to_string())
+        .into()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Restart {
+    time: u64,
+    version: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ExitError {
+    time: u64,
+    version: String,
+    message: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct RestartState {
+    restarts: Vec<Restart>,
+    errors: Vec<ExitError>,
+    /// When the current crash loop was last reported.
+    incident_reported: Option<u64>,
+}
+
+/// Restarts within the last hour and the errors the process last exited with,
+/// persisted as JSON so a crash loop can be told apart from a single restart.
+pub struct RestartLog {
+    path: PathBuf,
+    state: RestartState,
+}
+
+/// What to tell the ops channel about this start.
+pub enum Startup {
+    /// An ordinary start.
+    Notice(String),
+    /// The first start of a crash loop.
+    Incident(String),
+    /// A crash loop that was already reported.
+    Silent,
+}
+
+impl RestartLog {
+    pub fn load(path: PathBuf) -> anyhow::Result<Self> {
+        let state = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => RestartState::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(RestartLog { path, state })
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        fs::write(&self.path, serde_json::to_string(&self.state)?)?;
+        Ok(())
+    }
+
+    /// Records this start and decides how to announce it; more than
+    /// `max_restarts` starts within an hour are a crash loop, reported once.
+    pub fn record_start(&mut self, max_restarts: usize) -> anyhow::Result<Startup> {
+        let now = now_ms();
+        let since = now.saturating_sub(LOOP_WINDOW.as_millis() as u64);
+        self.state.restarts.retain(|restart| restart.time >= since);
+        self.state.restarts.push(Restart {
+            time: now,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        });
+
+        let startup = if self.state.restarts.len() <= max_restarts {
+            self.state.incident_reported = None;
+            Startup::Notice(format!(
+                "Watcher started (version {})",
+                env!("CARGO_PKG_VERSION")
+            ))
+        } else if self
+            .state
+            .incident_reported
+            .is_some_and(|time| time >= since)
+        {
+            Startup::Silent
+        } else {
+            self.state.incident_reported = Some(now);
+            Startup::Incident(self.incident_summary())
+        };
+        self.save()?;
+        Ok(startup)
+    }
+
+    fn incident_summary(&self) -> String {
+        let mut versions: Vec<&str> = self
+            .state
+            .restarts
+            .iter()
+            .map(|restart| restart.version.as_str())
+            .collect();
+        versions.dedup();
+        let mut summary = format!(
+            "Crash loop: the watcher restarted {} times in the last hour (versions: {})",
+            self.state.restarts.len(),
+            versions.join(", ")
+        );
+        if self.state.errors.is_empty() {
+            summary.push_str("\nNo exit errors were recorded.");
+        } else {
+            summary.push_str("\nLast errors:");
+            for error in &self.state.errors {
+                let minutes_ago = now_ms().saturating_sub(error.time) / 60_000;
+                summary.push_str(&format!(
+                    "\n- {minutes_ago}m ago (v{}): {}",
+                    error.version, error.message
+                ));
+            }
+        }
+        summary
+    }
+
+    /// Records the error the process is about to exit with.
+    pub fn record_error(&mut self, message: String) -> anyhow::Result<()> {
+        self.state.errors.push(ExitError {
+            time: now_ms(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            message,
+        });
+        let excess = self.state.errors.len().saturating_sub(KEPT_ERRORS);
+        self.state.errors.drain(..excess);
+        self.save()
+    }
+}
+
+/// Keeps the error for the incident summary of a possible crash loop.
+pub fn record_exit_error(err: &anyhow::Error) {
+    let recorded = RestartLog::load(log_path())
+        .and_then(|mut restart_log| restart_log.record_error(format!("{err:#}")));
+    if let Err(err) = recorded {
+        warn!("failed to record exit error: {err:?}");
+    }
+}
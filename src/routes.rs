@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::categories::{Categories, PREFIX};
+
+/// Coin to webhook routing table, editable at runtime and persisted as JSON.
+///
+/// A route for `category:<NAME>` applies to the category's coins that have no
+/// route of their own.
+pub struct Routes {
+    path: PathBuf,
+    webhooks: BTreeMap<String, String>,
+    categories: Categories,
+}
+
+impl Routes {
+    pub fn load(path: PathBuf) -> anyhow::Result<Self> {
+        let webhooks = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Routes {
+            path,
+            webhooks,
+            categories: Categories::default(),
+        })
+    }
+
+    pub fn with_categories(mut self, categories: Categories) -> Self {
+        self.categories = categories;
+        self
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        fs::write(&self.path, serde_json::to_string(&self.webhooks)?)?;
+        Ok(())
+    }
+
+    pub fn set(&mut self, coin: &str, webhook_url: &str) -> anyhow::Result<()> {
+        self.webhooks
+            .insert(coin.to_uppercase(), webhook_url.to_string());
+        self.save()
+    }
+
+    pub fn remove(&mut self, coin: &str) -> anyhow::Result<bool> {
+        let removed = self.webhooks.remove(&coin.to_uppercase()).is_some();
+        self.save()?;
+        Ok(removed)
+    }
+
+    pub fn get(&self, coin: &str) -> Option<&str> {
+        self.webhooks
+            .get(&coin.to_uppercase())
+            .or_else(|| {
+                let category = self.categories.of(coin)?;
+                self.webhooks
+                    .get(&format!("{PREFIX}{category}").to_uppercase())
+            })
+            .map(String::as_str)
+    }
+
+    pub fn all(&self) -> &BTreeMap<String, String> {
+        &self.webhooks
+    }
+}
@@ -0,0 +1,141 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Datelike, Days, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+
+/// A time of day in a named timezone, written as `Asia/Tokyo 09:00`.
+#[derive(Clone, Copy, Debug)]
+pub struct DailySchedule {
+    tz: Tz,
+    time: NaiveTime,
+}
+
+impl FromStr for DailySchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        let (tz, time) = value
+            .trim()
+            .split_once(' ')
+            .ok_or_else(|| anyhow!("expected `<timezone> <HH:MM>`, got {value:?}"))?;
+        let tz = Tz::from_str(tz).map_err(|err| anyhow!("invalid timezone {tz:?}: {err}"))?;
+        let time = NaiveTime::parse_from_str(time.trim(), "%H:%M")?;
+        Ok(DailySchedule { tz, time })
+    }
+}
+
+impl DailySchedule {
+    /// Returns the first scheduled moment strictly after `now`.
+    pub fn next_after(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let local_date = now.with_timezone(&self.tz).date_naive();
+        for offset in 0..3 {
+            let Some(date) = local_date.checked_add_days(Days::new(offset)) else {
+                continue;
+            };
+            // Ambiguous DST times use the earlier instant; skipped ones fall through
+            // to the next day.
+            let candidate = self
+                .tz
+                .from_local_datetime(&date.and_time(self.time))
+                .earliest()
+                .map(|datetime| datetime.with_timezone(&Utc));
+            if let Some(candidate) = candidate {
+                if candidate > now {
+                    return candidate;
+                }
+            }
+        }
+        now + chrono::Duration::days(1)
+    }
+}
+
+/// A day of the week plus a `DailySchedule`, written as `Mon Asia/Tokyo 09:00`.
+#[derive(Clone, Copy, Debug)]
+pub struct WeeklySchedule {
+    weekday: Weekday,
+    daily: DailySchedule,
+}
+
+impl FromStr for WeeklySchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        let (weekday, daily) = value
+            .trim()
+            .split_once(' ')
+            .ok_or_else(|| anyhow!("expected `<weekday> <timezone> <HH:MM>`, got {value:?}"))?;
+        let weekday =
+            Weekday::from_str(weekday).map_err(|_| anyhow!("invalid weekday {weekday:?}"))?;
+        Ok(WeeklySchedule {
+            weekday,
+            daily: daily.parse()?,
+        })
+    }
+}
+
+impl WeeklySchedule {
+    /// Returns the first scheduled moment strictly after `now`.
+    pub fn next_after(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let mut candidate = self.daily.next_after(now);
+        for _ in 0..7 {
+            if candidate.with_timezone(&self.daily.tz).weekday() == self.weekday {
+                break;
+            }
+            candidate = self.daily.next_after(candidate);
+        }
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn runs_later_today_or_tomorrow() {
+        let schedule: DailySchedule = "UTC 09:00".parse().unwrap();
+        assert_eq!(
+            schedule.next_after(at("2024-01-01T08:00:00Z")),
+            at("2024-01-01T09:00:00Z")
+        );
+        assert_eq!(
+            schedule.next_after(at("2024-01-01T09:00:00Z")),
+            at("2024-01-02T09:00:00Z")
+        );
+    }
+
+    #[test]
+    fn follows_the_local_date() {
+        let schedule: DailySchedule = "Asia/Tokyo 09:00".parse().unwrap();
+        // 09:30 in Tokyo.
+        assert_eq!(
+            schedule.next_after(at("2024-01-01T00:30:00Z")),
+            at("2024-01-02T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn skips_days_without_the_time() {
+        // New York clocks jump from 02:00 to 03:00 on 2024-03-10.
+        let schedule: DailySchedule = "America/New_York 02:30".parse().unwrap();
+        assert_eq!(
+            schedule.next_after(at("2024-03-10T05:00:00Z")),
+            at("2024-03-11T06:30:00Z")
+        );
+    }
+
+    #[test]
+    fn weekly_waits_for_the_weekday() {
+        let schedule: WeeklySchedule = "Mon UTC 00:00".parse().unwrap();
+        // A Wednesday.
+        assert_eq!(
+            schedule.next_after(at("2024-01-03T12:00:00Z")),
+            at("2024-01-08T00:00:00Z")
+        );
+    }
+}
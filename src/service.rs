@@ -0,0 +1,109 @@
+use std::ffi::OsString;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use tracing::error;
+use windows_service::service::{
+    ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+    ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use windows_service::{define_windows_service, service_dispatcher};
+
+const SERVICE_NAME: &str = "god_watcher";
+
+/// Registers the current executable as an auto-start service running with `--service`.
+pub fn install() -> anyhow::Result<()> {
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )?;
+    let info = ServiceInfo {
+        name: OsString::from(SERVICE_NAME),
+        display_name: OsString::from("God Watcher"),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: std::env::current_exe()?,
+        launch_arguments: vec![OsString::from("--service")],
+        dependencies: vec![],
+        account_name: None,
+        account_password: None,
+    };
+    let service = manager.create_service(&info, ServiceAccess::CHANGE_CONFIG)?;
+    service.set_description("Notify trades of Hyperliquid vaults to Discord")?;
+    Ok(())
+}
+
+pub fn uninstall() -> anyhow::Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let service = manager.open_service(SERVICE_NAME, ServiceAccess::DELETE)?;
+    service.delete()?;
+    Ok(())
+}
+
+/// Hands the process over to the service control manager.
+pub fn run() -> anyhow::Result<()> {
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)?;
+    Ok(())
+}
+
+define_windows_service!(ffi_service_main, service_main);
+
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(err) = run_service() {
+        error!("service failed: {err:?}");
+    }
+}
+
+fn status(current_state: ServiceState, controls_accepted: ServiceControlAccept) -> ServiceStatus {
+    ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state,
+        controls_accepted,
+        exit_code: ServiceExitCode::Win32(0),
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    }
+}
+
+fn run_service() -> anyhow::Result<()> {
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+
+    let control_shutdown_tx = shutdown_tx.clone();
+    let event_handler = move |control_event| -> ServiceControlHandlerResult {
+        match control_event {
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            ServiceControl::Stop => {
+                let _ = control_shutdown_tx.send(());
+                ServiceControlHandlerResult::NoError
+            }
+            _ => ServiceControlHandlerResult::NotImplemented,
+        }
+    };
+    let status_handle = service_control_handler::register(SERVICE_NAME, event_handler)?;
+    status_handle.set_service_status(status(ServiceState::Running, ServiceControlAccept::STOP))?;
+
+    // The watcher only returns on its own after Ctrl-C or when it failed.
+    thread::spawn(move || {
+        // Services are started with `--service` alone, so everything else
+        // comes from the environment.
+        let cli = <crate::Cli as clap::Parser>::parse_from(["god_watcher"]);
+        let result = crate::init_tracing(cli.log_level).and_then(|()| {
+            crate::runtime().and_then(|runtime| runtime.block_on(crate::run(&cli, None)))
+        });
+        if let Err(err) = result {
+            error!("watcher stopped: {err:?}");
+            crate::record_exit_error(&err);
+        }
+        let _ = shutdown_tx.send(());
+    });
+
+    let _ = shutdown_rx.recv();
+    status_handle
+        .set_service_status(status(ServiceState::Stopped, ServiceControlAccept::empty()))?;
+    Ok(())
+}
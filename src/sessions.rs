@@ -0,0 +1,143 @@
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+use ethers::types::H160;
+
+use crate::hyperliquid::fills::Fill;
+use crate::now_ms;
+
+/// A run of fills by one address without a long pause in between.
+pub struct Session {
+    pub user: H160,
+    pub started: u64,
+    pub last_fill: u64,
+    pub fills: usize,
+    pub volume_usd: f64,
+    pub closed_pnl: f64,
+    pub fees: f64,
+    pub coins: BTreeSet<String>,
+    announced: bool,
+}
+
+impl Session {
+    pub fn render_started(&self) -> String {
+        format!(
+            "Session started: {:?} trading {}",
+            self.user,
+            self.coins
+                .iter()
+                .cloned()
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+
+    pub fn render_ended(&self) -> String {
+        let minutes = self.last_fill.saturating_sub(self.started) / 60_000;
+        format!(
+            "Session ended: {:?} after {minutes} minutes\n\
+             {} fills in {}, volume ${:.2}, closed PnL ${:.2}, fees ${:.2}",
+            self.user,
+            self.fills,
+            self.coins
+                .iter()
+                .cloned()
+                .collect::<Vec<String>>()
+                .join(", "),
+            self.volume_usd,
+            self.closed_pnl,
+            self.fees
+        )
+    }
+}
+
+/// Groups each address's fills into sessions that end after `idle` without a
+/// fill. Sessions are only announced once they reach `min_fills`, so one-off
+/// fills do not open one.
+pub struct SessionTracker {
+    idle: Duration,
+    min_fills: usize,
+    sessions: HashMap<H160, Session>,
+    /// Announced sessions a later fill found idle, until `ended` reports them.
+    finished: Vec<Session>,
+}
+
+impl SessionTracker {
+    pub fn new(idle: Duration, min_fills: usize) -> Self {
+        SessionTracker {
+            idle,
+            min_fills,
+            sessions: HashMap::new(),
+            finished: Vec::new(),
+        }
+    }
+
+    /// Records a fill and returns its session when this fill made it sustained.
+    pub fn record(&mut self, user: H160, fill: &Fill) -> Option<&Session> {
+        let idle_ms = self.idle.as_millis() as u64;
+        let is_idle = self
+            .sessions
+            .get(&user)
+            .is_some_and(|session| fill.time.saturating_sub(session.last_fill) >= idle_ms);
+        if is_idle {
+            if let Some(session) = self
+                .sessions
+                .remove(&user)
+                .filter(|session| session.announced)
+            {
+                self.finished.push(session);
+            }
+        }
+        let session = self.sessions.entry(user).or_insert_with(|| Session {
+            user,
+            started: fill.time,
+            last_fill: fill.time,
+            fills: 0,
+            volume_usd: 0.0,
+            closed_pnl: 0.0,
+            fees: 0.0,
+            coins: BTreeSet::new(),
+            announced: false,
+        });
+        session.last_fill = session.last_fill.max(fill.time);
+        session.fills += 1;
+        session.volume_usd +=
+            fill.px.parse::<f64>().unwrap_or_default() * fill.sz.parse::<f64>().unwrap_or_default();
+        session.closed_pnl += fill.closed_pnl.parse::<f64>().unwrap_or_default();
+        session.fees += fill.fee.parse::<f64>().unwrap_or_default();
+        session.coins.insert(fill.coin.clone());
+        if session.announced || session.fills < self.min_fills {
+            return None;
+        }
+        session.announced = true;
+        Some(session)
+    }
+
+    /// Drops the session of `user`, which is no longer watched, without
+    /// reporting it as ended.
+    pub fn forget(&mut self, user: H160) {
+        self.sessions.remove(&user);
+        self.finished.retain(|session| session.user != user);
+    }
+
+    /// Removes sessions idle for longer than `idle` and returns the announced
+    /// ones.
+    pub fn ended(&mut self) -> Vec<Session> {
+        let idle_ms = self.idle.as_millis() as u64;
+        let now = now_ms();
+        let idle_users: Vec<H160> = self
+            .sessions
+            .values()
+            .filter(|session| now.saturating_sub(session.last_fill) >= idle_ms)
+            .map(|session| session.user)
+            .collect();
+        let mut ended = std::mem::take(&mut self.finished);
+        ended.extend(
+            idle_users
+                .into_iter()
+                .filter_map(|user| self.sessions.remove(&user))
+                .filter(|session| session.announced),
+        );
+        ended
+    }
+}
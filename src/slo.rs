@@ -0,0 +1,79 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::metrics;
+
+/// A latency objective such as "95% of fills notified within 3 seconds",
+/// evaluated over a rolling window of the notification latency histogram.
+pub struct LatencyBudget {
+    threshold_secs: f64,
+    target_pct: f64,
+    window: Duration,
+    /// Cumulative (total, within threshold) counts, oldest first.
+    snapshots: VecDeque<(Instant, u64, u64)>,
+    breached: bool,
+}
+
+pub enum BudgetChange {
+    Breached { compliance_pct: f64, fills: u64 },
+    Recovered { compliance_pct: f64 },
+}
+
+impl LatencyBudget {
+    pub fn new(threshold_secs: f64, target_pct: f64, window: Duration) -> anyhow::Result<Self> {
+        if !metrics::is_latency_bucket(threshold_secs) {
+            return Err(anyhow::anyhow!(
+                "latency SLO threshold {threshold_secs}s is not a histogram bucket"
+            ));
+        }
+        Ok(LatencyBudget {
+            threshold_secs,
+            target_pct,
+            window,
+            snapshots: VecDeque::new(),
+            breached: false,
+        })
+    }
+
+    pub fn threshold_secs(&self) -> f64 {
+        self.threshold_secs
+    }
+
+    pub fn target_pct(&self) -> f64 {
+        self.target_pct
+    }
+
+    /// Takes a snapshot of the histogram and returns a change when the window
+    /// just went out of budget or back within it.
+    pub fn check(&mut self) -> Option<BudgetChange> {
+        let (total, within) = metrics::latency_counts("discord", self.threshold_secs)?;
+        let now = Instant::now();
+        self.snapshots.push_back((now, total, within));
+        while let Some((at, _, _)) = self.snapshots.front() {
+            if now.duration_since(*at) <= self.window {
+                break;
+            }
+            self.snapshots.pop_front();
+        }
+
+        let (_, oldest_total, oldest_within) = *self.snapshots.front()?;
+        let fills = total - oldest_total;
+        if fills == 0 {
+            return None;
+        }
+        let compliance_pct = (within - oldest_within) as f64 / fills as f64 * 100.0;
+        let breached = compliance_pct < self.target_pct;
+        if breached == self.breached {
+            return None;
+        }
+        self.breached = breached;
+        Some(if breached {
+            BudgetChange::Breached {
+                compliance_pct,
+                fills,
+            }
+        } else {
+            BudgetChange::Recovered { compliance_pct }
+        })
+    }
+}
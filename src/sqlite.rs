@@ -0,0 +1,148 @@
+use std::path::Path;
+
+use ethers::types::H160;
+use rusqlite::{params, Connection};
+
+use crate::storage::{Query, Store};
+use crate::store::StoredFill;
+
+/// `SQLITE_PATH`; the database is off without it.
+pub fn path_from_env() -> Option<String> {
+    crate::tenants::var("SQLITE_PATH").ok()
+}
+
+/// SQLite table of every received fill, for querying history with SQL. The
+/// `Store` the watcher uses unless another is given.
+///
+/// Fills are keyed by `StoredFill::id`, which follows the address and trade
+/// id, so fills received again after a restart are ignored. Nothing is
+/// encrypted, which is why `storage::open_from_env` refuses it alongside
+/// `STORE_ENCRYPTION_KEY`.
+pub struct FillDatabase {
+    connection: Connection,
+}
+
+impl FillDatabase {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS fills (
+                id TEXT PRIMARY KEY,
+                user TEXT NOT NULL,
+                coin TEXT NOT NULL,
+                side TEXT NOT NULL,
+                px REAL NOT NULL,
+                sz REAL NOT NULL,
+                fee REAL NOT NULL,
+                time INTEGER NOT NULL,
+                dir TEXT NOT NULL,
+                closed_pnl REAL NOT NULL,
+                oid INTEGER NOT NULL,
+                hash TEXT NOT NULL,
+                tid INTEGER NOT NULL DEFAULT 0
+            );",
+        )?;
+        // Databases created before trade ids were kept lack the column.
+        let has_tid = connection
+            .prepare("SELECT 1 FROM pragma_table_info('fills') WHERE name = 'tid'")?
+            .exists([])?;
+        if !has_tid {
+            connection
+                .execute_batch("ALTER TABLE fills ADD COLUMN tid INTEGER NOT NULL DEFAULT 0")?;
+        }
+        connection.execute_batch(
+            "CREATE INDEX IF NOT EXISTS fills_time ON fills (time);
+            CREATE INDEX IF NOT EXISTS fills_user_time ON fills (user, time);
+            CREATE UNIQUE INDEX IF NOT EXISTS fills_user_tid ON fills (user, tid) WHERE tid != 0;",
+        )?;
+        Ok(FillDatabase { connection })
+    }
+}
+
+impl Store for FillDatabase {
+    fn save_fills(&mut self, fills: &[StoredFill]) -> anyhow::Result<usize> {
+        let transaction = self.connection.transaction()?;
+        let mut added = 0;
+        {
+            let mut statement = transaction.prepare_cached(
+                "INSERT OR IGNORE INTO fills
+                    (id, user, coin, side, px, sz, fee, time, dir, closed_pnl, oid, hash, tid)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            )?;
+            for fill in fills {
+                added += statement.execute(params![
+                    fill.id(),
+                    format!("{:?}", fill.user),
+                    fill.coin,
+                    fill.side,
+                    fill.px,
+                    fill.sz,
+                    fill.fee,
+                    fill.time as i64,
+                    fill.dir,
+                    fill.closed_pnl,
+                    fill.oid as i64,
+                    fill.hash,
+                    fill.tid as i64,
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(added)
+    }
+
+    fn query(&self, query: &Query) -> anyhow::Result<Vec<StoredFill>> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT user, coin, side, px, sz, fee, time, dir, closed_pnl, oid, hash, tid FROM fills
+             WHERE time >= ?1 AND (?2 IS NULL OR user = ?2)
+             ORDER BY time DESC LIMIT ?3",
+        )?;
+        let user = query.user.map(|user| format!("{user:?}"));
+        let rows = statement.query_map(
+            params![query.since as i64, user, query.limit as i64],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    StoredFill {
+                        user: H160::zero(),
+                        coin: row.get(1)?,
+                        side: row.get(2)?,
+                        px: row.get(3)?,
+                        sz: row.get(4)?,
+                        fee: row.get(5)?,
+                        time: row.get::<_, i64>(6)? as u64,
+                        dir: row.get(7)?,
+                        closed_pnl: row.get(8)?,
+                        oid: row.get::<_, i64>(9)? as u64,
+                        hash: row.get(10)?,
+                        tid: row.get::<_, i64>(11)? as u64,
+                        note: None,
+                    },
+                ))
+            },
+        )?;
+        let mut fills = Vec::new();
+        for row in rows {
+            let (user, mut fill) = row?;
+            fill.user = user.parse()?;
+            fills.push(fill);
+        }
+        Ok(fills)
+    }
+
+    fn high_water_mark(&self, user: H160) -> anyhow::Result<Option<u64>> {
+        let time: Option<i64> = self.connection.query_row(
+            "SELECT MAX(time) FROM fills WHERE user = ?1",
+            params![format!("{user:?}")],
+            |row| row.get(0),
+        )?;
+        Ok(time.map(|time| time as u64))
+    }
+
+    fn delete_user(&mut self, user: H160) -> anyhow::Result<usize> {
+        Ok(self.connection.execute(
+            "DELETE FROM fills WHERE user = ?1",
+            params![format!("{user:?}")],
+        )?)
+    }
+}
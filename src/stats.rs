@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use ethers::types::H160;
+use serde::Serialize;
+
+use crate::categories::Categories;
+use crate::store::StoredFill;
+
+#[derive(Default, Serialize)]
+pub struct CoinStats {
+    pub fills: usize,
+    pub volume: f64,
+    /// Closed PnL net of fees.
+    pub pnl: f64,
+    pub fees: f64,
+    /// Share of closing fills with a positive closed PnL, if any closed.
+    pub win_rate: Option<f64>,
+    #[serde(skip)]
+    wins: usize,
+    #[serde(skip)]
+    closes: usize,
+}
+
+impl CoinStats {
+    fn record(&mut self, fill: &StoredFill) {
+        self.fills += 1;
+        self.volume += fill.px * fill.sz;
+        self.pnl += fill.closed_pnl - fill.fee;
+        self.fees += fill.fee;
+        if fill.closed_pnl != 0.0 {
+            self.closes += 1;
+            if fill.closed_pnl > 0.0 {
+                self.wins += 1;
+            }
+        }
+        self.win_rate = (self.closes > 0).then(|| self.wins as f64 / self.closes as f64);
+    }
+}
+
+#[derive(Serialize)]
+pub struct Stats {
+    pub since: u64,
+    pub address: Option<H160>,
+    #[serde(flatten)]
+    pub total: CoinStats,
+    pub coins: BTreeMap<String, CoinStats>,
+}
+
+/// Summarizes the stored fills at or after `since`, optionally for one address.
+pub fn compute(fills: &[StoredFill], since: u64, address: Option<H160>) -> Stats {
+    let mut total = CoinStats::default();
+    let mut coins: BTreeMap<String, CoinStats> = BTreeMap::new();
+    for fill in fills
+        .iter()
+        .filter(|fill| fill.time >= since)
+        .filter(|fill| address.is_none_or(|address| fill.user == address))
+    {
+        total.record(fill);
+        coins.entry(fill.coin.clone()).or_default().record(fill);
+    }
+    Stats {
+        since,
+        address,
+        total,
+        coins,
+    }
+}
+
+fn line(name: &str, stats: &CoinStats) -> String {
+    let win_rate = stats
+        .win_rate
+        .map(|win_rate| format!(", {:.0}% winning closes", win_rate * 100.0))
+        .unwrap_or_default();
+    format!(
+        "{name}: {} fills, volume ${:.2}, PnL ${:.2} after ${:.2} fees{win_rate}",
+        stats.fills, stats.volume, stats.pnl, stats.fees
+    )
+}
+
+fn busiest_first<'a>(
+    coins: impl Iterator<Item = (&'a String, &'a CoinStats)>,
+) -> Vec<(&'a String, &'a CoinStats)> {
+    let mut coins: Vec<(&String, &CoinStats)> = coins.collect();
+    coins.sort_by(|a, b| b.1.volume.total_cmp(&a.1.volume));
+    coins
+}
+
+impl CoinStats {
+    fn merge(&mut self, other: &CoinStats) {
+        self.fills += other.fills;
+        self.volume += other.volume;
+        self.pnl += other.pnl;
+        self.fees += other.fees;
+        self.wins += other.wins;
+        self.closes += other.closes;
+        self.win_rate = (self.closes > 0).then(|| self.wins as f64 / self.closes as f64);
+    }
+}
+
+impl Stats {
+    /// Plain-text digest with one line per coin, busiest first.
+    pub fn render(&self) -> String {
+        let mut content = line("Total", &self.total);
+        for (coin, stats) in busiest_first(self.coins.iter()) {
+            content.push('\n');
+            content.push_str(&line(coin, stats));
+        }
+        content
+    }
+
+    /// Like `render`, with the coins under a subtotal of their category and
+    /// the uncategorized ones under "other".
+    pub fn render_by_category(&self, categories: &Categories) -> String {
+        if categories.is_empty() {
+            return self.render();
+        }
+        let mut groups: BTreeMap<&str, (CoinStats, BTreeMap<String, &CoinStats>)> = BTreeMap::new();
+        for (coin, stats) in &self.coins {
+            let group = groups
+                .entry(categories.of(coin).unwrap_or("other"))
+                .or_default();
+            group.0.merge(stats);
+            group.1.insert(coin.clone(), stats);
+        }
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        groups.sort_by(|a, b| b.1 .0.volume.total_cmp(&a.1 .0.volume));
+
+        let mut content = line("Total", &self.total);
+        for (category, (subtotal, coins)) in &groups {
+            content.push('\n');
+            content.push_str(&line(category, subtotal));
+            for (coin, stats) in busiest_first(coins.iter().map(|(coin, stats)| (coin, *stats))) {
+                content.push_str(&format!("\n- {}", line(coin, stats)));
+            }
+        }
+        content
+    }
+}
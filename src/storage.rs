@@ -0,0 +1,183 @@
+use ethers::types::H160;
+
+use crate::sqlite;
+use crate::store::StoredFill;
+
+/// Which stored fills to return, newest first.
+pub struct Query {
+    /// In milliseconds.
+    pub since: u64,
+    pub user: Option<H160>,
+    pub limit: usize,
+}
+
+/// A backend that keeps every received fill for later queries.
+///
+/// Fills are keyed by `StoredFill::id`, so saving a fill twice keeps one copy.
+pub trait Store: Send {
+    /// Saves the fills not stored yet and returns how many were new.
+    fn save_fills(&mut self, fills: &[StoredFill]) -> anyhow::Result<usize>;
+
+    fn save_fill(&mut self, fill: &StoredFill) -> anyhow::Result<bool> {
+        Ok(self.save_fills(std::slice::from_ref(fill))? > 0)
+    }
+
+    fn query(&self, query: &Query) -> anyhow::Result<Vec<StoredFill>>;
+
+    /// Time of `user`'s latest stored fill, from which a backfill can resume.
+    fn high_water_mark(&self, user: H160) -> anyhow::Result<Option<u64>>;
+
+    /// Removes every fill of `user` and returns how many there were.
+    fn delete_user(&mut self, user: H160) -> anyhow::Result<usize>;
+}
+
+/// The store configured in the environment: SQLite at `SQLITE_PATH`, or none.
+///
+/// The database keeps its columns in plaintext so it can be queried with SQL,
+/// so it is refused alongside `STORE_ENCRYPTION_KEY` rather than quietly
+/// leaving fills unencrypted at rest.
+pub fn open_from_env() -> anyhow::Result<Option<Box<dyn Store>>> {
+    let Some(path) = sqlite::path_from_env() else {
+        return Ok(None);
+    };
+    if crate::tenants::var("STORE_ENCRYPTION_KEY").is_ok() {
+        return Err(anyhow::anyhow!(
+            "SQLITE_PATH stores fills unencrypted; unset it or STORE_ENCRYPTION_KEY"
+        ));
+    }
+    Ok(Some(Box::new(sqlite::FillDatabase::open(path.as_ref())?)))
+}
+
+/// Keeps fills in memory only, for tests and short-lived tools.
+#[derive(Default)]
+pub struct MemoryStore {
+    fills: Vec<StoredFill>,
+}
+
+impl Store for MemoryStore {
+    fn save_fills(&mut self, fills: &[StoredFill]) -> anyhow::Result<usize> {
+        let mut added = 0;
+        for fill in fills {
+            let id = fill.id();
+            if !self.fills.iter().any(|stored| stored.id() == id) {
+                self.fills.push(fill.clone());
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    fn query(&self, query: &Query) -> anyhow::Result<Vec<StoredFill>> {
+        Ok(select(self.fills.iter(), query))
+    }
+
+    fn high_water_mark(&self, user: H160) -> anyhow::Result<Option<u64>> {
+        Ok(latest(self.fills.iter(), user))
+    }
+
+    fn delete_user(&mut self, user: H160) -> anyhow::Result<usize> {
+        let before = self.fills.len();
+        self.fills.retain(|fill| fill.user != user);
+        Ok(before - self.fills.len())
+    }
+}
+
+/// `query` over fills held in memory.
+pub(crate) fn select<'a>(
+    fills: impl Iterator<Item = &'a StoredFill>,
+    query: &Query,
+) -> Vec<StoredFill> {
+    let mut selected: Vec<StoredFill> = fills
+        .filter(|fill| fill.time >= query.since)
+        .filter(|fill| query.user.is_none_or(|user| fill.user == user))
+        .cloned()
+        .collect();
+    selected.sort_by_key(|fill| std::cmp::Reverse(fill.time));
+    selected.truncate(query.limit);
+    selected
+}
+
+pub(crate) fn latest<'a>(fills: impl Iterator<Item = &'a StoredFill>, user: H160) -> Option<u64> {
+    fills
+        .filter(|fill| fill.user == user)
+        .map(|fill| fill.time)
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(user: H160, time: u64, tid: u64) -> StoredFill {
+        StoredFill {
+            user,
+            coin: "BTC".to_string(),
+            side: "B".to_string(),
+            px: 60000.0,
+            sz: 0.1,
+            time,
+            dir: "Open Long".to_string(),
+            closed_pnl: 0.0,
+            fee: 0.0,
+            oid: 1,
+            hash: "0x00".to_string(),
+            tid,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn keeps_one_copy_of_each_fill() {
+        let user = H160::from_low_u64_be(1);
+        let mut store = MemoryStore::default();
+        assert_eq!(
+            store
+                .save_fills(&[fill(user, 1, 10), fill(user, 2, 20)])
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            store
+                .save_fills(&[fill(user, 2, 20), fill(user, 3, 30)])
+                .unwrap(),
+            1
+        );
+        assert!(!store.save_fill(&fill(user, 1, 10)).unwrap());
+    }
+
+    #[test]
+    fn queries_newest_first() {
+        let (alice, bob) = (H160::from_low_u64_be(1), H160::from_low_u64_be(2));
+        let mut store = MemoryStore::default();
+        store
+            .save_fills(&[
+                fill(alice, 1, 10),
+                fill(bob, 2, 20),
+                fill(alice, 3, 30),
+                fill(alice, 4, 40),
+            ])
+            .unwrap();
+
+        let query = |since, user, limit| {
+            let fills = store.query(&Query { since, user, limit }).unwrap();
+            fills.iter().map(|fill| fill.tid).collect::<Vec<_>>()
+        };
+        assert_eq!(query(0, None, 10), vec![40, 30, 20, 10]);
+        assert_eq!(query(2, Some(alice), 10), vec![40, 30]);
+        assert_eq!(query(0, Some(alice), 1), vec![40]);
+    }
+
+    #[test]
+    fn tracks_the_latest_fill_and_deletes_users() {
+        let (alice, bob) = (H160::from_low_u64_be(1), H160::from_low_u64_be(2));
+        let mut store = MemoryStore::default();
+        store
+            .save_fills(&[fill(alice, 5, 10), fill(alice, 3, 20), fill(bob, 9, 30)])
+            .unwrap();
+        assert_eq!(store.high_water_mark(alice).unwrap(), Some(5));
+
+        assert_eq!(store.delete_user(alice).unwrap(), 2);
+        assert_eq!(store.high_water_mark(alice).unwrap(), None);
+        assert_eq!(store.high_water_mark(bob).unwrap(), Some(9));
+    }
+}
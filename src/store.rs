@@ -0,0 +1,243 @@
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use ethers::types::H160;
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::LineCipher;
+use crate::hyperliquid::fills::Fill;
+use crate::side_label;
+use crate::storage::{self, Query, Store};
+
+/// `FILL_STORE_PATH`, or `fills.jsonl`.
+pub fn path_from_env() -> PathBuf {
+    crate::tenants::var("FILL_STORE_PATH")
+        .unwrap_or_else(|_| "fills.jsonl".to_string())
+        .into()
+}
+
+/// A fill as kept in the local store, whether seen live or imported.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StoredFill {
+    pub user: H160,
+    pub coin: String,
+    /// `B` for buys and `A` for sells, as Hyperliquid reports them;
+    /// `side_label` shows them as `Long` and `Short`.
+    pub side: String,
+    pub px: f64,
+    pub sz: f64,
+    pub time: u64,
+    #[serde(default)]
+    pub dir: String,
+    #[serde(default)]
+    pub closed_pnl: f64,
+    #[serde(default)]
+    pub fee: f64,
+    #[serde(default)]
+    pub oid: u64,
+    #[serde(default)]
+    pub hash: String,
+    /// Hyperliquid's trade id; zero for fills stored before it was kept.
+    #[serde(default)]
+    pub tid: u64,
+    /// Set by analysts through `/note` to mark the trade.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl StoredFill {
+    pub fn new(user: H160, fill: &Fill) -> Self {
+        StoredFill {
+            user,
+            coin: fill.coin.clone(),
+            side: fill.side.clone(),
+            px: fill.px.parse().unwrap_or_default(),
+            sz: fill.sz.parse().unwrap_or_default(),
+            time: fill.time,
+            dir: fill.dir.clone(),
+            closed_pnl: fill.closed_pnl.parse().unwrap_or_default(),
+            fee: fill.fee.parse().unwrap_or_default(),
+            oid: fill.oid,
+            hash: fill.hash.clone(),
+            tid: fill.tid,
+            note: None,
+        }
+    }
+
+    /// A fill is identified by its address and trade id. Fills stored before
+    /// trade ids were kept fall back to everything that tells two partial
+    /// fills of one order apart, so their ids stay the same.
+    fn key(&self) -> String {
+        if self.tid != 0 {
+            return format!("{:?}:{}", self.user, self.tid);
+        }
+        format!(
+            "{:?}:{}:{}:{}:{}:{}",
+            self.user, self.hash, self.oid, self.time, self.px, self.sz
+        )
+    }
+
+    /// Short stable id of the fill for permalinks, derived from its key.
+    pub fn id(&self) -> String {
+        ethers::utils::keccak256(self.key())[..8]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    /// The line reports list an annotated fill as, or `None` without a note.
+    pub fn render_note(&self) -> Option<String> {
+        let note = self.note.as_ref()?;
+        Some(format!(
+            "- {} {} {} at {} by {:?} ({}): {note}",
+            side_label(&self.side),
+            self.sz,
+            self.coin,
+            self.px,
+            self.user,
+            self.id()
+        ))
+    }
+}
+
+/// Append-only JSON-lines store of fills, deduplicated on write and kept in
+/// memory for queries.
+///
+/// With a cipher, new lines are written encrypted. Plaintext lines written
+/// before encryption was turned on are still read.
+pub struct FillStore {
+    path: PathBuf,
+    cipher: Option<LineCipher>,
+    keys: HashSet<String>,
+    fills: Vec<StoredFill>,
+}
+
+impl FillStore {
+    pub fn open(path: PathBuf, cipher: Option<LineCipher>) -> anyhow::Result<Self> {
+        let fills = FillStore::load(&path, cipher.as_ref())?;
+        let keys = fills.iter().map(StoredFill::key).collect();
+        Ok(FillStore {
+            path,
+            cipher,
+            keys,
+            fills,
+        })
+    }
+
+    fn load(path: &Path, cipher: Option<&LineCipher>) -> anyhow::Result<Vec<StoredFill>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut fills = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('{') {
+                fills.push(serde_json::from_str(line)?);
+                continue;
+            }
+            let cipher = cipher.ok_or_else(|| {
+                anyhow::anyhow!("{} is encrypted; set STORE_ENCRYPTION_KEY", path.display())
+            })?;
+            fills.push(serde_json::from_str(&cipher.open(line)?)?);
+        }
+        Ok(fills)
+    }
+
+    pub fn fills(&self) -> &[StoredFill] {
+        &self.fills
+    }
+
+    pub fn contains(&self, fill: &StoredFill) -> bool {
+        self.keys.contains(&fill.key())
+    }
+
+    pub fn find(&self, id: &str) -> Option<&StoredFill> {
+        self.fills.iter().find(|fill| fill.id() == id)
+    }
+
+    /// Rewrites the store without `user`'s fills and returns how many were removed.
+    pub fn purge(&mut self, user: H160) -> anyhow::Result<usize> {
+        let before = self.fills.len();
+        self.fills.retain(|fill| fill.user != user);
+        let removed = before - self.fills.len();
+        if removed == 0 {
+            return Ok(0);
+        }
+        self.rewrite()?;
+        self.keys = self.fills.iter().map(StoredFill::key).collect();
+        Ok(removed)
+    }
+
+    /// Sets or, with `None`, clears the note of the fill with `id` and
+    /// rewrites the store. Returns whether such a fill is stored.
+    pub fn annotate(&mut self, id: &str, note: Option<String>) -> anyhow::Result<bool> {
+        let Some(fill) = self.fills.iter_mut().find(|fill| fill.id() == id) else {
+            return Ok(false);
+        };
+        fill.note = note;
+        self.rewrite()?;
+        Ok(true)
+    }
+
+    fn rewrite(&self) -> anyhow::Result<()> {
+        let temporary = self.path.with_extension("tmp");
+        let mut file = File::create(&temporary)?;
+        for fill in &self.fills {
+            writeln!(file, "{}", self.encode(fill)?)?;
+        }
+        file.sync_all()?;
+        std::fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+
+    fn encode(&self, fill: &StoredFill) -> anyhow::Result<String> {
+        let line = serde_json::to_string(fill)?;
+        match &self.cipher {
+            Some(cipher) => cipher.seal(&line),
+            None => Ok(line),
+        }
+    }
+
+    /// Appends the fills not stored yet and returns how many were new.
+    pub fn append(&mut self, fills: &[StoredFill]) -> anyhow::Result<usize> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut added = 0;
+        for fill in fills {
+            if self.keys.insert(fill.key()) {
+                writeln!(file, "{}", self.encode(fill)?)?;
+                self.fills.push(fill.clone());
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+}
+
+impl Store for FillStore {
+    fn save_fills(&mut self, fills: &[StoredFill]) -> anyhow::Result<usize> {
+        self.append(fills)
+    }
+
+    fn query(&self, query: &Query) -> anyhow::Result<Vec<StoredFill>> {
+        Ok(storage::select(self.fills.iter(), query))
+    }
+
+    fn high_water_mark(&self, user: H160) -> anyhow::Result<Option<u64>> {
+        Ok(storage::latest(self.fills.iter(), user))
+    }
+
+    fn delete_user(&mut self, user: H160) -> anyhow::Result<usize> {
+        self.purge(user)
+    }
+}
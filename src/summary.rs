@@ -0,0 +1,100 @@
+use chrono::{NaiveDate, Utc};
+
+use crate::hyperliquid::positions::PositionTracker;
+
+/// Realized PnL of the current UTC day, summed from `closedPnl` of fills,
+/// and the unrealized PnL the position monitor reads, to measure its change
+/// against the day's first reading and against the previous digest's.
+pub struct DailyPnl {
+    date: NaiveDate,
+    realized: f64,
+    opening_unrealized: Option<f64>,
+    unrealized: Option<f64>,
+    digested_unrealized: Option<f64>,
+}
+
+impl Default for DailyPnl {
+    fn default() -> Self {
+        DailyPnl {
+            date: Utc::now().date_naive(),
+            realized: 0.0,
+            opening_unrealized: None,
+            unrealized: None,
+            digested_unrealized: None,
+        }
+    }
+}
+
+impl DailyPnl {
+    fn roll(&mut self) {
+        let today = Utc::now().date_naive();
+        if self.date != today {
+            self.date = today;
+            self.realized = 0.0;
+            self.opening_unrealized = None;
+        }
+    }
+
+    pub fn record(&mut self, closed_pnl: f64) {
+        self.roll();
+        self.realized += closed_pnl;
+    }
+
+    pub fn realized(&mut self) -> f64 {
+        self.roll();
+        self.realized
+    }
+
+    /// Records the unrealized PnL of a completed poll of every address; the
+    /// day's first becomes its baseline.
+    pub fn observe_unrealized(&mut self, unrealized: f64) {
+        self.roll();
+        self.opening_unrealized.get_or_insert(unrealized);
+        self.digested_unrealized.get_or_insert(unrealized);
+        self.unrealized = Some(unrealized);
+    }
+
+    /// Change of the unrealized PnL since the day's first reading, and the
+    /// latest reading; `None` until the day's first poll completed.
+    pub fn unrealized_change(&mut self) -> Option<(f64, f64)> {
+        self.roll();
+        let unrealized = self.unrealized?;
+        Some((unrealized - self.opening_unrealized?, unrealized))
+    }
+
+    /// Change of the unrealized PnL since the previous call, or since the
+    /// first reading on the first call, which a digest reports for its period.
+    pub fn unrealized_since_digest(&mut self) -> Option<f64> {
+        let unrealized = self.unrealized?;
+        let previous = self.digested_unrealized.replace(unrealized)?;
+        Some(unrealized - previous)
+    }
+}
+
+/// Renders the live summary kept pinned in the channel.
+pub fn render(vault_name: &str, positions: &PositionTracker, daily_pnl: &mut DailyPnl) -> String {
+    let mut lines = vec![format!("**{vault_name} live summary**")];
+
+    let net_positions = positions.net_positions();
+    if net_positions.values().all(|szi| *szi == 0.0) {
+        lines.push("No open positions".to_string());
+    }
+    for (coin, szi) in net_positions.iter().filter(|(_, szi)| **szi != 0.0) {
+        let side = if *szi > 0.0 { "Long" } else { "Short" };
+        lines.push(format!("{side} {coin} {}", szi.abs()));
+    }
+
+    lines.push(format!("Realized PnL today: ${:.2}", daily_pnl.realized()));
+    lines.push(match daily_pnl.unrealized_change() {
+        Some((change, unrealized)) => {
+            format!("Unrealized PnL change today: ${change:.2} (now ${unrealized:.2})")
+        }
+        None => "Unrealized PnL change today: awaiting the first position poll".to_string(),
+    });
+    lines.push(format!("Equity: ${:.2}", positions.total_account_value()));
+    lines.push(format!(
+        "Updated {}",
+        Utc::now().format("%Y-%m-%d %H:%M UTC")
+    ));
+    lines.join("\n")
+}
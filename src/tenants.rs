@@ -0,0 +1,199 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::env::{self, VarError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::config::{Config, Network};
+use crate::{restarts, Watcher};
+
+/// Settings naming a tenant's state files, with the file each defaults to in
+/// the tenant's data directory.
+const STATE_FILES: &[(&str, &str)] = &[
+    ("FILL_STORE_PATH", "fills.jsonl"),
+    ("HISTORY_PATH", "history.json"),
+    ("MUTES_PATH", "mutes.json"),
+    ("ROUTES_PATH", "routes.json"),
+    ("RESTART_LOG_PATH", "restarts.json"),
+];
+
+/// State that is only kept when configured; a tenant gets its own in its data
+/// directory when the process environment turns it on.
+const OPTIONAL_STATE_FILES: &[(&str, &str)] =
+    &[("SQLITE_PATH", "fills.db"), ("CAPTURE_DIR", "captures")];
+
+thread_local! {
+    /// The environment of the tenant whose watcher runs on this thread.
+    static OVERRIDES: RefCell<Option<Arc<BTreeMap<String, String>>>> =
+        const { RefCell::new(None) };
+}
+
+/// Reads a setting from the environment of the tenant running on this thread,
+/// falling back to the process environment. Settings are read through here
+/// rather than `std::env::var` so tenants in one process stay apart.
+pub fn var(key: &str) -> Result<String, VarError> {
+    let own = OVERRIDES.with(|overrides| {
+        overrides
+            .borrow()
+            .as_ref()
+            .and_then(|overrides| overrides.get(key).cloned())
+    });
+    match own {
+        Some(value) => Ok(value),
+        None => env::var(key),
+    }
+}
+
+/// One community's watcher among several run by one process.
+#[derive(Debug, Deserialize)]
+pub struct Tenant {
+    pub name: String,
+    /// The tenant's watch targets, as for `--config`.
+    pub config: PathBuf,
+    /// Where the tenant's state files are kept (default: a directory named
+    /// after the tenant).
+    pub data_dir: Option<PathBuf>,
+    /// Serves the tenant's HTTP API read-only, as `--public` does.
+    #[serde(default)]
+    pub public: bool,
+    /// Settings of this tenant over the process environment, such as its
+    /// `DISCORD_WEBHOOK_URL`, `HTTP_LISTEN_ADDR` and `API_TOKEN`.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl Tenant {
+    /// The tenant's settings, with its state files in its data directory
+    /// unless it names them itself.
+    fn environment(&self) -> anyhow::Result<BTreeMap<String, String>> {
+        let data_dir = self
+            .data_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(&self.name));
+        fs::create_dir_all(&data_dir)?;
+        let mut environment = self.env.clone();
+        for (key, file_name) in STATE_FILES {
+            environment
+                .entry(key.to_string())
+                .or_insert_with(|| data_dir.join(file_name).display().to_string());
+        }
+        for (key, file_name) in OPTIONAL_STATE_FILES {
+            if env::var(key).is_ok() {
+                environment
+                    .entry(key.to_string())
+                    .or_insert_with(|| data_dir.join(file_name).display().to_string());
+            }
+        }
+        Ok(environment)
+    }
+}
+
+#[derive(Deserialize)]
+struct TenantsFile {
+    tenants: Vec<Tenant>,
+}
+
+/// Reads the tenants from a TOML file:
+///
+/// ```toml
+/// [[tenants]]
+/// name = "alpha"
+/// config = "alpha.toml"
+///
+/// [tenants.env]
+/// DISCORD_WEBHOOK_URL = "https://discord.com/api/webhooks/..."
+/// HTTP_LISTEN_ADDR = "127.0.0.1:8081"
+/// API_TOKEN = "..."
+/// ```
+pub fn load(path: &Path) -> anyhow::Result<Vec<Tenant>> {
+    let file: TenantsFile = toml::from_str(&fs::read_to_string(path)?)?;
+    if file.tenants.is_empty() {
+        return Err(anyhow!("{} lists no tenants", path.display()));
+    }
+    let mut names = HashSet::new();
+    for tenant in &file.tenants {
+        if !names.insert(&tenant.name) {
+            return Err(anyhow!("tenant {:?} is listed twice", tenant.name));
+        }
+    }
+    Ok(file.tenants)
+}
+
+/// Calls `f` for each tenant in turn on this thread, with the settings read
+/// through `var` coming from that tenant's environment, as one-off commands
+/// such as `purge` need.
+pub fn for_each(
+    tenants: &[Tenant],
+    mut f: impl FnMut(&Tenant) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    for tenant in tenants {
+        let environment = tenant.environment()?;
+        OVERRIDES.with(|overrides| *overrides.borrow_mut() = Some(Arc::new(environment)));
+        let result = f(tenant);
+        OVERRIDES.with(|overrides| *overrides.borrow_mut() = None);
+        result?;
+    }
+    Ok(())
+}
+
+/// Runs each tenant's watcher on a thread and runtime of its own, so every
+/// setting it reads comes from its own environment. `network` overrides each
+/// config file's, as `--network` does. A tenant whose watcher fails stops
+/// alone; the first failure is returned once all have stopped.
+pub fn run(
+    tenants: Vec<Tenant>,
+    network: Option<Network>,
+    metrics_prefix: Option<String>,
+) -> anyhow::Result<()> {
+    let mut handles = Vec::new();
+    for tenant in tenants {
+        let environment = tenant.environment()?;
+        let mut config = Config::load(&tenant.config)?;
+        if let Some(network) = network {
+            config.network = network;
+        }
+        let metrics_prefix = metrics_prefix.clone();
+        let handle = thread::Builder::new()
+            .name(format!("tenant-{}", tenant.name))
+            .spawn(move || -> anyhow::Result<()> {
+                OVERRIDES.with(|overrides| *overrides.borrow_mut() = Some(Arc::new(environment)));
+                let mut watcher = Watcher::new(config).with_config_path(tenant.config);
+                if let Some(prefix) = metrics_prefix {
+                    watcher = watcher.with_metrics_prefix(prefix);
+                }
+                if tenant.public {
+                    watcher = watcher.with_public_api();
+                }
+                let result = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()?
+                    .block_on(watcher.run());
+                if let Err(err) = &result {
+                    warn!("tenant {} stopped: {err:?}", tenant.name);
+                    restarts::record_exit_error(err);
+                }
+                result
+            })?;
+        handles.push(handle);
+    }
+
+    let mut first_error = None;
+    for handle in handles {
+        let result = handle
+            .join()
+            .unwrap_or_else(|_| Err(anyhow!("tenant thread panicked")));
+        if let Err(err) = result {
+            first_error.get_or_insert(err);
+        }
+    }
+    match first_error {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use ethers::types::H160;
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+struct UserVelocity {
+    recent: VecDeque<Instant>,
+    last_fill: Instant,
+    spiking: bool,
+    silence_reported: bool,
+}
+
+/// Tracks fills per minute of each address to spot bursts and unusual silence.
+pub struct VelocityTracker {
+    spike_per_minute: usize,
+    silence: Duration,
+    users: HashMap<H160, UserVelocity>,
+}
+
+impl VelocityTracker {
+    pub fn new(spike_per_minute: usize, silence: Duration) -> Self {
+        VelocityTracker {
+            spike_per_minute,
+            silence,
+            users: HashMap::new(),
+        }
+    }
+
+    /// Records a fill and returns the current fills per minute when the address
+    /// just started spiking.
+    pub fn record(&mut self, user: H160) -> Option<usize> {
+        let now = Instant::now();
+        let velocity = self.users.entry(user).or_insert_with(|| UserVelocity {
+            recent: VecDeque::new(),
+            last_fill: now,
+            spiking: false,
+            silence_reported: false,
+        });
+        velocity.recent.push_back(now);
+        while let Some(at) = velocity.recent.front() {
+            if now.duration_since(*at) < RATE_WINDOW {
+                break;
+            }
+            velocity.recent.pop_front();
+        }
+        velocity.last_fill = now;
+        velocity.silence_reported = false;
+
+        let rate = velocity.recent.len();
+        if rate < self.spike_per_minute {
+            velocity.spiking = false;
+            return None;
+        }
+        if velocity.spiking {
+            return None;
+        }
+        velocity.spiking = true;
+        Some(rate)
+    }
+
+    /// Drops what was recorded of `user`, which is no longer watched.
+    pub fn forget(&mut self, user: H160) {
+        self.users.remove(&user);
+    }
+
+    /// Returns previously active addresses that went silent, each only once per
+    /// silent period.
+    pub fn silent_users(&mut self) -> Vec<(H160, Duration)> {
+        let mut silent = Vec::new();
+        for (user, velocity) in self.users.iter_mut() {
+            let elapsed = velocity.last_fill.elapsed();
+            if elapsed >= self.silence && !velocity.silence_reported {
+                velocity.silence_reported = true;
+                silent.push((*user, elapsed));
+            }
+        }
+        silent
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ethers::types::H160;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+use tracing::warn;
+
+use super::Shared;
+use crate::hyperliquid::{ledger, positions};
+use crate::notify::alerts::Priority;
+use crate::{costs, metrics, now_ms, summary};
+
+/// Records the funding paid by every watched address into `cost_ledger`
+/// hourly, summing it up every `summary` period when one is set.
+pub(crate) struct FundingPoller {
+    pub(crate) shared: Shared,
+    pub(crate) cost_ledger: Arc<Mutex<costs::CostLedger>>,
+    pub(crate) summary: Option<costs::FundingPeriod>,
+}
+
+impl FundingPoller {
+    pub(crate) async fn run(self) {
+        let started_ms = now_ms();
+        let mut last_polled_ms: HashMap<H160, u64> = HashMap::new();
+        let mut last_summary_ms = started_ms;
+        loop {
+            // Funding is paid hourly.
+            sleep(Duration::from_secs(60 * 60)).await;
+
+            let users = self.shared.watchlist.lock().await.users();
+            for user in users {
+                let start_time = *last_polled_ms.entry(user).or_insert(started_ms);
+                let payments = {
+                    let info_client = self.shared.info_client.lock().await;
+                    ledger::fetch_funding(&info_client, user, start_time).await
+                };
+                match payments {
+                    Ok(payments) => {
+                        let mut cost_ledger = self.cost_ledger.lock().await;
+                        for payment in &payments {
+                            cost_ledger.record_funding(
+                                user,
+                                &payment.delta.coin,
+                                payment.time,
+                                payment.delta.usdc.parse().unwrap_or_default(),
+                            );
+                        }
+                        if let Some(latest) = payments.iter().map(|payment| payment.time).max() {
+                            let cursor = last_polled_ms.entry(user).or_insert(start_time);
+                            *cursor = (*cursor).max(latest + 1);
+                        }
+                    }
+                    Err(err) => warn!("failed to fetch funding of {user:?}: {err:?}"),
+                }
+            }
+
+            let Some(period) = self.summary else {
+                continue;
+            };
+            let now = now_ms();
+            // Polls drift by the time they take, so a daily summary is due
+            // a little before a full day has passed.
+            if now.saturating_sub(last_summary_ms) + 5 * 60 * 1000 < period.window_ms() {
+                continue;
+            }
+            last_summary_ms = now;
+            let funding = self
+                .cost_ledger
+                .lock()
+                .await
+                .funding_since(now.saturating_sub(period.window_ms()));
+            if funding.is_empty() {
+                continue;
+            }
+            let labels: BTreeMap<H160, String> = {
+                let watchlist = self.shared.watchlist.lock().await;
+                funding
+                    .by_user
+                    .keys()
+                    .filter_map(|user| Some((*user, watchlist.label(*user)?.to_string())))
+                    .collect()
+            };
+            self.shared
+                .alerter
+                .send(Priority::Low, &funding.render(period, &labels))
+                .await;
+        }
+    }
+}
+
+/// Alerts on liquidations and on deposits and withdrawals of at least
+/// `threshold_usd` in the ledgers of the subscribed addresses.
+pub(crate) struct LedgerAlerts {
+    pub(crate) shared: Shared,
+    pub(crate) interval: Duration,
+    pub(crate) threshold_usd: f64,
+    pub(crate) liquidation_mention: Option<String>,
+}
+
+impl LedgerAlerts {
+    pub(crate) async fn run(self) {
+        let mut last_polled_ms: HashMap<H160, u64> = HashMap::new();
+        loop {
+            sleep(self.interval).await;
+
+            let users = self.shared.subscriptions.lock().await.users();
+            for user in users {
+                let now_ms = now_ms();
+                let start_time = *last_polled_ms.entry(user).or_insert(now_ms);
+                let updates = {
+                    let info_client = self.shared.info_client.lock().await;
+                    ledger::fetch_updates(&info_client, user, start_time).await
+                };
+                let updates = match updates {
+                    Ok(updates) => updates,
+                    Err(err) => {
+                        warn!("failed to fetch ledger updates of {user:?}: {err:?}");
+                        continue;
+                    }
+                };
+
+                for update in updates.iter() {
+                    if let Some(content) = update.render_liquidation(user) {
+                        self.shared
+                            .alerter
+                            .send_mentioning(
+                                Priority::Critical,
+                                &content,
+                                self.liquidation_mention.as_deref(),
+                            )
+                            .await;
+                        continue;
+                    }
+                    let label = match update.delta.type_.as_str() {
+                        "deposit" => "Deposit",
+                        "withdraw" => "Withdrawal",
+                        _ => continue,
+                    };
+                    if update.usdc() < self.threshold_usd {
+                        continue;
+                    }
+
+                    let content = format!("{label}: {user:?} ${:.2}", update.usdc());
+                    self.shared.alerter.send(Priority::High, &content).await;
+                }
+
+                let latest = updates.iter().map(|update| update.time + 1).max();
+                last_polled_ms.insert(user, latest.unwrap_or(start_time).max(start_time));
+            }
+        }
+    }
+}
+
+/// Polls the clearinghouse state of the subscribed addresses into `tracker`,
+/// alerting on leverage changes and on whichever thresholds are set.
+pub(crate) struct PositionMonitor {
+    pub(crate) shared: Shared,
+    pub(crate) tracker: Arc<Mutex<positions::PositionTracker>>,
+    /// Takes the unrealized PnL of every completed poll.
+    pub(crate) daily_pnl: Arc<Mutex<summary::DailyPnl>>,
+    pub(crate) interval: Duration,
+    pub(crate) equity_alert_below: Option<f64>,
+    pub(crate) equity_alert_above: Option<f64>,
+    pub(crate) liquidation_alert_pct: Option<f64>,
+    pub(crate) liquidation_mention: Option<String>,
+    pub(crate) concentration_alert_pct: Option<f64>,
+    pub(crate) delta_alert_usd: Option<f64>,
+}
+
+impl PositionMonitor {
+    pub(crate) async fn run(self) {
+        let alerter = &self.shared.alerter;
+        let mut concentrated = false;
+        let mut drifted_coins: HashSet<String> = HashSet::new();
+        let mut near_liquidation: HashSet<(H160, String)> = HashSet::new();
+        loop {
+            let users = self.shared.subscriptions.lock().await.users();
+            for user in users {
+                let state = match self.shared.info_client.lock().await.user_state(user).await {
+                    Ok(state) => state,
+                    Err(err) => {
+                        warn!("failed to fetch clearinghouse state of {user:?}: {err:?}");
+                        continue;
+                    }
+                };
+
+                let mut position_tracker = self.tracker.lock().await;
+                let previous_value = position_tracker.account_value(user);
+                let changes = position_tracker.update(user, &state);
+                let account_value = position_tracker.account_value(user).unwrap_or_default();
+                let mut liquidation_alerts = Vec::new();
+                if let Some(within_pct) = self.liquidation_alert_pct {
+                    let near = position_tracker.near_liquidation(user, within_pct);
+                    for (coin, position, distance) in &near {
+                        if near_liquidation.contains(&(user, coin.to_string())) {
+                            continue;
+                        }
+                        liquidation_alerts.push(format!(
+                            "Liquidation risk: {user:?} {} {coin} is {distance:.1}% from liquidation at {} (mark {})",
+                            position.szi,
+                            position.liquidation_px.unwrap_or_default(),
+                            position.mark_px().unwrap_or_default()
+                        ));
+                    }
+                    near_liquidation.retain(|(near_user, _)| *near_user != user);
+                    near_liquidation
+                        .extend(near.iter().map(|(coin, _, _)| (user, coin.to_string())));
+                }
+                drop(position_tracker);
+                metrics::set_account_value(user, account_value);
+
+                let mut equity_alerts = Vec::new();
+                if let Some(below) = self.equity_alert_below {
+                    if account_value < below && previous_value.is_none_or(|v| v >= below) {
+                        equity_alerts.push((Priority::Critical, format!(
+                            "Equity alert: {user:?} account value ${account_value:.2} dropped below ${below:.2}"
+                        )));
+                    }
+                }
+                if let Some(above) = self.equity_alert_above {
+                    if account_value > above && previous_value.is_none_or(|v| v <= above) {
+                        equity_alerts.push((Priority::Normal, format!(
+                            "Equity alert: {user:?} account value ${account_value:.2} rose above ${above:.2}"
+                        )));
+                    }
+                }
+                for (priority, content) in equity_alerts {
+                    alerter.send(priority, &content).await;
+                }
+                for content in liquidation_alerts {
+                    alerter
+                        .send_mentioning(
+                            Priority::High,
+                            &content,
+                            self.liquidation_mention.as_deref(),
+                        )
+                        .await;
+                }
+
+                for change in changes {
+                    let content = format!(
+                        "Leverage change: {:?} {} {}x {} -> {}x {}",
+                        change.user,
+                        change.coin,
+                        change.from.value,
+                        change.from.type_,
+                        change.to.value,
+                        change.to.type_
+                    );
+                    alerter.send(Priority::High, &content).await;
+                }
+            }
+            let unrealized = self.tracker.lock().await.total_unrealized_pnl();
+            self.daily_pnl.lock().await.observe_unrealized(unrealized);
+
+            if let Some(limit) = self.concentration_alert_pct {
+                let concentration = self.tracker.lock().await.concentration();
+                let was_concentrated = concentrated;
+                concentrated = concentration.as_ref().is_some_and(|(_, pct)| *pct > limit);
+                if let Some((coin, pct)) =
+                    concentration.filter(|_| concentrated && !was_concentrated)
+                {
+                    let content = format!(
+                        "Concentration alert: {coin} exposure is {pct:.1}% of vault equity, above {limit:.1}%"
+                    );
+                    alerter.send(Priority::High, &content).await;
+                }
+            }
+
+            if let Some(limit) = self.delta_alert_usd {
+                let exposures = self.tracker.lock().await.net_exposures();
+                let mut drifted = HashSet::new();
+                for (coin, exposure) in exposures {
+                    if exposure.abs() <= limit {
+                        continue;
+                    }
+                    if !drifted_coins.contains(&coin) {
+                        let direction = if exposure > 0.0 { "long" } else { "short" };
+                        let content = format!(
+                            "Delta alert: net {direction} ${:.2} of {coin} across child addresses, beyond ${limit:.2}",
+                            exposure.abs()
+                        );
+                        alerter.send(Priority::High, &content).await;
+                    }
+                    drifted.insert(coin);
+                }
+                drifted_coins = drifted;
+            }
+
+            sleep(self.interval).await;
+        }
+    }
+}
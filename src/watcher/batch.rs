@@ -0,0 +1,439 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ethers::types::H160;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use super::Shared;
+use crate::config::Destination;
+use crate::hyperliquid::fills::Fill;
+use crate::hyperliquid::{events, orders, positions, prices};
+use crate::notify::discord;
+use crate::notify::notifier::{EventKinds, Notifier, TradeEvent};
+use crate::{
+    batching, dedup, filters, format_usd, metrics, now_ms, permalink, routes, side_label, store,
+};
+
+/// Posts the fills gathered in `trades` every batch interval, one message per
+/// destination.
+///
+/// On shutdown it posts what it holds once more when `flush_receiver` is sent
+/// a sender, and drops that sender once done.
+pub(crate) struct Batcher {
+    pub(crate) shared: Shared,
+    pub(crate) trades: Arc<Mutex<Vec<(H160, Fill)>>>,
+    pub(crate) flush_receiver: mpsc::Receiver<oneshot::Sender<()>>,
+    pub(crate) notifiers: Arc<Vec<(Box<dyn Notifier>, EventKinds)>>,
+    pub(crate) position_tracker: Arc<Mutex<positions::PositionTracker>>,
+    pub(crate) routes: Arc<Mutex<routes::Routes>>,
+    pub(crate) fill_positions: Arc<Mutex<positions::FillPositions>>,
+    pub(crate) batch_interval: Duration,
+    pub(crate) aggregation: batching::Aggregation,
+    pub(crate) adaptive_batching: Option<batching::AdaptiveBatching>,
+    pub(crate) shared_dedup: Option<dedup::SharedDedup>,
+    pub(crate) backpressure: Option<filters::Backpressure>,
+    pub(crate) notional_filter: filters::NotionalFilter,
+    pub(crate) order_summaries: orders::OrderSummaries,
+    pub(crate) verbose: bool,
+    pub(crate) book_depth_min_usd: Option<f64>,
+    pub(crate) public_base_url: Option<String>,
+}
+
+impl Batcher {
+    pub(crate) async fn run(mut self) {
+        let client = &self.shared.client;
+        let webhook_pool = &self.shared.webhook_pool;
+        let vault_name = &self.shared.vault_name;
+        let mut order_tracker = orders::OrderTracker::default();
+        loop {
+            let interval = self
+                .adaptive_batching
+                .as_ref()
+                .map_or(self.batch_interval, |adaptive| {
+                    adaptive.interval(self.batch_interval)
+                });
+            let flushed = tokio::select! {
+                () = sleep(interval) => None,
+                flushed = self.flush_receiver.recv() => flushed,
+            };
+            let last_batch = flushed.is_some();
+
+            let mut trades = std::mem::take(&mut *self.trades.lock().await);
+            if let Some(shared_dedup) = &self.shared_dedup {
+                let mut claimed = Vec::with_capacity(trades.len());
+                for (user, trade) in trades {
+                    let id = store::StoredFill::new(user, &trade).id();
+                    if shared_dedup.claim(&id).await {
+                        claimed.push((user, trade));
+                    }
+                }
+                trades = claimed;
+            }
+            let mut merge = self.aggregation == batching::Aggregation::Merge;
+            if let Some(adaptive) = &mut self.adaptive_batching {
+                if adaptive.record(trades.len()) {
+                    let notice = if adaptive.is_busy() {
+                        format!(
+                            "Busy: {} fills in the last minute, posting every {}s with fills merged per address, coin and side",
+                            adaptive.fills_per_minute(),
+                            adaptive.busy_interval().as_secs()
+                        )
+                    } else if self.aggregation == batching::Aggregation::Merge {
+                        format!(
+                            "Calm again: back to posting every {}s",
+                            self.batch_interval.as_secs_f64()
+                        )
+                    } else {
+                        "Calm again: back to one line per fill".to_string()
+                    };
+                    info!("{notice}");
+                    webhook_pool.post(client, Some(vault_name), &notice).await;
+                }
+                merge |= adaptive.is_busy();
+            }
+            if trades.is_empty() {
+                if last_batch {
+                    return;
+                }
+                continue;
+            }
+            // Under load only the larger fills keep their own line.
+            let degrade_below = self
+                .backpressure
+                .as_ref()
+                .filter(|backpressure| backpressure.is_saturated(trades.len()))
+                .map(|backpressure| backpressure.min_notional_usd());
+            if let Some(min_usd) = degrade_below {
+                warn!(
+                    "{} fills in one batch, summing up those under {}",
+                    trades.len(),
+                    format_usd(min_usd)
+                );
+            }
+
+            // Everything the batch needs from Hyperliquid is fetched first, so
+            // the locks taken to render it are not held across requests.
+            let unseeded: BTreeSet<H160> = {
+                let fill_positions = self.fill_positions.lock().await;
+                trades
+                    .iter()
+                    .map(|(user, _)| *user)
+                    .filter(|user| !fill_positions.is_seeded(*user))
+                    .collect()
+            };
+            // Addresses added by a reload are seeded on their first fill.
+            let mut states = Vec::new();
+            for user in unseeded {
+                let state = self.shared.info_client.lock().await.user_state(user).await;
+                match state {
+                    Ok(state) => states.push((user, state)),
+                    Err(err) => warn!("failed to seed positions of {user:?}: {err:?}"),
+                }
+            }
+            if self.order_summaries != orders::OrderSummaries::Off {
+                for (user, trade) in trades.iter() {
+                    if !order_tracker.record(*user, trade) {
+                        continue;
+                    }
+                    let size = {
+                        let info_client = self.shared.info_client.lock().await;
+                        orders::fetch_order_size(&info_client, *user, trade.oid).await
+                    };
+                    match size {
+                        Ok(Some(size)) => order_tracker.set_size(*user, trade.oid, size),
+                        Ok(None) => (),
+                        Err(err) => warn!("failed to fetch order {}: {err:?}", trade.oid),
+                    }
+                }
+            }
+            let mut depths: HashMap<String, prices::BookDepth> = HashMap::new();
+            if let Some(min_usd) = self
+                .book_depth_min_usd
+                .filter(|_| !merge && !self.order_summaries.replaces_fills())
+            {
+                for (_, trade) in trades.iter() {
+                    let notional = notional(trade);
+                    let own_line = notional >= min_usd
+                        && !self.notional_filter.is_small(&trade.coin, notional)
+                        && degrade_below.is_none_or(|degrade_usd| notional >= degrade_usd);
+                    if !own_line || depths.contains_key(&trade.coin) {
+                        continue;
+                    }
+                    let depth = {
+                        let info_client = self.shared.info_client.lock().await;
+                        prices::BookDepth::fetch(&info_client, &trade.coin).await
+                    };
+                    match depth {
+                        Ok(depth) => {
+                            depths.insert(trade.coin.clone(), depth);
+                        }
+                        Err(err) => warn!("failed to fetch the {} book: {err:?}", trade.coin),
+                    }
+                }
+            }
+
+            let position_tracker = self.position_tracker.lock().await;
+            let routes = self.routes.lock().await;
+            let watchlist = self.shared.watchlist.lock().await;
+            let mut fill_positions = self.fill_positions.lock().await;
+            for (user, state) in &states {
+                if !fill_positions.is_seeded(*user) {
+                    fill_positions.seed(*user, state);
+                }
+            }
+            let mut lines: BTreeMap<Destination, Vec<String>> = BTreeMap::new();
+            let mut notes: BTreeMap<Destination, Vec<String>> = BTreeMap::new();
+            let mut route_fills: BTreeMap<Destination, Vec<events::FillEvent>> = BTreeMap::new();
+            let mut labels: BTreeMap<H160, String> = BTreeMap::new();
+            let mut touched_orders: HashSet<(H160, u64)> = HashSet::new();
+            let mut small_fills: BTreeMap<Destination, (usize, f64)> = BTreeMap::new();
+            let mut degraded_fills: BTreeMap<Destination, BTreeMap<String, (usize, f64)>> =
+                BTreeMap::new();
+            let mut merger = batching::FillMerger::default();
+            for (user, trade) in trades.iter() {
+                let position_change = fill_positions.apply(*user, trade);
+
+                if self.order_summaries.replaces_fills() {
+                    touched_orders.insert((*user, trade.oid));
+                    continue;
+                }
+
+                let notional = notional(trade);
+                if self.notional_filter.is_small(&trade.coin, notional) {
+                    if self.notional_filter.summarize {
+                        let route = watchlist
+                            .destination(*user)
+                            .or_webhook(routes.get(&trade.coin));
+                        let small = small_fills.entry(route).or_default();
+                        small.0 += 1;
+                        small.1 += notional;
+                    }
+                    continue;
+                }
+                if degrade_below.is_some_and(|min_usd| notional < min_usd) {
+                    let route = watchlist
+                        .destination(*user)
+                        .or_webhook(routes.get(&trade.coin));
+                    let degraded = degraded_fills
+                        .entry(route)
+                        .or_default()
+                        .entry(trade.coin.clone())
+                        .or_default();
+                    degraded.0 += 1;
+                    degraded.1 += notional;
+                    continue;
+                }
+                if merge {
+                    let route = watchlist
+                        .destination(*user)
+                        .or_webhook(routes.get(&trade.coin));
+                    let label = watchlist.label(*user);
+                    if let Some(label) = label {
+                        labels.insert(*user, label.to_string());
+                    }
+                    merger.add(
+                        route,
+                        events::FillEvent::new(*user, trade),
+                        label,
+                        position_change.after,
+                    );
+                    continue;
+                }
+
+                let mut line = format!("{} {} {}", position_change.label, trade.coin, trade.sz);
+                if self.verbose {
+                    line = format!("{} {line}", position_change.arrow());
+                }
+                if let Some(label) = watchlist.label(*user) {
+                    line = format!("{label}: {line}");
+                }
+                // Prices and sizes keep the precision Hyperliquid sends for the asset.
+                line.push_str(&format!(" @ {} ({})", trade.px, format_usd(notional)));
+                let fee: f64 = trade.fee.parse().unwrap_or_default();
+                if fee != 0.0 {
+                    line.push_str(&format!(", fee {}", format_usd(fee)));
+                }
+                let closed_pnl: f64 = trade.closed_pnl.parse().unwrap_or_default();
+                if closed_pnl != 0.0 {
+                    line.push_str(&format!(", PnL {}", format_usd(closed_pnl)));
+                }
+                line.push_str(&format!(", position {}", position_change.after));
+                if let Some(depth) = depths.get(&trade.coin).filter(|_| {
+                    self.book_depth_min_usd
+                        .is_some_and(|min_usd| notional >= min_usd)
+                }) {
+                    line.push_str(&format!(" [{}]", depth.render()));
+                }
+                if self.verbose {
+                    match position_tracker.get(*user, &trade.coin) {
+                        Some(position) if position.leverage.type_ == "isolated" => {
+                            line.push_str(&format!(
+                                " [isolated, margin ${:.2}]",
+                                position.margin_used
+                            ));
+                        }
+                        Some(position) => line.push_str(&format!(" [{}]", position.leverage.type_)),
+                        None => (),
+                    }
+                    if let Some(gauge) = position_change.gauge() {
+                        line.push_str(&format!(" [{gauge}]"));
+                    }
+                }
+                let mut fill_event = events::FillEvent::new(*user, trade);
+                if let Some(base_url) = &self.public_base_url {
+                    let url = permalink::url(base_url, &store::StoredFill::new(*user, trade));
+                    line.push_str(&format!(" <{url}>"));
+                    fill_event.permalink = Some(url);
+                }
+                let route = watchlist
+                    .destination(*user)
+                    .or_webhook(routes.get(&trade.coin));
+                route_fills
+                    .entry(route.clone())
+                    .or_default()
+                    .push(fill_event);
+                if let Some(label) = watchlist.label(*user) {
+                    labels.insert(*user, label.to_string());
+                }
+                lines.entry(route).or_default().push(line);
+            }
+            for (route, merged) in merger.into_groups() {
+                lines
+                    .entry(route.clone())
+                    .or_default()
+                    .push(merged.render());
+                route_fills.entry(route).or_default().push(merged.event);
+            }
+            for (route, (count, notional)) in small_fills {
+                notes
+                    .entry(route)
+                    .or_default()
+                    .push(format!("{count} smaller fills, ${notional:.2} in total"));
+            }
+            for (route, coins) in degraded_fills {
+                let summary: Vec<String> = coins
+                    .into_iter()
+                    .map(|(coin, (count, notional))| {
+                        format!("{count} {coin} fills ({})", format_usd(notional))
+                    })
+                    .collect();
+                notes
+                    .entry(route)
+                    .or_default()
+                    .push(format!("Summed up under load: {}", summary.join(", ")));
+            }
+            let mut edits: Vec<(orders::LiveMessage, String)> = Vec::new();
+            for order in order_tracker.take_completed() {
+                let line = format!(
+                    "Order fully filled: {:?} {} {} {} @ {:.4} avg",
+                    order.user,
+                    side_label(&order.side),
+                    order.size,
+                    order.coin,
+                    order.average_px
+                );
+                if let Some(message) = order.message {
+                    edits.push((message, line));
+                    continue;
+                }
+                let route = watchlist
+                    .destination(order.user)
+                    .or_webhook(routes.get(&order.coin));
+                notes.entry(route).or_default().push(line);
+            }
+
+            let mut live_posts: Vec<((H160, u64), Option<String>, String)> = Vec::new();
+            if self.order_summaries == orders::OrderSummaries::Live {
+                for (user, oid) in touched_orders {
+                    let Some(running) = order_tracker.running(user, oid) else {
+                        continue;
+                    };
+                    let line = format!(
+                        "Order filling: {user:?} {} {} {} @ {:.4} VWAP",
+                        side_label(&running.side),
+                        running.filled,
+                        running.coin,
+                        running.average_px
+                    );
+                    match running.message {
+                        Some(message) => edits.push((message, line)),
+                        None => {
+                            let route = watchlist
+                                .destination(user)
+                                .or_webhook(routes.get(&running.coin))
+                                .discord_webhook_url;
+                            live_posts.push(((user, oid), route, line));
+                        }
+                    }
+                }
+            }
+            drop(watchlist);
+            drop(routes);
+            drop(fill_positions);
+            drop(position_tracker);
+
+            let batch_routes: BTreeSet<Destination> =
+                lines.keys().chain(notes.keys()).cloned().collect();
+            for route in batch_routes {
+                let event = TradeEvent {
+                    title: vault_name.clone(),
+                    lines: lines.remove(&route).unwrap_or_default(),
+                    notes: notes.remove(&route).unwrap_or_default(),
+                    fills: route_fills.remove(&route).unwrap_or_default(),
+                    labels: labels.clone(),
+                    route,
+                };
+                for (notifier, kinds) in self.notifiers.iter() {
+                    if !kinds.fills {
+                        continue;
+                    }
+                    if notifier.send(&event).await {
+                        let now = now_ms();
+                        for fill in &event.fills {
+                            metrics::observe_latency(
+                                notifier.name(),
+                                now.saturating_sub(fill.time) as f64 / 1000.0,
+                            );
+                        }
+                    }
+                }
+            }
+            for (message, line) in edits {
+                let content = format!("**{vault_name}**\n{line}");
+                discord::edit(client, &message.webhook_url, &message.message_id, &content).await;
+            }
+            for ((user, oid), route, line) in live_posts {
+                let content = format!("**{vault_name}**\n{line}");
+                let posted = match route {
+                    Some(webhook_url) => {
+                        discord::post_with_id(client, &webhook_url, Some(vault_name), &content)
+                            .await
+                            .map(|posted| (webhook_url, posted))
+                    }
+                    None => {
+                        webhook_pool
+                            .post_with_id(client, Some(vault_name), &content)
+                            .await
+                    }
+                };
+                if let Some((webhook_url, posted)) = posted {
+                    let message = orders::LiveMessage {
+                        webhook_url,
+                        message_id: posted.id,
+                    };
+                    order_tracker.set_message(user, oid, message);
+                }
+            }
+            if last_batch {
+                return;
+            }
+        }
+    }
+}
+
+fn notional(fill: &Fill) -> f64 {
+    fill.px.parse::<f64>().unwrap_or_default() * fill.sz.parse::<f64>().unwrap_or_default()
+}
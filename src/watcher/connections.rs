@@ -0,0 +1,189 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use ethers::types::H160;
+use hyperliquid_rust_sdk::{Message, Subscription};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+use tracing::warn;
+
+use super::Shared;
+use crate::hyperliquid::endpoints::Endpoints;
+use crate::hyperliquid::prices::MidCache;
+use crate::hyperliquid::subscriptions::{SubscriptionManager, SubscriptionState};
+use crate::notify::alerts::Priority;
+use crate::notify::lifecycle;
+use crate::{health, metrics};
+
+/// Replaces failed and silent subscriptions every 15 seconds.
+pub(crate) struct Reconnector {
+    pub(crate) shared: Shared,
+    pub(crate) max_silence: Duration,
+    /// Consecutive failures after which a subscription is reported, once per
+    /// streak.
+    pub(crate) failure_alert_threshold: u32,
+}
+
+impl Reconnector {
+    pub(crate) async fn run(self) {
+        let retrying_users = |subscriptions: &SubscriptionManager| -> Vec<H160> {
+            subscriptions
+                .statuses()
+                .iter()
+                .filter(|(_, status)| status.state == SubscriptionState::Retrying)
+                .map(|(user, _)| *user)
+                .collect()
+        };
+        loop {
+            sleep(Duration::from_secs(15)).await;
+
+            // Connecting can take a while, so it happens without the lock.
+            let (connector, stale) = {
+                let subscriptions = self.shared.subscriptions.lock().await;
+                (
+                    subscriptions.connector(),
+                    subscriptions.stale(self.max_silence),
+                )
+            };
+            let reconnection = connector.connect(stale).await;
+            let mut subscriptions = self.shared.subscriptions.lock().await;
+            let previously_retrying = retrying_users(&subscriptions);
+            let failures = subscriptions.swap_in(reconnection);
+            let retrying = retrying_users(&subscriptions);
+            metrics::set_subscriptions(
+                subscriptions.statuses().len() - retrying.len(),
+                retrying.len(),
+            );
+            for user in previously_retrying {
+                if !retrying.contains(&user) && subscriptions.statuses().contains_key(&user) {
+                    self.shared
+                        .lifecycle
+                        .emit(lifecycle::Event::Reconnected { user })
+                        .await;
+                }
+            }
+            for (user, err) in failures {
+                warn!("failed to reconnect {user:?}: {err:?}");
+                let status = &subscriptions.statuses()[&user];
+                // Alert once per streak, when it first exceeds the threshold.
+                if status.consecutive_failures != self.failure_alert_threshold + 1 {
+                    continue;
+                }
+                let content = format!(
+                    "Subscription for {user:?} has failed {} times in a row: {err}",
+                    status.consecutive_failures
+                );
+                self.shared
+                    .lifecycle
+                    .emit(lifecycle::Event::Degraded {
+                        reason: "subscription",
+                        detail: content.clone(),
+                    })
+                    .await;
+                self.shared
+                    .alerter
+                    .post_ops(Some(Priority::High), &content)
+                    .await;
+            }
+        }
+    }
+}
+
+/// Moves the info client and every subscription to another endpoint when the
+/// active one stops answering or goes quiet.
+pub(crate) struct Failover {
+    pub(crate) shared: Shared,
+    pub(crate) endpoints: Endpoints,
+    pub(crate) heartbeat: health::Heartbeat,
+    pub(crate) max_latency: Duration,
+    pub(crate) max_silence: Duration,
+    /// Where subscribed mids go, so they follow the client; `None` when they
+    /// are polled.
+    pub(crate) mids_sender: Option<UnboundedSender<Message>>,
+    pub(crate) mids_subscription: Arc<Mutex<Option<u32>>>,
+}
+
+impl Failover {
+    pub(crate) async fn run(self) {
+        loop {
+            sleep(Duration::from_secs(30)).await;
+
+            let active = self.endpoints.active().to_string();
+            let problem = match self.endpoints.probe(&active, self.max_latency).await {
+                None => format!(
+                    "it did not answer within {}ms",
+                    self.max_latency.as_millis()
+                ),
+                Some(_) => match self.heartbeat.age() {
+                    Some(age) if age > self.max_silence => {
+                        format!("no WebSocket messages for {}s", age.as_secs())
+                    }
+                    _ => continue,
+                },
+            };
+            let Some(next) = self.endpoints.fail_over(self.max_latency).await else {
+                warn!("staying on {active} although {problem}: no other endpoint answers");
+                continue;
+            };
+            warn!("failing over from {active} to {next}: {problem}");
+
+            match self.endpoints.client().await {
+                Ok(mut new_client) => {
+                    if let Some(mids_sender) = &self.mids_sender {
+                        match new_client
+                            .subscribe(Subscription::AllMids, mids_sender.clone())
+                            .await
+                        {
+                            Ok(subscription_id) => {
+                                *self.mids_subscription.lock().await = Some(subscription_id)
+                            }
+                            Err(err) => warn!("failed to subscribe all mids: {err:?}"),
+                        }
+                    }
+                    *self.shared.info_client.lock().await = new_client;
+                }
+                Err(err) => warn!("failed to create client for {next}: {err:?}"),
+            }
+            let (connector, users) = {
+                let subscriptions = self.shared.subscriptions.lock().await;
+                (subscriptions.connector(), subscriptions.users())
+            };
+            let reconnection = connector.connect(users).await;
+            let failures = self.shared.subscriptions.lock().await.swap_in(reconnection);
+            for (user, err) in &failures {
+                warn!("failed to resubscribe {user:?} on {next}: {err:?}");
+            }
+
+            let content = format!(
+                "Failed over from {active} to {next} because {problem}; {} subscriptions failed to move",
+                failures.len()
+            );
+            self.shared
+                .lifecycle
+                .emit(lifecycle::Event::Degraded {
+                    reason: "failover",
+                    detail: content.clone(),
+                })
+                .await;
+            self.shared
+                .alerter
+                .post_ops(Some(Priority::High), &content)
+                .await;
+        }
+    }
+}
+
+/// Keeps `mid_cache` current from the mids feed, which also beats `heartbeat`.
+pub(crate) async fn track_mids(
+    mut receiver: UnboundedReceiver<Message>,
+    mid_cache: Arc<Mutex<MidCache>>,
+    heartbeat: health::Heartbeat,
+) {
+    while let Some(message) = receiver.recv().await {
+        heartbeat.beat();
+        if let Message::AllMids(all_mids) = message {
+            mid_cache.lock().await.update(all_mids.data.mids);
+        }
+    }
+}
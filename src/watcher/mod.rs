@@ -0,0 +1,1029 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use ethers::types::H160;
+use hyperliquid_rust_sdk::{InfoClient, Subscription};
+use tokio::sync::mpsc::unbounded_channel;
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
+use tracing::{info, warn};
+
+use crate::config::{Config, Destination, WatcherConfig};
+use crate::context::{self, spawn};
+use crate::hyperliquid::fills::{Fill, UserEvent};
+use crate::hyperliquid::subscriptions::{self, SubscriptionManager};
+use crate::hyperliquid::{
+    capture, endpoints, events, order_updates, polling, positions, prices, spot,
+};
+use crate::notify::alerts::{self, Priority};
+use crate::notify::notifier::{
+    DiscordNotifier, EventKinds, JsonNotifier, Notifier, SlackNotifier, TelegramNotifier,
+    TradeEvent,
+};
+use crate::notify::{
+    apprise, discord, home_assistant, lifecycle, pacing, rabbitmq, retry, sheets, zmq,
+};
+use crate::{
+    api, correlation, costs, crypto, dedup, health, history, metrics, mutes, reconcile, restarts,
+    routes, sessions, side_label, storage, store, summary, velocity, watchlist,
+};
+
+mod accounts;
+mod batch;
+mod connections;
+mod reports;
+mod vaults;
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// What the background tasks share with the main loop.
+#[derive(Clone)]
+pub(crate) struct Shared {
+    pub(crate) client: reqwest::Client,
+    pub(crate) webhook_pool: discord::WebhookPool,
+    /// Heads messages; see `Watchlist::name`.
+    pub(crate) vault_name: String,
+    pub(crate) alerter: alerts::Alerter,
+    pub(crate) lifecycle: lifecycle::LifecycleWebhook,
+    pub(crate) info_client: Arc<Mutex<InfoClient>>,
+    pub(crate) subscriptions: Arc<Mutex<SubscriptionManager>>,
+    pub(crate) watchlist: Arc<Mutex<watchlist::Watchlist>>,
+    pub(crate) trackers: watchlist::Trackers,
+}
+
+/// Watches the configured vaults and addresses and notifies their fills.
+///
+/// Beyond the watch targets and any notifiers added here, everything is
+/// configured from the environment as described in the README, read up front
+/// by [`WatcherConfig::from_env`].
+pub struct Watcher {
+    config: Config,
+    config_path: Option<PathBuf>,
+    alert_destinations: Vec<apprise::Destination>,
+    trade_notifiers: Vec<Box<dyn Notifier>>,
+    metrics_prefix: Option<String>,
+    store: Option<Box<dyn storage::Store>>,
+    public_api: bool,
+}
+
+impl Watcher {
+    pub fn new(config: Config) -> Self {
+        Watcher {
+            config,
+            config_path: None,
+            alert_destinations: Vec::new(),
+            trade_notifiers: Vec::new(),
+            metrics_prefix: None,
+            store: None,
+            public_api: false,
+        }
+    }
+
+    /// Lets the watcher reload its watch targets from `path` on `SIGHUP` or
+    /// the `/reload` command.
+    pub fn with_config_path(mut self, path: PathBuf) -> Self {
+        self.config_path = Some(path);
+        self
+    }
+
+    /// Also watches `address`, prefixing its fills with `label`.
+    pub fn add_address(mut self, address: H160, label: Option<String>) -> Self {
+        self.config.addresses.push(crate::config::WatchedAddress {
+            address,
+            label,
+            destination: Destination::default(),
+        });
+        self
+    }
+
+    /// Also sends every batch of fills to `notifier`, after Discord and the
+    /// notifiers configured from the environment.
+    pub fn add_notifier(mut self, notifier: Box<dyn Notifier>) -> Self {
+        self.trade_notifiers.push(notifier);
+        self
+    }
+
+    /// Also sends every alert to `destination`, alongside `NOTIFY_URLS`.
+    pub fn add_alert_destination(mut self, destination: apprise::Destination) -> Self {
+        self.alert_destinations.push(destination);
+        self
+    }
+
+    /// Overrides `METRICS_PREFIX`.
+    pub fn with_metrics_prefix(mut self, prefix: String) -> Self {
+        self.metrics_prefix = Some(prefix);
+        self
+    }
+
+    /// Serves the HTTP API read-only for sharing with the vault's followers:
+    /// no commands, routes, subscriptions or metrics, and addresses shortened.
+    pub fn with_public_api(mut self) -> Self {
+        self.public_api = true;
+        self
+    }
+
+    /// Also saves every received fill to `store`, in place of the SQLite
+    /// database at `SQLITE_PATH`.
+    pub fn with_store(mut self, store: Box<dyn storage::Store>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Runs until a fatal error, with metrics, pacing and retries of its own.
+    pub async fn run(self) -> anyhow::Result<()> {
+        context::scope(Arc::new(context::Context::default()), self.watch()).await
+    }
+
+    async fn watch(self) -> anyhow::Result<()> {
+        let settings = WatcherConfig::from_env(&self.config.categories)?;
+        let startup = restarts::RestartLog::load(restarts::log_path())?
+            .record_start(settings.crash_loop_restarts)?;
+
+        info!("Initializing client...");
+        let network = self.config.network;
+        let endpoints = endpoints::Endpoints::from_env(network)?;
+        let endpoint_max_latency = settings.endpoint_max_latency;
+        // Start on a mirror when the primary is already down.
+        if endpoints.has_fallbacks()
+            && endpoints
+                .probe(endpoints.active(), endpoint_max_latency)
+                .await
+                .is_none()
+        {
+            match endpoints.fail_over(endpoint_max_latency).await {
+                Some(url) => info!("Starting on {url}"),
+                None => warn!("no endpoint answers, starting on {}", endpoints.active()),
+            }
+        }
+        let info_client = endpoints.client().await?;
+
+        let config = self.config;
+        let config_path = self.config_path;
+        // Reloads change what is watched; categories take a restart.
+        let categories = config.categories.clone();
+
+        let watchlist = watchlist::Watchlist::fetch(&info_client, config).await?;
+        // Messages are headed with the first vault's name; fills of the other
+        // sources carry their own label.
+        let vault_name = watchlist.name();
+
+        let metrics_prefix = self.metrics_prefix.unwrap_or(settings.metrics_prefix);
+        metrics::init(&metrics_prefix, &vault_name)?;
+
+        let mut spot_names = match spot::SpotNames::fetch(&info_client).await {
+            Ok(spot_names) => spot_names,
+            Err(err) => {
+                warn!("failed to fetch spot metadata: {err:?}");
+                spot::SpotNames::default()
+            }
+        };
+
+        let client = reqwest::Client::new();
+        let webhook_pool = discord::WebhookPool::new(settings.discord_webhook_urls)?;
+        let debug_webhook_url = settings.debug_webhook_url;
+        let lifecycle = lifecycle::LifecycleWebhook::from_env(client.clone(), vault_name.clone());
+        lifecycle
+            .emit(lifecycle::Event::Started {
+                version: env!("CARGO_PKG_VERSION"),
+                network,
+                config_hash: watchlist.config().hash()?,
+            })
+            .await;
+        // Ordinary starts are announced with the reconciliation report once
+        // subscribed; during a crash loop only the incident is posted, once.
+        let mut alerter =
+            alerts::Alerter::new(client.clone(), webhook_pool.clone(), vault_name.clone());
+        if let Some(webhook_url) = settings.ops_webhook_url {
+            alerter = alerter.with_ops_webhook(webhook_url);
+        }
+        let startup_notice = match startup {
+            restarts::Startup::Notice(content) => Some(content),
+            restarts::Startup::Incident(content) => {
+                alerter.post_ops(None, &content).await;
+                None
+            }
+            restarts::Startup::Silent => None,
+        };
+        let debug_client = client.clone();
+        let debug_vault_name = vault_name.clone();
+        pacing::configure(settings.sink_pacing, settings.sink_jitter);
+        retry::configure(settings.discord_max_attempts);
+        if let Some(priority) = settings.queue_jump_priority {
+            alerter = alerter.with_queue_jump(priority);
+        }
+        if let Some(priority) = settings.desktop_priority {
+            alerter = alerter.with_desktop(priority);
+        }
+        if let Some(priority) = settings.tts_priority {
+            alerter = alerter.with_tts(priority);
+        }
+        if let Some((priority, trigger)) = settings.trigger {
+            alerter = alerter.with_trigger(priority, trigger);
+        }
+        if let Some((server_url, app_token)) = settings.gotify {
+            alerter = alerter.with_gotify(server_url, app_token);
+        }
+        if let Some(sms) = settings.sms {
+            alerter = alerter.with_sms(sms);
+        }
+        let mut destinations = settings.alert_destinations;
+        destinations.extend(self.alert_destinations);
+        for destination in destinations {
+            alerter = match destination {
+                apprise::Destination::Discord { webhook_url } => alerter.with_webhook(webhook_url),
+                apprise::Destination::Gotify {
+                    server_url,
+                    app_token,
+                } => alerter.with_gotify(server_url, app_token),
+                apprise::Destination::Twilio {
+                    account_sid,
+                    auth_token,
+                    from,
+                    to,
+                } => alerter.with_sms(alerts::SmsConfig {
+                    account_sid,
+                    auth_token,
+                    from,
+                    to,
+                    daily_cap: settings.sms_daily_cap,
+                }),
+                apprise::Destination::Json { url, format } => alerter.with_json(url, format),
+                apprise::Destination::Ntfy { url } => alerter.with_ntfy(url),
+            };
+        }
+        let history = Arc::new(Mutex::new(history::TradeHistory::load(
+            history::path_from_env(),
+        )?));
+        let fill_store = Arc::new(Mutex::new(store::FillStore::open(
+            store::path_from_env(),
+            crypto::LineCipher::from_env()?,
+        )?));
+        let mut fill_database = match self.store {
+            Some(store) => Some(store),
+            None => storage::open_from_env()?,
+        };
+        let correlation_window = settings.correlation_window;
+        let mut correlations = correlation::CorrelationTracker::new(
+            correlation_window,
+            settings.correlation_min_addresses,
+        );
+        let velocity = Arc::new(Mutex::new(velocity::VelocityTracker::new(
+            settings.velocity_spike_per_minute,
+            settings.velocity_silence,
+        )));
+        let session_tracker = settings.session_idle.map(|idle| {
+            Arc::new(Mutex::new(sessions::SessionTracker::new(
+                idle,
+                settings.session_min_fills,
+            )))
+        });
+        let mutes = Arc::new(Mutex::new(mutes::Mutes::load(settings.mutes_path)?));
+        let routes = Arc::new(Mutex::new(
+            routes::Routes::load(settings.routes_path)?.with_categories(categories.clone()),
+        ));
+        let max_silence = settings.healthz_max_silence;
+        let bad_fill_bps = settings.bad_fill_bps;
+        let coin_filter = settings.coin_filter;
+        let alert_categories = settings.alert_categories;
+        let shared_dedup = dedup::SharedDedup::from_env().await?;
+        let mut recent_fills = dedup::RecentFills::from_env()?;
+        let position_poll_interval = settings.position_poll_interval;
+        let liquidation_mention = settings.liquidation_mention;
+        // Polling stands in for the WebSocket where it is blocked.
+        let poll_interval = settings.poll_interval;
+
+        info!("Subscribing user events...");
+        let (sender, mut receiver) = unbounded_channel();
+
+        let mut notifiers: Vec<Box<dyn Notifier>> = vec![Box::new(DiscordNotifier {
+            client: client.clone(),
+            pool: webhook_pool.clone(),
+            username: vault_name.clone(),
+        })];
+        if let Some(webhook_url) = settings.slack_webhook_url {
+            notifiers.push(Box::new(SlackNotifier {
+                client: client.clone(),
+                webhook_url,
+            }));
+        }
+        if let Some((bot_token, chat_id)) = settings.telegram {
+            notifiers.push(Box::new(TelegramNotifier {
+                client: client.clone(),
+                bot_token,
+                chat_id,
+            }));
+        }
+        if let Some(url) = settings.trade_json_webhook_url {
+            notifiers.push(Box::new(JsonNotifier {
+                client: client.clone(),
+                url,
+            }));
+        }
+        notifiers.extend(self.trade_notifiers);
+        let notifiers: Arc<Vec<(Box<dyn Notifier>, EventKinds)>> = Arc::new(
+            notifiers
+                .into_iter()
+                .map(|notifier| {
+                    let kinds = EventKinds::from_env(notifier.name())?;
+                    Ok((notifier, kinds))
+                })
+                .collect::<anyhow::Result<_>>()?,
+        );
+
+        let (order_sender, mut order_receiver) = unbounded_channel();
+        let heartbeat = health::Heartbeat::default();
+        let mut user_subscriptions = SubscriptionManager::new(endpoints.clone(), sender.clone())
+            .with_heartbeat(heartbeat.clone());
+        if poll_interval.is_some() {
+            user_subscriptions = user_subscriptions.polling();
+        }
+        if let Some((capture_dir, max_bytes, keep)) =
+            settings.capture.filter(|_| poll_interval.is_none())
+        {
+            let writer = capture::CaptureWriter::new(capture_dir, max_bytes, keep)?;
+            let (capture_sender, capture_receiver) = unbounded_channel();
+            spawn(writer.run(capture_receiver));
+            user_subscriptions = user_subscriptions.with_capture(capture_sender);
+        }
+        if notifiers.iter().any(|(_, kinds)| kinds.orders) {
+            if poll_interval.is_some() {
+                warn!("order updates need the websocket transport, leaving them out");
+            } else {
+                user_subscriptions = user_subscriptions.with_order_updates(order_sender);
+            }
+        }
+        for user in watchlist.users() {
+            if let Err(e) = user_subscriptions.subscribe(user).await {
+                warn!("failed to subscribe {user:?}: {e:?}");
+            }
+        }
+
+        // Fills made while the watcher was down never arrive over the socket.
+        let users = user_subscriptions.users();
+        let backfill =
+            reconcile::backfill(&info_client, &mut *fill_store.lock().await, &users).await;
+        if let Some(fill_database) = &mut fill_database {
+            if let Err(err) = fill_database.save_fills(&backfill.added) {
+                warn!("failed to save backfilled fills to the database: {err:?}");
+            }
+        }
+        if settings.missed_trades_summary {
+            if let Some(content) = backfill.render_missed() {
+                webhook_pool
+                    .post(&client, Some(&vault_name), &content)
+                    .await;
+            }
+        }
+
+        let retrying = user_subscriptions
+            .statuses()
+            .values()
+            .filter(|status| status.state == subscriptions::SubscriptionState::Retrying)
+            .count();
+        lifecycle
+            .emit(lifecycle::Event::Subscribed {
+                subscribed: users.len(),
+                retrying,
+                backfilled: backfill.added.len(),
+            })
+            .await;
+        if let Some(notice) = startup_notice {
+            let reconciliation = reconcile::Reconciliation {
+                subscribed: users.len(),
+                retrying,
+                backfilled: backfill.added.len(),
+                net_exposures: reconcile::net_exposures(&info_client, &users).await,
+                config_hash: watchlist.config().hash()?,
+            };
+            let content = format!("{notice}\n{}", reconciliation.render());
+            alerter.post_ops(None, &content).await;
+        }
+
+        let info_client = Arc::new(Mutex::new(info_client));
+        let subscriptions = Arc::new(Mutex::new(user_subscriptions));
+        if let Some(poll_interval) = poll_interval {
+            spawn(polling::poll_fills(
+                endpoints.clone(),
+                Arc::clone(&subscriptions),
+                sender.clone(),
+                poll_interval,
+            ));
+        }
+        let watchlist = Arc::new(Mutex::new(watchlist));
+        let position_tracker = Arc::new(Mutex::new(positions::PositionTracker::default()));
+        let fill_positions = Arc::new(Mutex::new(positions::FillPositions::default()));
+        let daily_pnl = Arc::new(Mutex::new(summary::DailyPnl::default()));
+        let trackers = watchlist::Trackers {
+            positions: Arc::clone(&position_tracker),
+            fill_positions: Arc::clone(&fill_positions),
+            velocity: Arc::clone(&velocity),
+            sessions: session_tracker.clone(),
+        };
+        let reloader = watchlist::Reloader {
+            path: config_path,
+            watchlist: Arc::clone(&watchlist),
+            info_client: Arc::clone(&info_client),
+            subscriptions: Arc::clone(&subscriptions),
+            trackers: trackers.clone(),
+            alerter: alerter.clone(),
+        };
+        #[cfg(unix)]
+        {
+            let reloader = reloader.clone();
+            let mut hangups =
+                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+            spawn(async move {
+                while hangups.recv().await.is_some() {
+                    info!("Reloading config...");
+                    reloader.reload_and_report().await;
+                }
+            });
+        }
+
+        if let Some(addr) = settings.http_listen_addr {
+            let state = api::ApiState {
+                mutes: Arc::clone(&mutes),
+                routes: Arc::clone(&routes),
+                subscriptions: Arc::clone(&subscriptions),
+                fill_store: Arc::clone(&fill_store),
+                vault_name: vault_name.clone(),
+                alerter: alerter.clone(),
+                relay_token: settings.relay_token,
+                reloader: reloader.clone(),
+                heartbeat: heartbeat.clone(),
+                max_silence,
+                explorer_url: network.explorer_url(),
+                public: self.public_api,
+                api_token: settings.api_token,
+            };
+            spawn(async move {
+                if let Err(err) = api::serve(addr, state).await {
+                    warn!("HTTP API stopped: {err:?}");
+                }
+            });
+        }
+
+        let shared = Shared {
+            client: client.clone(),
+            webhook_pool: webhook_pool.clone(),
+            vault_name: vault_name.clone(),
+            alerter: alerter.clone(),
+            lifecycle: lifecycle.clone(),
+            info_client: Arc::clone(&info_client),
+            subscriptions: Arc::clone(&subscriptions),
+            watchlist: Arc::clone(&watchlist),
+            trackers,
+        };
+
+        spawn(
+            connections::Reconnector {
+                shared: shared.clone(),
+                max_silence: settings.subscription_max_silence,
+                failure_alert_threshold: settings.subscribe_failure_alert_threshold,
+            }
+            .run(),
+        );
+
+        if let Some(latency_budget) = settings.latency_budget {
+            spawn(reports::report_latency_budget(
+                shared.clone(),
+                latency_budget,
+            ));
+        }
+
+        let cost_ledger = Arc::new(Mutex::new(costs::CostLedger::default()));
+        spawn(
+            accounts::FundingPoller {
+                shared: shared.clone(),
+                cost_ledger: Arc::clone(&cost_ledger),
+                summary: settings.funding_summary,
+            }
+            .run(),
+        );
+
+        let flow_vaults: Vec<(H160, String)> = {
+            let watchlist = watchlist.lock().await;
+            watchlist
+                .vaults()
+                .into_iter()
+                .map(|vault| (vault, watchlist.vault_name(vault).to_string()))
+                .collect()
+        };
+        for (vault, name) in flow_vaults {
+            spawn(
+                vaults::VaultFlows {
+                    shared: shared.clone(),
+                    vault,
+                    name,
+                    cost_ledger: Arc::clone(&cost_ledger),
+                    interval: position_poll_interval,
+                    schedule: settings.flows_schedule,
+                    withdrawal_alert_usd: settings.vault_withdrawal_alert_usd,
+                }
+                .run(),
+            );
+        }
+
+        spawn(
+            vaults::VaultRefresher {
+                shared: shared.clone(),
+                interval: settings.vault_refresh_interval,
+                leader_stake_alert_pct: settings.leader_stake_alert_pct,
+            }
+            .run(),
+        );
+
+        if let Some(digest_schedule) = settings.digest_schedule {
+            spawn(
+                reports::Digest {
+                    shared: shared.clone(),
+                    fill_store: Arc::clone(&fill_store),
+                    daily_pnl: Arc::clone(&daily_pnl),
+                    schedule: digest_schedule,
+                }
+                .run(),
+            );
+        }
+
+        if !settings.compare_vaults.is_empty() || settings.benchmark_coin.is_some() {
+            let vaults: Vec<H160> = watchlist
+                .lock()
+                .await
+                .vaults()
+                .into_iter()
+                .chain(settings.compare_vaults)
+                .collect();
+            spawn(
+                vaults::VaultComparison {
+                    shared: shared.clone(),
+                    vaults,
+                    benchmark_coin: settings.benchmark_coin,
+                    cost_ledger: Arc::clone(&cost_ledger),
+                    schedule: settings.comparison_schedule,
+                }
+                .run(),
+            );
+        }
+
+        spawn(
+            accounts::PositionMonitor {
+                shared: shared.clone(),
+                tracker: Arc::clone(&position_tracker),
+                daily_pnl: Arc::clone(&daily_pnl),
+                interval: position_poll_interval,
+                equity_alert_below: settings.equity_alert_below,
+                equity_alert_above: settings.equity_alert_above,
+                liquidation_alert_pct: settings.liquidation_alert_pct,
+                liquidation_mention: liquidation_mention.clone(),
+                concentration_alert_pct: settings.concentration_alert_pct,
+                delta_alert_usd: settings.delta_alert_usd,
+            }
+            .run(),
+        );
+        spawn(
+            accounts::LedgerAlerts {
+                shared: shared.clone(),
+                interval: position_poll_interval,
+                threshold_usd: settings.ledger_alert_threshold_usd,
+                liquidation_mention,
+            }
+            .run(),
+        );
+
+        let trades: Arc<Mutex<Vec<(H160, Fill)>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let (event_sender, _) = broadcast::channel::<events::FillEvent>(1024);
+        if let Some((uri, exchange)) = settings.rabbitmq {
+            spawn(rabbitmq::publish(uri, exchange, event_sender.subscribe()));
+        }
+        if let Some((base_url, auth)) = settings.home_assistant {
+            spawn(home_assistant::publish(
+                client.clone(),
+                base_url,
+                auth,
+                event_sender.subscribe(),
+            ));
+        }
+        if let Some(endpoint) = settings.zmq_pub_endpoint {
+            let events = event_sender.subscribe();
+            spawn(async move {
+                if let Err(err) = zmq::publish(endpoint, events).await {
+                    warn!("zeromq publisher stopped: {err:?}");
+                }
+            });
+        }
+
+        let mut sheet_fills = None;
+        if let Some((service_account_path, spreadsheet_id, range)) = settings.google_sheets {
+            let appender = sheets::SheetsAppender::new(
+                client.clone(),
+                &service_account_path,
+                spreadsheet_id,
+                range,
+            )?;
+            let fills: Arc<Mutex<Vec<(H160, Fill)>>> = Arc::new(Mutex::new(Vec::new()));
+            sheet_fills = Some(Arc::clone(&fills));
+            spawn(appender.run(fills));
+        }
+
+        let mid_cache = Arc::new(Mutex::new(prices::MidCache::new(settings.mid_cache_ttl)));
+
+        let (mids_sender, mids_receiver) = unbounded_channel();
+        let mids_subscription = Arc::new(Mutex::new(None));
+        // Polled mids follow the shared client; subscribed ones are taken
+        // along on a failover.
+        let mids_resubscribe = poll_interval.is_none().then(|| mids_sender.clone());
+        if let Some(poll_interval) = poll_interval {
+            spawn(polling::poll_mids(
+                Arc::clone(&info_client),
+                mids_sender,
+                poll_interval,
+            ));
+        } else {
+            let subscribed = info_client
+                .lock()
+                .await
+                .subscribe(Subscription::AllMids, mids_sender)
+                .await;
+            match subscribed {
+                Ok(subscription_id) => *mids_subscription.lock().await = Some(subscription_id),
+                Err(err) => warn!("failed to subscribe all mids: {err:?}"),
+            }
+        }
+
+        if endpoints.has_fallbacks() {
+            spawn(
+                connections::Failover {
+                    shared: shared.clone(),
+                    endpoints: endpoints.clone(),
+                    heartbeat: heartbeat.clone(),
+                    max_latency: endpoint_max_latency,
+                    max_silence,
+                    mids_sender: mids_resubscribe,
+                    mids_subscription: Arc::clone(&mids_subscription),
+                }
+                .run(),
+            );
+        }
+
+        spawn(connections::track_mids(
+            mids_receiver,
+            Arc::clone(&mid_cache),
+            heartbeat.clone(),
+        ));
+
+        if let Some(live_summary_interval) = settings.live_summary_interval {
+            spawn(
+                reports::LiveSummary {
+                    shared: shared.clone(),
+                    position_tracker: Arc::clone(&position_tracker),
+                    daily_pnl: Arc::clone(&daily_pnl),
+                    interval: live_summary_interval,
+                    bot_token: settings.discord_bot_token,
+                }
+                .run(),
+            );
+        }
+
+        let mut open_orders = order_updates::OpenOrders::default();
+        let order_notifiers = Arc::clone(&notifiers);
+        let order_vault_name = vault_name.clone();
+
+        let session_client = client.clone();
+        let session_pool = webhook_pool.clone();
+        let session_vault_name = vault_name.clone();
+        if let Some(session_tracker) = &session_tracker {
+            spawn(reports::post_ended_sessions(
+                shared.clone(),
+                Arc::clone(session_tracker),
+            ));
+        }
+
+        {
+            let users = watchlist.lock().await.users();
+            let info_client = info_client.lock().await;
+            let mut fill_positions = fill_positions.lock().await;
+            for user in users {
+                match info_client.user_state(user).await {
+                    Ok(state) => fill_positions.seed(user, &state),
+                    Err(err) => warn!("failed to seed positions of {user:?}: {err:?}"),
+                }
+            }
+        }
+
+        // On shutdown the batch loop posts what it holds once more, then
+        // drops the sender it was given.
+        let (flush_sender, flush_receiver) = mpsc::channel::<oneshot::Sender<()>>(1);
+        spawn(
+            batch::Batcher {
+                shared: shared.clone(),
+                trades: Arc::clone(&trades),
+                flush_receiver,
+                notifiers,
+                position_tracker: Arc::clone(&position_tracker),
+                routes: Arc::clone(&routes),
+                fill_positions,
+                batch_interval: settings.batch_interval,
+                aggregation: settings.aggregation,
+                adaptive_batching: settings.adaptive_batching,
+                shared_dedup,
+                backpressure: settings.backpressure,
+                notional_filter: settings.notional_filter,
+                order_summaries: settings.order_summaries,
+                verbose: settings.verbose,
+                book_depth_min_usd: settings.book_depth_min_usd,
+                public_base_url: settings.public_base_url,
+            }
+            .run(),
+        );
+
+        spawn(reports::alert_silent_users(shared, Arc::clone(&velocity)));
+
+        let trades_arc = Arc::clone(&trades);
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+        loop {
+            let received = tokio::select! {
+                received = receiver.recv() => received,
+                Some((address, mut update)) = order_receiver.recv() => {
+                    update.order.coin = spot_names.resolve(&update.order.coin);
+                    let coin = update.order.coin.clone();
+                    if mutes.lock().await.is_muted(&coin) || !coin_filter.allows(&coin) {
+                        continue;
+                    }
+                    let Some(change) = open_orders.classify(address, &update) else {
+                        continue;
+                    };
+                    let label = watchlist.lock().await.label(address).map(str::to_string);
+                    let coin_route = routes.lock().await.get(&coin).map(str::to_string);
+                    let route = watchlist
+                        .lock()
+                        .await
+                        .destination(address)
+                        .or_webhook(coin_route.as_deref());
+                    let event = TradeEvent {
+                        title: order_vault_name.clone(),
+                        lines: Vec::new(),
+                        notes: vec![order_updates::render(&change, &update, label.as_deref())],
+                        fills: Vec::new(),
+                        labels: BTreeMap::new(),
+                        route,
+                    };
+                    let notifiers = Arc::clone(&order_notifiers);
+                    spawn(async move {
+                        for (notifier, kinds) in notifiers.iter() {
+                            if kinds.orders {
+                                notifier.send(&event).await;
+                            }
+                        }
+                    });
+                    continue;
+                }
+                result = &mut shutdown => {
+                    result?;
+                    break;
+                }
+            };
+            let Some((address, message)) = received else {
+                continue;
+            };
+            let mut fills = match message {
+                UserEvent::Fills(fills) => fills,
+                UserEvent::Unparsed(raw) => {
+                    warn!("unparsed fills for {address:?}: {raw}");
+                    if let Some(debug_webhook_url) = &debug_webhook_url {
+                        let raw: String = raw.chars().take(1800).collect();
+                        let content = format!("Unparsed fills for {address:?}:\n```{raw}```");
+                        discord::post(
+                            &debug_client,
+                            debug_webhook_url,
+                            Some(&debug_vault_name),
+                            &content,
+                        )
+                        .await;
+                    }
+                    continue;
+                }
+            };
+            let received_fills = fills.len();
+            fills.retain(|fill| recent_fills.insert(store::StoredFill::new(address, fill).id()));
+            if fills.len() < received_fills {
+                info!(
+                    "Dropped {} duplicate fills of {address:?}",
+                    received_fills - fills.len()
+                );
+            }
+            if fills.is_empty() {
+                continue;
+            }
+            if fills.iter().any(|fill| spot_names.is_unknown(&fill.coin)) {
+                let info_client = info_client.lock().await;
+                match spot::SpotNames::fetch(&info_client).await {
+                    Ok(fetched) => spot_names = fetched,
+                    Err(err) => warn!("failed to refresh spot metadata: {err:?}"),
+                }
+            }
+            for fill in fills.iter_mut() {
+                fill.coin = spot_names.resolve(&fill.coin);
+            }
+
+            // Paging through an address's fills takes a while, so it happens
+            // beside the receive loop.
+            if history.lock().await.start_seeding(address) {
+                let history = Arc::clone(&history);
+                let info_client = Arc::clone(&info_client);
+                let spot_names = spot_names.clone();
+                spawn(async move {
+                    let coins = {
+                        let info_client = info_client.lock().await;
+                        history::fetch_traded_coins(&info_client, address).await
+                    };
+                    let mut history = history.lock().await;
+                    let seeded = match coins {
+                        Ok(coins) => {
+                            history.seed(address, coins.iter().map(|coin| spot_names.resolve(coin)))
+                        }
+                        Err(err) => {
+                            history.seed_failed(address);
+                            Err(err)
+                        }
+                    };
+                    if let Err(err) = seeded {
+                        warn!("failed to seed trade history of {address:?}: {err:?}");
+                    }
+                });
+            }
+            let mut daily_pnl_guard = daily_pnl.lock().await;
+            let mut cost_ledger_guard = cost_ledger.lock().await;
+            for fill in fills.iter() {
+                daily_pnl_guard.record(fill.closed_pnl.parse().unwrap_or_default());
+                cost_ledger_guard.record_fee(
+                    address,
+                    &fill.coin,
+                    fill.time,
+                    fill.fee.parse().unwrap_or_default(),
+                );
+                metrics::record_fill(address, &fill.coin);
+            }
+            drop(cost_ledger_guard);
+            drop(daily_pnl_guard);
+
+            let stored: Vec<store::StoredFill> = fills
+                .iter()
+                .map(|fill| store::StoredFill::new(address, fill))
+                .collect();
+            if let Err(err) = fill_store.lock().await.append(&stored) {
+                warn!("failed to store fills of {address:?}: {err:?}");
+            }
+            if let Some(fill_database) = &mut fill_database {
+                if let Err(err) = fill_database.save_fills(&stored) {
+                    warn!("failed to save fills of {address:?} to the database: {err:?}");
+                }
+            }
+
+            let mut history = history.lock().await;
+            let mutes = mutes.lock().await;
+            // Filtered coins are silenced like muted ones, before anything is queued.
+            let muted = |coin: &str| mutes.is_muted(coin) || !coin_filter.allows(coin);
+            for fill in fills.iter() {
+                let muted = muted(&fill.coin);
+                match history.record(address, &fill.coin) {
+                    Ok(true) if !muted => alerter.spawn(
+                        Priority::Normal,
+                        format!(
+                            "New market: {address:?} traded {} for the first time",
+                            fill.coin
+                        ),
+                    ),
+                    Ok(_) => (),
+                    Err(err) => warn!("failed to record trade history: {err:?}"),
+                }
+
+                if let Some(category) = categories.of(&fill.coin).filter(|category| {
+                    !muted
+                        && alert_categories
+                            .iter()
+                            .any(|name| name.eq_ignore_ascii_case(category))
+                }) {
+                    alerter.spawn(
+                        Priority::High,
+                        format!(
+                            "{category} fill: {address:?} {} {} {} @ {}",
+                            side_label(&fill.side),
+                            fill.sz,
+                            fill.coin,
+                            fill.px
+                        ),
+                    );
+                }
+
+                if let Some(session_tracker) = session_tracker.as_ref().filter(|_| !muted) {
+                    if let Some(session) = session_tracker.lock().await.record(address, fill) {
+                        let content = session.render_started();
+                        let client = session_client.clone();
+                        let pool = session_pool.clone();
+                        let username = session_vault_name.clone();
+                        spawn(async move {
+                            pool.post(&client, Some(&username), &content).await;
+                        });
+                    }
+                }
+
+                if let Some(rate) = velocity.lock().await.record(address) {
+                    alerter.spawn(
+                        Priority::Normal,
+                        format!("Activity spike: {address:?} at {rate} fills per minute"),
+                    );
+                }
+
+                if let Ok(px) = fill.px.parse::<f64>() {
+                    let mid = {
+                        let info_client = info_client.lock().await;
+                        mid_cache.lock().await.get(&info_client, &fill.coin).await
+                    };
+                    if let Some(mid) = mid.filter(|_| !muted) {
+                        let bps = prices::bps_through_mid(&fill.side, px, mid);
+                        if bps >= bad_fill_bps {
+                            alerter.spawn(Priority::Normal, format!(
+                                    "Bad fill: {address:?} {} {} {} @ {}, {bps:.0} bps through mid {mid}",
+                                    side_label(&fill.side),
+                                    fill.sz,
+                                    fill.coin,
+                                    fill.px
+                                ));
+                        }
+                    }
+                }
+
+                let correlated = correlations.record(address, &fill.coin, &fill.side);
+                if let Some(addresses) = correlated.filter(|_| !muted) {
+                    alerter.spawn(
+                        Priority::High,
+                        format!(
+                            "Correlated flow: {} addresses {} {} within {}s: {:?}",
+                            addresses.len(),
+                            side_label(&fill.side),
+                            fill.coin,
+                            correlation_window.as_secs(),
+                            addresses
+                        ),
+                    );
+                }
+            }
+
+            if event_sender.receiver_count() > 0 {
+                for fill in fills.iter() {
+                    if !muted(&fill.coin) {
+                        let _ = event_sender.send(events::FillEvent::new(address, fill));
+                    }
+                }
+            }
+            if let Some(sheet_fills) = &sheet_fills {
+                sheet_fills.lock().await.extend(
+                    fills
+                        .iter()
+                        .filter(|fill| !muted(&fill.coin))
+                        .map(|fill| (address, fill.clone())),
+                );
+            }
+
+            let mut trades = trades_arc.lock().await;
+            trades.extend(
+                fills
+                    .into_iter()
+                    .filter(|fill| !muted(&fill.coin))
+                    .map(|fill| (address, fill)),
+            );
+        }
+
+        info!("Shutting down...");
+        subscriptions.lock().await.unsubscribe_all();
+        if let Some(subscription_id) = *mids_subscription.lock().await {
+            if let Err(err) = info_client.lock().await.unsubscribe(subscription_id).await {
+                warn!("failed to unsubscribe all mids: {err:?}");
+            }
+        }
+        let (flushed_sender, flushed_receiver) = oneshot::channel();
+        if flush_sender.send(flushed_sender).await.is_ok() {
+            let _ = flushed_receiver.await;
+        }
+        info!("Posted the pending fills, exiting");
+        lifecycle.emit(lifecycle::Event::Shutdown).await;
+        Ok(())
+    }
+}
+
+/// Resolves on SIGINT, or SIGTERM on Unix.
+async fn shutdown_signal() -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result,
+            _ = terminate.recv() => Ok(()),
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await
+}
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use ethers::types::H160;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+use super::{Shared, DAY_MS};
+use crate::hyperliquid::positions;
+use crate::notify::alerts::Priority;
+use crate::notify::{discord, lifecycle};
+use crate::{digest, now_ms, schedule, sessions, slo, store, summary, velocity};
+
+/// Posts the last day's fills on `schedule`.
+pub(crate) struct Digest {
+    pub(crate) shared: Shared,
+    pub(crate) fill_store: Arc<Mutex<store::FillStore>>,
+    pub(crate) daily_pnl: Arc<Mutex<summary::DailyPnl>>,
+    pub(crate) schedule: schedule::DailySchedule,
+}
+
+impl Digest {
+    pub(crate) async fn run(self) {
+        let vault_name = &self.shared.vault_name;
+        loop {
+            let next_digest = self.schedule.next_after(Utc::now());
+            let wait = (next_digest - Utc::now()).to_std().unwrap_or_default();
+            sleep(wait).await;
+
+            let since = now_ms().saturating_sub(DAY_MS);
+            let fills: Vec<store::StoredFill> = self
+                .fill_store
+                .lock()
+                .await
+                .fills()
+                .iter()
+                .filter(|fill| fill.time >= since)
+                .cloned()
+                .collect();
+            let (labels, clusters) = {
+                let watchlist = self.shared.watchlist.lock().await;
+                let labels: BTreeMap<H160, String> = watchlist
+                    .users()
+                    .into_iter()
+                    .filter_map(|user| Some((user, watchlist.label(user)?.to_string())))
+                    .collect();
+                (labels, watchlist.config().clusters.clone())
+            };
+            let unrealized_change = self.daily_pnl.lock().await.unrealized_since_digest();
+            let content = digest::render(vault_name, &fills, &labels, &clusters, unrealized_change);
+            self.shared
+                .webhook_pool
+                .post(&self.shared.client, Some(vault_name), &content)
+                .await;
+        }
+    }
+}
+
+/// Keeps one message with the open positions and the day's PnL up to date,
+/// pinned when a bot token is set.
+pub(crate) struct LiveSummary {
+    pub(crate) shared: Shared,
+    pub(crate) position_tracker: Arc<Mutex<positions::PositionTracker>>,
+    pub(crate) daily_pnl: Arc<Mutex<summary::DailyPnl>>,
+    pub(crate) interval: Duration,
+    pub(crate) bot_token: Option<String>,
+}
+
+impl LiveSummary {
+    pub(crate) async fn run(self) {
+        let client = &self.shared.client;
+        let vault_name = &self.shared.vault_name;
+        let mut message: Option<(String, String)> = None;
+        loop {
+            let content = summary::render(
+                vault_name,
+                &*self.position_tracker.lock().await,
+                &mut *self.daily_pnl.lock().await,
+            );
+
+            match &message {
+                Some((webhook_url, message_id)) => {
+                    discord::edit(client, webhook_url, message_id, &content).await
+                }
+                None => {
+                    let posted = self
+                        .shared
+                        .webhook_pool
+                        .post_with_id(client, Some(vault_name), &content)
+                        .await;
+                    if let Some((webhook_url, posted)) = posted {
+                        if let Some(bot_token) = &self.bot_token {
+                            discord::pin(client, bot_token, &posted.channel_id, &posted.id).await;
+                        }
+                        message = Some((webhook_url, posted.id));
+                    }
+                }
+            }
+
+            sleep(self.interval).await;
+        }
+    }
+}
+
+/// Posts the trading sessions that went idle, checking every minute.
+pub(crate) async fn post_ended_sessions(
+    shared: Shared,
+    session_tracker: Arc<Mutex<sessions::SessionTracker>>,
+) {
+    loop {
+        sleep(Duration::from_secs(60)).await;
+
+        let ended = session_tracker.lock().await.ended();
+        for session in ended {
+            shared
+                .webhook_pool
+                .post(
+                    &shared.client,
+                    Some(&shared.vault_name),
+                    &session.render_ended(),
+                )
+                .await;
+        }
+    }
+}
+
+/// Alerts on addresses that stopped trading, checking every minute.
+pub(crate) async fn alert_silent_users(
+    shared: Shared,
+    velocity: Arc<Mutex<velocity::VelocityTracker>>,
+) {
+    loop {
+        sleep(Duration::from_secs(60)).await;
+
+        let silent_users = velocity.lock().await.silent_users();
+        for (user, elapsed) in silent_users {
+            shared.alerter.spawn(
+                Priority::Normal,
+                format!(
+                    "Gone quiet: {user:?} has not traded for {} minutes",
+                    elapsed.as_secs() / 60
+                ),
+            );
+        }
+    }
+}
+
+/// Reports when the notification latency budget is breached or recovers,
+/// checking every minute.
+pub(crate) async fn report_latency_budget(shared: Shared, mut latency_budget: slo::LatencyBudget) {
+    loop {
+        sleep(Duration::from_secs(60)).await;
+
+        let content = match latency_budget.check() {
+            Some(slo::BudgetChange::Breached {
+                compliance_pct,
+                fills,
+            }) => {
+                let content = format!(
+                    "Latency SLO breached: {compliance_pct:.1}% of {fills} recent fills notified within {}s (target {}%)",
+                    latency_budget.threshold_secs(),
+                    latency_budget.target_pct()
+                );
+                shared
+                    .lifecycle
+                    .emit(lifecycle::Event::Degraded {
+                        reason: "latency_slo",
+                        detail: content.clone(),
+                    })
+                    .await;
+                content
+            }
+            Some(slo::BudgetChange::Recovered { compliance_pct }) => format!(
+                "Latency SLO recovered: {compliance_pct:.1}% of recent fills notified within {}s",
+                latency_budget.threshold_secs()
+            ),
+            None => continue,
+        };
+        shared
+            .alerter
+            .post_ops(Some(Priority::High), &content)
+            .await;
+    }
+}
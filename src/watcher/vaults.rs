@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use ethers::types::H160;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use super::{Shared, DAY_MS};
+use crate::hyperliquid::{ledger, vault};
+use crate::notify::alerts::Priority;
+use crate::{comparison, costs, now_ms, schedule};
+
+/// Follows the ledger of one vault, alerting on withdrawals of at least
+/// `withdrawal_alert_usd` and reporting its flows on `schedule`.
+pub(crate) struct VaultFlows {
+    pub(crate) shared: Shared,
+    pub(crate) vault: H160,
+    pub(crate) name: String,
+    pub(crate) cost_ledger: Arc<Mutex<costs::CostLedger>>,
+    pub(crate) interval: Duration,
+    pub(crate) schedule: schedule::DailySchedule,
+    pub(crate) withdrawal_alert_usd: f64,
+}
+
+impl VaultFlows {
+    pub(crate) async fn run(self) {
+        let name = &self.name;
+        let mut flows = ledger::VaultFlows::default();
+        let mut next_flows_report = self.schedule.next_after(Utc::now());
+        let mut last_polled_ms = now_ms();
+        loop {
+            sleep(self.interval).await;
+
+            let updates = {
+                let info_client = self.shared.info_client.lock().await;
+                ledger::fetch_updates(&info_client, self.vault, last_polled_ms).await
+            };
+            match updates {
+                Ok(updates) => {
+                    for update in updates.iter() {
+                        flows.record(update);
+                        last_polled_ms = last_polled_ms.max(update.time + 1);
+
+                        if update.delta.type_ == "vaultWithdraw"
+                            && update.usdc() >= self.withdrawal_alert_usd
+                        {
+                            let content = format!(
+                                "Large vault withdrawal from {name}: ${:.2}",
+                                update.usdc()
+                            );
+                            self.shared.alerter.send(Priority::High, &content).await;
+                        }
+                    }
+                }
+                Err(err) => warn!("failed to fetch vault ledger updates: {err:?}"),
+            }
+
+            if Utc::now() >= next_flows_report {
+                let users: Vec<H160> = {
+                    let watchlist = self.shared.watchlist.lock().await;
+                    watchlist
+                        .users()
+                        .into_iter()
+                        .filter(|user| watchlist.vault_of(*user) == Some(self.vault))
+                        .collect()
+                };
+                let costs = self
+                    .cost_ledger
+                    .lock()
+                    .await
+                    .since(now_ms().saturating_sub(DAY_MS), Some(&users));
+                let content = format!(
+                    "**{name} daily flows**\nDeposits: ${:.2}\nWithdrawals: ${:.2}\nNet: ${:.2}\n{}",
+                    flows.deposits,
+                    flows.withdrawals,
+                    flows.net(),
+                    costs.render()
+                );
+                self.shared.alerter.send(Priority::Low, &content).await;
+                flows = ledger::VaultFlows::default();
+                next_flows_report = self.schedule.next_after(Utc::now());
+            }
+        }
+    }
+}
+
+/// Re-reads the watched vaults every `interval`, following their child
+/// addresses and alerting when a leader's stake drops below
+/// `leader_stake_alert_pct`.
+pub(crate) struct VaultRefresher {
+    pub(crate) shared: Shared,
+    pub(crate) interval: Duration,
+    pub(crate) leader_stake_alert_pct: Option<f64>,
+}
+
+impl VaultRefresher {
+    pub(crate) async fn run(self) {
+        let mut leader_stakes: HashMap<H160, f64> = HashMap::new();
+        loop {
+            sleep(self.interval).await;
+
+            let watched_vaults: Vec<(H160, String)> = {
+                let watchlist = self.shared.watchlist.lock().await;
+                watchlist
+                    .vaults()
+                    .into_iter()
+                    .map(|vault| (vault, watchlist.vault_name(vault).to_string()))
+                    .collect()
+            };
+            let info_client = self.shared.info_client.lock().await;
+            let mut refreshed: HashMap<H160, H160> = HashMap::new();
+            let mut complete = true;
+            for (vault, name) in &watched_vaults {
+                let vault_details = match vault::VaultApi::new(&info_client)
+                    .details(&format!("{vault:?}"))
+                    .await
+                {
+                    Ok(vault_details) => vault_details,
+                    Err(err) => {
+                        warn!("failed to refresh vault details: {err:?}");
+                        complete = false;
+                        continue;
+                    }
+                };
+
+                let previous_stake_pct = leader_stakes.get(vault).copied();
+                let leader_stake_pct = vault_details.leader_stake_pct();
+                if let Some(stake_pct) = leader_stake_pct {
+                    leader_stakes.insert(*vault, stake_pct);
+                }
+                if let (Some(threshold), Some(stake_pct)) =
+                    (self.leader_stake_alert_pct, leader_stake_pct)
+                {
+                    if stake_pct < threshold
+                        && previous_stake_pct.is_none_or(|pct| pct >= threshold)
+                    {
+                        let content = format!(
+                            "Leader stake alert: {name} leader {:?} holds {stake_pct:.2}% of ${:.2} TVL, below {threshold:.2}%",
+                            vault_details.leader,
+                            vault_details.tvl().unwrap_or_default()
+                        );
+                        self.shared.alerter.send(Priority::High, &content).await;
+                    }
+                }
+                for child in vault_details.child_addresses {
+                    refreshed.insert(child, *vault);
+                }
+            }
+            drop(info_client);
+
+            // A vault that failed to refresh would look like it lost every child.
+            if !complete {
+                continue;
+            }
+
+            let mut notices = Vec::new();
+            let (connector, removed_users, added_users) = {
+                let mut subscriptions = self.shared.subscriptions.lock().await;
+                let watchlist = self.shared.watchlist.lock().await;
+                let removed_users: Vec<H160> = subscriptions
+                    .users()
+                    .into_iter()
+                    .filter(|user| !refreshed.contains_key(user) && !watchlist.is_listed(*user))
+                    .collect();
+
+                for &user in &removed_users {
+                    subscriptions.unsubscribe(user);
+
+                    info!("Removed child address {user:?}");
+                    let vault_name = match watchlist.vault_of(user) {
+                        Some(vault) => watchlist.vault_name(vault),
+                        None => &self.shared.vault_name,
+                    };
+                    notices.push(format!(
+                        "Child address {user:?} was removed from {vault_name}"
+                    ));
+                }
+
+                let watched = subscriptions.users();
+                let added_users: Vec<H160> = refreshed
+                    .keys()
+                    .filter(|user| !watched.contains(user) && !watchlist.is_excluded(**user))
+                    .copied()
+                    .collect();
+                (subscriptions.connector(), removed_users, added_users)
+            };
+
+            // Connecting can take a while, so it happens without the locks.
+            let reconnection = connector.connect(added_users.clone()).await;
+            let failures = self
+                .shared
+                .subscriptions
+                .lock()
+                .await
+                .add(&added_users, reconnection);
+            for (user, err) in failures {
+                warn!("failed to subscribe {user:?}: {err:?}");
+            }
+            for user in removed_users {
+                self.shared.trackers.forget(user).await;
+            }
+
+            let mut watchlist = self.shared.watchlist.lock().await;
+            for user in added_users {
+                info!("Added child address {user:?}");
+                notices.push(format!(
+                    "Child address {user:?} joined {}",
+                    watchlist.vault_name(refreshed[&user])
+                ));
+            }
+            watchlist.set_children(refreshed);
+            drop(watchlist);
+            for content in notices {
+                self.shared.alerter.post_ops_only(&content).await;
+            }
+        }
+    }
+}
+
+/// Compares `vaults`, and `benchmark_coin` when set, with the week's costs on
+/// `schedule`.
+pub(crate) struct VaultComparison {
+    pub(crate) shared: Shared,
+    pub(crate) vaults: Vec<H160>,
+    pub(crate) benchmark_coin: Option<String>,
+    pub(crate) cost_ledger: Arc<Mutex<costs::CostLedger>>,
+    pub(crate) schedule: schedule::WeeklySchedule,
+}
+
+impl VaultComparison {
+    pub(crate) async fn run(self) {
+        loop {
+            let next_report = self.schedule.next_after(Utc::now());
+            let wait = (next_report - Utc::now()).to_std().unwrap_or_default();
+            sleep(wait).await;
+
+            let info_client = self.shared.info_client.lock().await;
+            let mut stats = Vec::new();
+            for vault in &self.vaults {
+                let address = format!("{vault:?}");
+                match vault::VaultApi::new(&info_client).details(&address).await {
+                    Ok(details) => {
+                        stats.push(comparison::stats(&info_client, *vault, &details).await)
+                    }
+                    Err(err) => {
+                        warn!("failed to fetch vault {address} for comparison: {err:?}")
+                    }
+                }
+            }
+            let benchmark = match &self.benchmark_coin {
+                Some(coin) => match comparison::benchmark(&info_client, coin).await {
+                    Ok(benchmark) => Some(benchmark),
+                    Err(err) => {
+                        warn!("failed to compute the {coin} benchmark: {err:?}");
+                        None
+                    }
+                },
+                None => None,
+            };
+            drop(info_client);
+
+            if !stats.is_empty() {
+                let costs = self
+                    .cost_ledger
+                    .lock()
+                    .await
+                    .since(now_ms().saturating_sub(7 * DAY_MS), None);
+                let content = format!(
+                    "{}\nWatched addresses this week\n{}",
+                    comparison::render(stats, benchmark.as_ref()),
+                    costs.render()
+                );
+                self.shared.alerter.send(Priority::Low, &content).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use ethers::types::H160;
+use hyperliquid_rust_sdk::InfoClient;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::config::{Config, Destination};
+use crate::hyperliquid::positions::{FillPositions, PositionTracker};
+use crate::hyperliquid::subscriptions::SubscriptionManager;
+use crate::hyperliquid::vault;
+use crate::notify::alerts::{Alerter, Priority};
+use crate::sessions::SessionTracker;
+use crate::velocity::VelocityTracker;
+
+/// The configured vaults and addresses, with the vaults' current children and
+/// the names fills are labelled with.
+pub struct Watchlist {
+    config: Config,
+    vault_names: HashMap<H160, String>,
+    child_vaults: HashMap<H160, H160>,
+    /// `EXCLUDED_ADDRESSES`, left out of `users` wherever they come from.
+    excluded: HashSet<H160>,
+}
+
+impl Watchlist {
+    /// Fetches every configured vault, failing if any cannot be fetched.
+    pub async fn fetch(info_client: &InfoClient, config: Config) -> anyhow::Result<Self> {
+        let excluded: HashSet<H160> = match crate::tenants::var("EXCLUDED_ADDRESSES") {
+            Ok(value) => crate::parse_addresses(&value)?.into_iter().collect(),
+            Err(_) => HashSet::new(),
+        };
+        let mut vault_names = HashMap::new();
+        let mut child_vaults = HashMap::new();
+        for watched in &config.vaults {
+            let vault_details = vault::VaultApi::new(info_client)
+                .details(&format!("{:?}", watched.address))
+                .await?;
+            info!(
+                "Watching vault {}: {}",
+                vault_details.name, vault_details.description
+            );
+            if let Some(all_time) = vault_details.portfolio("allTime") {
+                info!(
+                    "Vault account value ${:.2}, all-time PnL ${:.2}, {} followers, leader {:?} holding ${:.2}",
+                    all_time.account_value().unwrap_or_default(),
+                    all_time.pnl().unwrap_or_default(),
+                    vault_details.followers.len(),
+                    vault_details.leader,
+                    vault_details.leader_equity()
+                );
+            }
+            for child in vault_details.child_addresses {
+                child_vaults.insert(child, watched.address);
+            }
+            let name = watched.label.clone().unwrap_or(vault_details.name);
+            vault_names.insert(watched.address, name);
+        }
+        Ok(Watchlist {
+            config,
+            vault_names,
+            child_vaults,
+            excluded,
+        })
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The first vault's name, which heads messages.
+    pub fn name(&self) -> String {
+        match self.config.vaults.first() {
+            Some(watched) => self.vault_name(watched.address).to_string(),
+            None => "God Watcher".to_string(),
+        }
+    }
+
+    pub fn vault_name(&self, vault: H160) -> &str {
+        self.vault_names.get(&vault).map_or("", String::as_str)
+    }
+
+    pub fn vaults(&self) -> Vec<H160> {
+        self.config
+            .vaults
+            .iter()
+            .map(|watched| watched.address)
+            .collect()
+    }
+
+    /// Every child address, listed address and cluster member that is not
+    /// excluded.
+    pub fn users(&self) -> Vec<H160> {
+        let mut users: Vec<H160> = self.child_vaults.keys().copied().collect();
+        let listed = self
+            .config
+            .addresses
+            .iter()
+            .map(|watched| watched.address)
+            .chain(self.config.clusters.members());
+        for user in listed {
+            if !users.contains(&user) {
+                users.push(user);
+            }
+        }
+        users.retain(|user| !self.excluded.contains(user));
+        users
+    }
+
+    pub fn is_excluded(&self, user: H160) -> bool {
+        self.excluded.contains(&user)
+    }
+
+    /// Whether the config file lists `user`, as an address or a cluster member.
+    pub fn is_listed(&self, user: H160) -> bool {
+        self.config
+            .addresses
+            .iter()
+            .any(|watched| watched.address == user)
+            || self.config.clusters.of(user).is_some()
+    }
+
+    pub fn vault_of(&self, child: H160) -> Option<H160> {
+        self.child_vaults.get(&child).copied()
+    }
+
+    /// The label an address's fills are prefixed with: its own, or the name of
+    /// its vault unless that is the first one.
+    pub fn label(&self, user: H160) -> Option<&str> {
+        let own = self
+            .config
+            .addresses
+            .iter()
+            .find(|watched| watched.address == user)
+            .and_then(|watched| watched.label.as_deref());
+        if own.is_some() {
+            return own;
+        }
+        let vault = self.vault_of(user)?;
+        if self.config.vaults.first()?.address == vault {
+            return None;
+        }
+        Some(self.vault_name(vault))
+    }
+
+    /// Where `user`'s fills go: its own destination, or its vault's.
+    pub fn destination(&self, user: H160) -> Destination {
+        let own = self
+            .config
+            .addresses
+            .iter()
+            .find(|watched| watched.address == user)
+            .map(|watched| &watched.destination)
+            .filter(|destination| **destination != Destination::default());
+        if let Some(own) = own {
+            return own.clone();
+        }
+        self.vault_of(user)
+            .and_then(|vault| {
+                self.config
+                    .vaults
+                    .iter()
+                    .find(|watched| watched.address == vault)
+            })
+            .map(|watched| watched.destination.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_children(&mut self, child_vaults: HashMap<H160, H160>) {
+        self.child_vaults = child_vaults;
+    }
+}
+
+/// What the watcher keeps per address, forgotten once an address stops being
+/// watched so it leaves the totals and alerts.
+#[derive(Clone)]
+pub struct Trackers {
+    pub positions: Arc<Mutex<PositionTracker>>,
+    pub fill_positions: Arc<Mutex<FillPositions>>,
+    pub velocity: Arc<Mutex<VelocityTracker>>,
+    pub sessions: Option<Arc<Mutex<SessionTracker>>>,
+}
+
+impl Trackers {
+    pub async fn forget(&self, user: H160) {
+        self.positions.lock().await.forget(user);
+        self.fill_positions.lock().await.forget(user);
+        self.velocity.lock().await.forget(user);
+        if let Some(sessions) = &self.sessions {
+            sessions.lock().await.forget(user);
+        }
+    }
+}
+
+/// Re-reads the config file and applies it to the running watcher.
+#[derive(Clone)]
+pub struct Reloader {
+    pub path: Option<PathBuf>,
+    pub watchlist: Arc<Mutex<Watchlist>>,
+    pub info_client: Arc<Mutex<InfoClient>>,
+    pub subscriptions: Arc<Mutex<SubscriptionManager>>,
+    pub trackers: Trackers,
+    pub alerter: Alerter,
+}
+
+impl Reloader {
+    /// Validates the new config by fetching everything it lists, then swaps it
+    /// in and resubscribes as a whole; an invalid config changes nothing.
+    /// Returns the changes.
+    pub async fn reload(&self) -> anyhow::Result<Vec<String>> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("the watcher was not started from a config file"))?;
+        let config = Config::load(path)?;
+        let changes = self.watchlist.lock().await.config().diff(&config);
+        if changes.is_empty() {
+            return Ok(changes);
+        }
+        let reloaded = Watchlist::fetch(&*self.info_client.lock().await, config).await?;
+
+        let mut removed_users = Vec::new();
+        let (connector, added_users) = {
+            let mut subscriptions = self.subscriptions.lock().await;
+            let mut watchlist = self.watchlist.lock().await;
+            let previous_users = watchlist.users();
+            let users = reloaded.users();
+            for user in &previous_users {
+                if !users.contains(user) {
+                    subscriptions.unsubscribe(*user);
+                    removed_users.push(*user);
+                }
+            }
+            let added_users: Vec<H160> = users
+                .into_iter()
+                .filter(|user| !previous_users.contains(user))
+                .collect();
+            *watchlist = reloaded;
+            (subscriptions.connector(), added_users)
+        };
+
+        // Connecting can take a while, so it happens without the locks.
+        let reconnection = connector.connect(added_users.clone()).await;
+        let failures = self
+            .subscriptions
+            .lock()
+            .await
+            .add(&added_users, reconnection);
+        for (user, err) in failures {
+            warn!("failed to subscribe {user:?}: {err:?}");
+        }
+        for user in removed_users {
+            self.trackers.forget(user).await;
+        }
+        Ok(changes)
+    }
+
+    /// Reloads and tells the ops webhook (or the alerter without it) what
+    /// changed or why the config was refused; returns the same text.
+    pub async fn reload_and_report(&self) -> String {
+        let content = match self.reload().await {
+            Ok(changes) if changes.is_empty() => "Config reloaded: no changes".to_string(),
+            Ok(changes) => format!("Config reloaded:\n{}", changes.join("\n")),
+            Err(err) => format!("Config reload refused, keeping the current config: {err:#}"),
+        };
+        self.alerter
+            .post_ops(Some(Priority::Normal), &content)
+            .await;
+        content
+    }
+}